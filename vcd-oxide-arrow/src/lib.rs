@@ -0,0 +1,212 @@
+use arrow_array::{RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+use std::{collections::HashMap, sync::Arc};
+use vcd_oxide_parser::ValueChangeDump;
+
+/// Options controlling which signals are exported and how value changes are batched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArrowOptions {
+    /// Glob patterns selecting the signals to export. Every signal is exported when empty.
+    pub signals: Vec<String>,
+    /// Maximum number of value changes per record batch.
+    pub batch_size: usize,
+}
+
+impl Default for ArrowOptions {
+    fn default() -> Self {
+        Self {
+            signals: vec![],
+            batch_size: 64 * 1024,
+        }
+    }
+}
+
+/// A dump as Arrow record batches: a dictionary of signals and a long table
+/// of value changes referring to it by id.
+///
+/// Aliased signals share an id, so each value change is stored once. Values
+/// are kept as written in the dump, e.g. `1`, `b1010` or `r0.5`.
+#[derive(Debug, Clone)]
+pub struct ArrowDump {
+    /// One row per signal: `id`, `reference`, `kind`, `size` and `identifier`
+    pub signals: RecordBatch,
+    /// `time`, `signal_id` and `value` columns, sorted by time then id
+    pub value_changes: Vec<RecordBatch>,
+}
+
+impl ArrowDump {
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &ArrowOptions) -> Result<Self, ArrowError> {
+        let signals: Vec<_> = vcd
+            .signals()
+            .into_iter()
+            .filter(|sig| {
+                options.signals.is_empty() || options.signals.iter().any(|p| sig.matches(p))
+            })
+            .collect();
+
+        // Ids are handed out in declaration order, once per identifier code
+        let mut ids: HashMap<&str, u32> = HashMap::new();
+        let mut waves = vec![];
+        let signal_ids: Vec<u32> = signals
+            .iter()
+            .map(|sig| {
+                *ids.entry(&sig.identifier).or_insert_with(|| {
+                    waves.push(vcd.wave(&sig.identifier));
+                    waves.len() as u32 - 1
+                })
+            })
+            .collect();
+
+        let signal_batch = RecordBatch::try_new(
+            Arc::new(signal_schema()),
+            vec![
+                Arc::new(UInt32Array::from(signal_ids)),
+                Arc::new(StringArray::from_iter_values(
+                    signals.iter().map(|sig| &sig.reference),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    signals.iter().map(|sig| &sig.kind),
+                )),
+                Arc::new(UInt32Array::from_iter_values(
+                    signals.iter().map(|sig| sig.size as u32),
+                )),
+                Arc::new(StringArray::from_iter_values(
+                    signals.iter().map(|sig| &sig.identifier),
+                )),
+            ],
+        )?;
+
+        let mut changes: Vec<(usize, u32, &str)> = waves
+            .iter()
+            .enumerate()
+            .flat_map(|(id, wave)| {
+                wave.value_changes
+                    .iter()
+                    .map(move |vc| (vc.time, id as u32, vc.value.as_str()))
+            })
+            .collect();
+        changes.sort_by_key(|(time, id, _)| (*time, *id));
+
+        let schema = Arc::new(value_change_schema());
+        let value_changes = changes
+            .chunks(options.batch_size.max(1))
+            .map(|chunk| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(UInt64Array::from_iter_values(
+                            chunk.iter().map(|(time, _, _)| *time as u64),
+                        )),
+                        Arc::new(UInt32Array::from_iter_values(
+                            chunk.iter().map(|(_, id, _)| *id),
+                        )),
+                        Arc::new(StringArray::from_iter_values(
+                            chunk.iter().map(|(_, _, value)| value),
+                        )),
+                    ],
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(ArrowDump {
+            signals: signal_batch,
+            value_changes,
+        })
+    }
+
+    /// Write the signal dictionary and the value changes as two Parquet files.
+    #[cfg(feature = "parquet")]
+    pub fn write_parquet(
+        &self,
+        signals: impl std::io::Write + Send,
+        value_changes: impl std::io::Write + Send,
+    ) -> Result<(), parquet::errors::ParquetError> {
+        use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer =
+            ArrowWriter::try_new(signals, self.signals.schema(), Some(properties.clone()))?;
+        writer.write(&self.signals)?;
+        writer.close()?;
+
+        let mut writer = ArrowWriter::try_new(
+            value_changes,
+            Arc::new(value_change_schema()),
+            Some(properties),
+        )?;
+        for batch in &self.value_changes {
+            writer.write(batch)?;
+        }
+        writer.close()?;
+        Ok(())
+    }
+}
+
+pub fn signal_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::UInt32, false),
+        Field::new("reference", DataType::Utf8, false),
+        Field::new("kind", DataType::Utf8, false),
+        Field::new("size", DataType::UInt32, false),
+        Field::new("identifier", DataType::Utf8, false),
+    ])
+}
+
+pub fn value_change_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("time", DataType::UInt64, false),
+        Field::new("signal_id", DataType::UInt32, false),
+        Field::new("value", DataType::Utf8, false),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use arrow_cast::pretty::pretty_format_batches;
+    use insta::assert_snapshot;
+
+    fn uart() -> ValueChangeDump {
+        ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/UartRxTest.vcd")).unwrap()
+    }
+
+    #[test]
+    fn test_uart_batches() {
+        let options = ArrowOptions {
+            signals: vec![
+                "*.clk".to_owned(),
+                "*.uart_rx".to_owned(),
+                "*.state".to_owned(),
+            ],
+            batch_size: 8,
+        };
+        let dump = ArrowDump::from_vcd(&uart(), &options).unwrap();
+        assert!(dump.value_changes.iter().all(|batch| batch.num_rows() <= 8));
+        assert_snapshot!(pretty_format_batches(&[dump.signals]).unwrap());
+        assert_snapshot!(pretty_format_batches(&dump.value_changes[..2]).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "parquet")]
+    fn test_parquet_round_trip() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let dump = ArrowDump::from_vcd(&uart(), &ArrowOptions::default()).unwrap();
+        let (mut signals, mut value_changes) = (vec![], vec![]);
+        dump.write_parquet(&mut signals, &mut value_changes)
+            .unwrap();
+
+        let read = |bytes: Vec<u8>| -> Vec<RecordBatch> {
+            ParquetRecordBatchReaderBuilder::try_new(bytes::Bytes::from(bytes))
+                .unwrap()
+                .build()
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        };
+        assert_eq!(read(signals), vec![dump.signals]);
+        assert_eq!(read(value_changes), dump.value_changes);
+    }
+}
@@ -0,0 +1,392 @@
+mod property;
+
+pub use crate::property::Property;
+
+use crate::property::PropertyKind;
+use serde::Serialize;
+use std::fmt;
+use vcd_oxide_parser::{ResolvedExpression, Samples, ValueChangeDump};
+
+/// Options for checking properties against a dump.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CheckOptions {
+    /// Failures kept per property. All of them are kept when `None`, and the
+    /// count is always complete.
+    pub max_failures: Option<usize>,
+}
+
+/// The outcome of every property, in the order of the properties file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckReport {
+    pub results: Vec<PropertyResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PropertyResult {
+    pub name: Option<String>,
+    pub property: String,
+    pub line: usize,
+    pub failure_count: usize,
+    pub failures: Vec<Failure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    pub time: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The properties file does not follow the property grammar
+    Syntax(String),
+    /// A delay range whose start is after its end, e.g. `##[4:1]`
+    InvalidDelay { line: usize, min: usize, max: usize },
+    /// A property with a delay, which counts clock cycles, has no clock
+    MissingClock { line: usize },
+    /// No signal matches a name used in a property
+    UnknownSignal { line: usize, name: String },
+    /// A bit or part select outside the declared range of a signal
+    InvalidSelect {
+        line: usize,
+        signal: String,
+        select: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(message) => write!(f, "syntax error\n{message}"),
+            Error::InvalidDelay { line, min, max } => {
+                write!(f, "line {line}: invalid delay ##[{min}:{max}]")
+            }
+            Error::MissingClock { line } => {
+                write!(f, "line {line}: a delay needs a clock, e.g. @(posedge clk)")
+            }
+            Error::UnknownSignal { line, name } => {
+                write!(f, "line {line}: no signal matches '{name}'")
+            }
+            Error::InvalidSelect {
+                line,
+                signal,
+                select,
+            } => write!(
+                f,
+                "line {line}: '{select}' is outside the declared range of '{signal}'"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// An error parsing or resolving an expression on `line`.
+    fn from_expression(error: vcd_oxide_parser::Error, line: usize) -> Self {
+        match error {
+            vcd_oxide_parser::Error::Syntax(message) => {
+                Error::Syntax(format!("line {line}\n{message}"))
+            }
+            vcd_oxide_parser::Error::UnknownSignal(name) => Error::UnknownSignal { line, name },
+            vcd_oxide_parser::Error::InvalidSelect { signal, select } => Error::InvalidSelect {
+                line,
+                signal,
+                select,
+            },
+            error => Error::Syntax(format!("line {line}: {error}")),
+        }
+    }
+}
+
+impl PropertyResult {
+    pub fn passed(&self) -> bool {
+        self.failure_count == 0
+    }
+}
+
+impl CheckReport {
+    /// Check each property over the whole dump.
+    ///
+    /// Clocked properties sample signals just before each edge of their clock,
+    /// as a register would. Properties without a clock are evaluated after every
+    /// change of the signals they use. Unknown values never satisfy a condition,
+    /// and implications still waiting for their consequent when the dump ends
+    /// don't fail.
+    pub fn from_vcd(
+        vcd: &ValueChangeDump,
+        properties: &[Property],
+        options: &CheckOptions,
+    ) -> Result<Self, Error> {
+        let results = properties
+            .iter()
+            .map(|property| {
+                let failures = Sampler::new(vcd, property)?.check();
+                Ok(PropertyResult {
+                    name: property.name.clone(),
+                    property: property.text.to_owned(),
+                    line: property.line,
+                    failure_count: failures.len(),
+                    failures: failures
+                        .into_iter()
+                        .take(options.max_failures.unwrap_or(usize::MAX))
+                        .collect(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(CheckReport { results })
+    }
+
+    pub fn passed(&self) -> bool {
+        self.results.iter().all(PropertyResult::passed)
+    }
+}
+
+/// A property with its expressions resolved against a dump, and its sample times.
+struct Sampler<'a> {
+    kind: PropertyKind<ResolvedExpression<'a>>,
+    samples: Samples,
+}
+
+impl<'a> Sampler<'a> {
+    fn new(vcd: &'a ValueChangeDump, property: &Property) -> Result<Self, Error> {
+        let line = property.line;
+        let kind = property.kind.try_map(|expr| {
+            expr.resolve(vcd)
+                .map_err(|error| Error::from_expression(error, line))
+        })?;
+        let times = match &property.clock {
+            Some(clock) => {
+                let sig = vcd
+                    .find_signal(&clock.signal)
+                    .ok_or_else(|| Error::UnknownSignal {
+                        line,
+                        name: clock.signal.to_owned(),
+                    })?;
+                vcd.wave(&sig.identifier).edges(clock.rising).collect()
+            }
+            None => {
+                let mut times: Vec<usize> = kind
+                    .expressions()
+                    .iter()
+                    .flat_map(|expr| expr.waves())
+                    .flat_map(|wave| wave.value_changes.iter().map(|vc| vc.time))
+                    .collect();
+                times.sort_unstable();
+                times.dedup();
+                times
+            }
+        };
+        Ok(Sampler {
+            kind,
+            samples: Samples {
+                times,
+                before: property.clock.is_some(),
+            },
+        })
+    }
+
+    fn check(&self) -> Vec<Failure> {
+        let failure = |sample: usize, message: String| Failure {
+            time: self.samples.times[sample],
+            message,
+        };
+        let eval = |expr: &ResolvedExpression, sample: usize| expr.eval(&self.samples, sample);
+        let holds = |expr: &ResolvedExpression, sample: usize| truthy(&eval(expr, sample));
+        let samples = 0..self.samples.times.len();
+        match &self.kind {
+            PropertyKind::Never(expr) => samples
+                .filter(|k| holds(expr, *k))
+                .map(|k| failure(k, "condition holds".to_owned()))
+                .collect(),
+            PropertyKind::Always(expr) => samples
+                .filter(|k| !holds(expr, *k))
+                .map(|k| failure(k, "condition doesn't hold".to_owned()))
+                .collect(),
+            PropertyKind::Stable { value, condition } => samples
+                .skip(1)
+                .filter(|k| holds(condition, k - 1))
+                .filter_map(|k| {
+                    let (before, after) = (eval(value, k - 1), eval(value, k));
+                    (!known(&before) || before != after).then(|| {
+                        let message = format!("changed from {} to {}", hex(&before), hex(&after));
+                        failure(k, message)
+                    })
+                })
+                .collect(),
+            PropertyKind::Implication {
+                antecedent,
+                min,
+                max,
+                consequent,
+            } => {
+                let seen: Vec<bool> = samples.clone().map(|k| holds(consequent, k)).collect();
+                let message = if min == max {
+                    format!("consequent doesn't hold {}", cycles_later(*min))
+                } else {
+                    format!("consequent not seen within {min} to {max} cycles")
+                };
+                samples
+                    .filter(|k| holds(antecedent, *k))
+                    // Attempts running past the end of the dump are unfinished
+                    .filter(|k| k.saturating_add(*max) < seen.len())
+                    .filter(|k| !seen[k + min..=k + max].iter().any(|s| *s))
+                    .map(|k| failure(k, message.to_owned()))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Whether all bits are `0` or `1`.
+fn known(bits: &str) -> bool {
+    bits.chars().all(|bit| matches!(bit, '0' | '1'))
+}
+
+/// Unknown values never satisfy a condition.
+fn truthy(bits: &str) -> bool {
+    known(bits) && bits.contains('1')
+}
+
+/// The value in hex, `x` if unknown or wider than 128 bits.
+fn hex(bits: &str) -> String {
+    match u128::from_str_radix(bits, 2) {
+        Ok(value) if known(bits) => format!("0x{value:x}"),
+        _ => "x".to_owned(),
+    }
+}
+
+fn cycles_later(cycles: usize) -> String {
+    match cycles {
+        0 => "in the same cycle".to_owned(),
+        1 => "1 cycle later".to_owned(),
+        _ => format!("{cycles} cycles later"),
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .results
+            .iter()
+            .map(|result| result.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for result in &self.results {
+            let status = if result.passed() { "PASS" } else { "FAIL" };
+            writeln!(
+                f,
+                "{}  {:>width$}  {}",
+                status, result.line, result.property
+            )?;
+            for failure in &result.failures {
+                writeln!(f, "  #{}  {}", failure.time, failure.message)?;
+            }
+            let hidden = result.failure_count - result.failures.len();
+            if hidden > 0 {
+                writeln!(f, "  ... {hidden} more")?;
+            }
+        }
+        let failed = self.results.iter().filter(|r| !r.passed()).count();
+        writeln!(f, "{} of {} properties failed", failed, self.results.len())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use vcd_oxide_parser::testing::clocked_dump;
+
+    fn check(vcd: &ValueChangeDump, properties: &str) -> CheckReport {
+        let properties = Property::parse_file(properties).unwrap();
+        CheckReport::from_vcd(vcd, &properties, &Default::default()).unwrap()
+    }
+
+    #[test]
+    fn test_handshake() {
+        // The first request is acknowledged 2 cycles later, the second never
+        let vcd = clocked_dump(&[
+            ("req", 1, &[0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0]),
+            ("ack", 1, &[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0]),
+            ("valid", 1, &[0, 1, 1, 1, 0, 1, 1, 0, 0, 0, 0, 0]),
+            ("ready", 1, &[0, 0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0]),
+            ("data", 8, &[0, 0xa, 0xa, 0xa, 0, 0xb, 0xc, 0, 0, 0, 0, 0]),
+        ]);
+        let report = check(
+            &vcd,
+            "// Handshakes of the request interface\n\
+             handshake: @(posedge clk) req |-> ##[1:4] ack\n\
+             @(posedge clk) req |=> ack\n\
+             \n\
+             @(posedge tb.clk) stable(data) while valid && !ready\n\
+             never (req && ack)\n",
+        );
+        assert!(!report.passed());
+        assert_snapshot!(report.to_string());
+    }
+
+    #[test]
+    fn test_expressions() {
+        let vcd = clocked_dump(&[
+            ("state", 2, &[0, 1, 2, 3, 0]),
+            ("count", 8, &[0, 3, 4, 5, 0xff]),
+        ]);
+        let report = check(
+            &vcd,
+            "@(posedge clk) rose(state == 3) |-> count == 5\n\
+             @(posedge clk) fell(state == 3) |-> past(count) == 'd5 && count == 8'hff\n\
+             @(negedge clk) always (state & 2'b10) == 0 || count >= 0x4\n\
+             @(posedge clk) stable(count) |-> state ^ 1 != 2\n\
+             never count > 4 && state != 3\n\
+             @(posedge clk) rose(state == 2) |-> count[1:0] + 1 == 2'd1 && count * 2 == 8\n",
+        );
+        let failures: Vec<usize> = report.results.iter().map(|r| r.failure_count).collect();
+        assert_eq!(failures, [0, 0, 0, 0, 1, 0]);
+        assert_eq!(report.results[4].failures[0].time, 40);
+    }
+
+    #[test]
+    fn test_counter_fixture() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../vcd-oxide-parser/test/Counter.vcd"
+        );
+        let vcd = ValueChangeDump::parse(&std::fs::read_to_string(path).unwrap()).unwrap();
+        // count is unknown until the first edge
+        let report = check(
+            &vcd,
+            "@(posedge Counter.clk) always count < 5\nnever count == 4\n",
+        );
+        let options = CheckOptions {
+            max_failures: Some(1),
+        };
+        let properties = Property::parse_file("@(posedge clk) always count == 0\n").unwrap();
+        let limited = CheckReport::from_vcd(&vcd, &properties, &options).unwrap();
+        assert_snapshot!(format!("{}{}", report, limited));
+    }
+
+    #[test]
+    fn test_errors() {
+        let vcd = clocked_dump(&[("req", 1, &[0, 1])]);
+        let errors = [
+            "req |-> ##1 ack",
+            "@(posedge clk) req |-> ##[3:1] req",
+            "@(posedge clk) req |=> ##[1:99999999999999999999999] req",
+            "@(posedge clk) req |=> ##18446744073709551615 req",
+            "never (req &&)",
+            "@(posedge clk) req |-> missing",
+            "@(posedge clk) always req[1]",
+        ]
+        .map(|properties| {
+            Property::parse_file(properties)
+                .and_then(|properties| {
+                    CheckReport::from_vcd(&vcd, &properties, &Default::default())
+                })
+                .unwrap_err()
+                .to_string()
+        });
+        assert_snapshot!(errors.join("\n"));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::Error;
+use pest::{iterators::Pair, Parser};
+use pest_derive::Parser;
+use vcd_oxide_parser::Expression;
+
+#[derive(Parser)]
+#[grammar = "grammar/property.pest"]
+struct PropertyParser;
+
+/// A property read from a properties file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    pub name: Option<String>,
+    /// The property as written, without comments
+    pub text: String,
+    /// Line of the properties file, starting at 1
+    pub line: usize,
+    pub(crate) clock: Option<Clock>,
+    pub(crate) kind: PropertyKind<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Clock {
+    pub(crate) signal: String,
+    pub(crate) rising: bool,
+}
+
+/// A property's kind, over parsed or resolved expressions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PropertyKind<E> {
+    Never(E),
+    Always(E),
+    /// `value` may not change on a sample following one where `condition` holds
+    Stable {
+        value: E,
+        condition: E,
+    },
+    /// `consequent` holds within `min` to `max` samples of each sample where `antecedent` holds
+    Implication {
+        antecedent: E,
+        min: usize,
+        max: usize,
+        consequent: E,
+    },
+}
+
+impl Property {
+    /// Parse a properties file with one property per line.
+    pub fn parse_file(input: &str) -> Result<Vec<Property>, Error> {
+        let pairs =
+            PropertyParser::parse(Rule::file, input).map_err(|e| Error::Syntax(e.to_string()))?;
+        pairs
+            .filter(|pair| pair.as_rule() == Rule::property)
+            .map(visit_property)
+            .collect()
+    }
+}
+
+fn visit_property(rule: Pair<Rule>) -> Result<Property, Error> {
+    let line = rule.line_col().0;
+    let text = rule.as_str().trim().to_owned();
+    let mut name = None;
+    let mut clock = None;
+    let mut kind = None;
+    for item in rule.into_inner() {
+        match item.as_rule() {
+            Rule::name => name = Some(item.as_str().to_owned()),
+            Rule::clock => clock = Some(visit_clock(item)),
+            Rule::never => {
+                kind = Some(PropertyKind::Never(visit_expression(last(item), line)?));
+            }
+            Rule::always => {
+                kind = Some(PropertyKind::Always(visit_expression(last(item), line)?));
+            }
+            Rule::stable_while => {
+                let mut inner = item.into_inner();
+                let value = visit_expression(inner.next().unwrap(), line)?;
+                let condition = visit_expression(inner.last().unwrap(), line)?;
+                kind = Some(PropertyKind::Stable { value, condition });
+            }
+            Rule::implication => {
+                let mut inner = item.into_inner();
+                let antecedent = visit_expression(inner.next().unwrap(), line)?;
+                let mut delay: (usize, usize) = match inner.next().unwrap().as_str() {
+                    "|=>" => (1, 1),
+                    _ => (0, 0),
+                };
+                let mut next = inner.next().unwrap();
+                if next.as_rule() == Rule::delay {
+                    let too_large = || Error::Syntax(format!("line {line}: delay too large"));
+                    let numbers = next
+                        .into_inner()
+                        .map(|number| number.as_str().parse().map_err(|_| too_large()))
+                        .collect::<Result<Vec<usize>, _>>()?;
+                    let (min, max) = (numbers[0], *numbers.last().unwrap());
+                    if min > max {
+                        return Err(Error::InvalidDelay { line, min, max });
+                    }
+                    delay = delay
+                        .0
+                        .checked_add(min)
+                        .zip(delay.1.checked_add(max))
+                        .ok_or_else(too_large)?;
+                    next = inner.next().unwrap();
+                }
+                if clock.is_none() && delay != (0, 0) {
+                    return Err(Error::MissingClock { line });
+                }
+                kind = Some(PropertyKind::Implication {
+                    antecedent,
+                    min: delay.0,
+                    max: delay.1,
+                    consequent: visit_expression(next, line)?,
+                });
+            }
+            _ => unreachable!("{:#?}", item),
+        }
+    }
+    Ok(Property {
+        name,
+        text,
+        line,
+        clock,
+        kind: kind.unwrap(),
+    })
+}
+
+fn last(rule: Pair<Rule>) -> Pair<Rule> {
+    rule.into_inner().last().unwrap()
+}
+
+fn visit_clock(rule: Pair<Rule>) -> Clock {
+    let mut inner = rule.into_inner();
+    let rising = inner.next().unwrap().as_str() == "posedge";
+    Clock {
+        signal: inner.next().unwrap().as_str().to_owned(),
+        rising,
+    }
+}
+
+fn visit_expression(rule: Pair<Rule>, line: usize) -> Result<Expression, Error> {
+    Expression::parse(rule.as_str().trim()).map_err(|error| Error::from_expression(error, line))
+}
+
+impl<E> PropertyKind<E> {
+    pub(crate) fn try_map<F>(
+        &self,
+        mut f: impl FnMut(&E) -> Result<F, Error>,
+    ) -> Result<PropertyKind<F>, Error> {
+        Ok(match self {
+            PropertyKind::Never(expr) => PropertyKind::Never(f(expr)?),
+            PropertyKind::Always(expr) => PropertyKind::Always(f(expr)?),
+            PropertyKind::Stable { value, condition } => PropertyKind::Stable {
+                value: f(value)?,
+                condition: f(condition)?,
+            },
+            PropertyKind::Implication {
+                antecedent,
+                min,
+                max,
+                consequent,
+            } => PropertyKind::Implication {
+                antecedent: f(antecedent)?,
+                min: *min,
+                max: *max,
+                consequent: f(consequent)?,
+            },
+        })
+    }
+
+    pub(crate) fn expressions(&self) -> Vec<&E> {
+        match self {
+            PropertyKind::Never(expr) | PropertyKind::Always(expr) => vec![expr],
+            PropertyKind::Stable { value, condition } => vec![value, condition],
+            PropertyKind::Implication {
+                antecedent,
+                consequent,
+                ..
+            } => vec![antecedent, consequent],
+        }
+    }
+}
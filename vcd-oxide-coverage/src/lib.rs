@@ -0,0 +1,422 @@
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt, fmt::Write};
+use vcd_oxide_parser::{extend_bits, ValueChangeDump, ValueChangeDumpSignal};
+
+/// Options selecting the signals whose bits are measured.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CoverageOptions {
+    /// Glob patterns selecting the signals. Every signal is measured when empty.
+    pub signals: Vec<String>,
+    /// Leave out `parameter` vars, which are constant and never toggle.
+    pub exclude_parameters: bool,
+}
+
+/// Toggle coverage of every bit of the selected signals, with roll-ups per scope.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ToggleCoverage {
+    pub bits: usize,
+    /// Bits that both rose and fell
+    pub toggled: usize,
+    pub percent: f64,
+    /// Every scope holding a selected signal, counting the bits of its sub-scopes
+    pub scopes: Vec<ScopeCoverage>,
+    pub signals: Vec<SignalCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ScopeCoverage {
+    pub scope: String,
+    pub bits: usize,
+    pub toggled: usize,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SignalCoverage {
+    pub reference: String,
+    pub kind: String,
+    /// Bits in declaration order, e.g. `count[3]` down to `count[0]` for `count [3:0]`
+    pub bits: Vec<BitCoverage>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct BitCoverage {
+    /// Hierarchical name of the bit, e.g. `Counter.count[3]`
+    pub name: String,
+    /// Transitions from 0 to 1
+    pub rises: usize,
+    /// Transitions from 1 to 0
+    pub falls: usize,
+}
+
+impl BitCoverage {
+    pub fn is_toggled(&self) -> bool {
+        self.rises > 0 && self.falls > 0
+    }
+}
+
+impl SignalCoverage {
+    pub fn toggled(&self) -> usize {
+        self.bits.iter().filter(|bit| bit.is_toggled()).count()
+    }
+}
+
+impl ToggleCoverage {
+    /// Count the transitions of each bit of the selected signals. Only direct
+    /// transitions between 0 and 1 count, so `0 -> x -> 1` is not a rise.
+    /// Real and event vars have no bits to toggle and are skipped.
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &CoverageOptions) -> Self {
+        let signals: Vec<SignalCoverage> = vcd
+            .signals()
+            .iter()
+            .filter(|sig| {
+                options.signals.is_empty()
+                    || options.signals.iter().any(|pattern| sig.matches(pattern))
+            })
+            .filter(|sig| !matches!(sig.kind.as_str(), "real" | "realtime" | "event"))
+            .filter(|sig| !(options.exclude_parameters && sig.kind == "parameter"))
+            .map(|sig| signal_coverage(vcd, sig))
+            .collect();
+
+        let mut scopes: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+        for signal in &signals {
+            let scope = signal
+                .reference
+                .rsplit_once('.')
+                .map_or("", |(scope, _)| scope);
+            // Each enclosing scope rolls up the bits of the signal
+            let ends = scope
+                .match_indices('.')
+                .map(|(index, _)| index)
+                .chain([scope.len()]);
+            for end in ends.filter(|end| *end > 0) {
+                let counts = scopes.entry(&scope[..end]).or_default();
+                counts.0 += signal.bits.len();
+                counts.1 += signal.toggled();
+            }
+        }
+        let scopes = scopes
+            .into_iter()
+            .map(|(scope, (bits, toggled))| ScopeCoverage {
+                scope: scope.to_owned(),
+                bits,
+                toggled,
+                percent: percent(toggled, bits),
+            })
+            .collect();
+
+        let bits = signals.iter().map(|signal| signal.bits.len()).sum();
+        let toggled = signals.iter().map(SignalCoverage::toggled).sum();
+        ToggleCoverage {
+            bits,
+            toggled,
+            percent: percent(toggled, bits),
+            scopes,
+            signals,
+        }
+    }
+
+    /// Bits that didn't both rise and fall, in declaration order.
+    pub fn untoggled(&self) -> impl Iterator<Item = &BitCoverage> {
+        self.signals
+            .iter()
+            .flat_map(|signal| &signal.bits)
+            .filter(|bit| !bit.is_toggled())
+    }
+
+    /// A standalone HTML page with the scope roll-ups and every signal's bits.
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+        html += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n";
+        html += "<title>Toggle coverage</title>\n<style>\n";
+        html += "body { font-family: sans-serif; }\n";
+        html += "table { border-collapse: collapse; margin-bottom: 2em; }\n";
+        html += "th, td { border: 1px solid #ccc; padding: 2px 8px; text-align: left; }\n";
+        html +=
+            ".bit { display: inline-block; min-width: 1.5em; margin: 1px; text-align: center; }\n";
+        html += ".toggled { background: #b7e4b0; }\n.partial { background: #fbe3a1; }\n";
+        html += ".untoggled { background: #f4b6b6; }\n</style>\n</head>\n<body>\n";
+        let _ = writeln!(
+            html,
+            "<h1>Toggle coverage: {}/{} bits ({:.1}%)</h1>",
+            self.toggled, self.bits, self.percent
+        );
+
+        html += "<h2>Scopes</h2>\n<table>\n";
+        html += "<tr><th>scope</th><th>toggled</th><th>bits</th><th>%</th></tr>\n";
+        for scope in &self.scopes {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}</td></tr>",
+                escape(&scope.scope),
+                scope.toggled,
+                scope.bits,
+                scope.percent
+            );
+        }
+        html += "</table>\n";
+
+        html += "<h2>Signals</h2>\n<table>\n";
+        html += "<tr><th>signal</th><th>kind</th><th>toggled</th><th>bits</th></tr>\n";
+        for signal in &self.signals {
+            let _ = write!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}/{}</td><td>",
+                escape(&signal.reference),
+                escape(&signal.kind),
+                signal.toggled(),
+                signal.bits.len()
+            );
+            for bit in &signal.bits {
+                let class = if bit.is_toggled() {
+                    "toggled"
+                } else if bit.rises + bit.falls > 0 {
+                    "partial"
+                } else {
+                    "untoggled"
+                };
+                let index = bit.name.rsplit_once('[').map_or("", |(_, index)| index);
+                let _ = write!(
+                    html,
+                    "<span class=\"bit {}\" title=\"{}: {} rises, {} falls\">{}</span>",
+                    class,
+                    escape(&bit.name),
+                    bit.rises,
+                    bit.falls,
+                    escape(index.trim_end_matches(']'))
+                );
+            }
+            html += "</td></tr>\n";
+        }
+        html += "</table>\n</body>\n</html>\n";
+        html
+    }
+}
+
+fn signal_coverage(vcd: &ValueChangeDump, sig: &ValueChangeDumpSignal) -> SignalCoverage {
+    let mut bits: Vec<BitCoverage> = bit_names(sig)
+        .into_iter()
+        .map(|name| BitCoverage {
+            name,
+            rises: 0,
+            falls: 0,
+        })
+        .collect();
+    let mut previous: Option<String> = None;
+    for vc in &vcd.wave(&sig.identifier).value_changes {
+        let value = extend_bits(&vc.value, sig.size);
+        if let Some(previous) = &previous {
+            for ((before, after), bit) in previous.chars().zip(value.chars()).zip(&mut bits) {
+                match (before, after) {
+                    ('0', '1') => bit.rises += 1,
+                    ('1', '0') => bit.falls += 1,
+                    _ => {}
+                }
+            }
+        }
+        previous = Some(value);
+    }
+    SignalCoverage {
+        reference: sig.reference.to_owned(),
+        kind: sig.kind.to_owned(),
+        bits,
+    }
+}
+
+/// Names of the bits of a signal from the most significant one, following
+/// its declared range, e.g. `[0:3]` gives `name[0]` to `name[3]`. Signals
+/// declared without a matching range are numbered down from `size - 1`.
+fn bit_names(sig: &ValueChangeDumpSignal) -> Vec<String> {
+    let (name, range) = sig
+        .reference
+        .split_once(" [")
+        .map_or((sig.reference.as_str(), None), |(name, range)| {
+            (name, range.strip_suffix(']'))
+        });
+    let bounds = range.and_then(|range| match range.split_once(':') {
+        Some((left, right)) => Some((left.trim().parse().ok()?, right.trim().parse().ok()?)),
+        None => range.trim().parse().ok().map(|index: i64| (index, index)),
+    });
+    let indices: Vec<i64> = match bounds {
+        Some((left, right)) if left.abs_diff(right) as usize + 1 == sig.size => {
+            if left >= right {
+                (right..=left).rev().collect()
+            } else {
+                (left..=right).collect()
+            }
+        }
+        _ if sig.size == 1 && range.is_none() => return vec![name.to_owned()],
+        _ => (0..sig.size as i64).rev().collect(),
+    };
+    indices
+        .into_iter()
+        .map(|index| format!("{name}[{index}]"))
+        .collect()
+}
+
+fn percent(toggled: usize, bits: usize) -> f64 {
+    if bits == 0 {
+        100.0
+    } else {
+        toggled as f64 * 100.0 / bits as f64
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl fmt::Display for ToggleCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "toggle coverage: {}/{} bits ({:.1}%)",
+            self.toggled, self.bits, self.percent
+        )?;
+
+        if !self.scopes.is_empty() {
+            writeln!(f, "\nscopes:")?;
+            let width = self.scopes.iter().map(|s| s.scope.len()).max().unwrap_or(0);
+            let counts: Vec<String> = self
+                .scopes
+                .iter()
+                .map(|s| format!("{}/{}", s.toggled, s.bits))
+                .collect();
+            let counts_width = counts.iter().map(String::len).max().unwrap_or(0);
+            for (scope, counts) in self.scopes.iter().zip(&counts) {
+                writeln!(
+                    f,
+                    "  {:<width$}  {:>counts_width$}  {:5.1}%",
+                    scope.scope, counts, scope.percent
+                )?;
+            }
+        }
+
+        let untoggled: Vec<&BitCoverage> = self.untoggled().collect();
+        if !untoggled.is_empty() {
+            writeln!(f, "\nuntoggled bits:")?;
+            let width = untoggled
+                .iter()
+                .map(|bit| bit.name.len())
+                .max()
+                .unwrap_or(0);
+            for bit in untoggled {
+                let missing = match (bit.rises, bit.falls) {
+                    (0, 0) => "never rises or falls",
+                    (0, _) => "never rises",
+                    _ => "never falls",
+                };
+                writeln!(f, "  {:<width$}  {}", bit.name, missing)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::{assert_json_snapshot, assert_snapshot};
+
+    fn fixture(name: &str) -> ValueChangeDump {
+        let path = format!(
+            "{}/../vcd-oxide-parser/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        ValueChangeDump::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_counter() {
+        // count counts up to 5, so bit 2 only rises and bit 3 never moves
+        let coverage = ToggleCoverage::from_vcd(&fixture("Counter.vcd"), &Default::default());
+        assert_snapshot!(coverage.to_string());
+        assert_json_snapshot!(coverage.signals[0]);
+    }
+
+    #[test]
+    fn test_bit_ranges() {
+        let vcd = ValueChangeDump::parse(
+            "$scope module top $end\n\
+             $var wire 2 ! up [0:1] $end\n\
+             $var wire 2 \" shifted [5:4] $end\n\
+             $var wire 1 # sel [7] $end\n\
+             $var wire 3 $ plain $end\n\
+             $upscope $end\n$enddefinitions $end\n\
+             #0\nb01 !\nb00 \"\n0#\nb000 $\n\
+             #1\nb10 !\nb10 \"\n1#\nbx01 $\n\
+             #2\nb101 $\n",
+        )
+        .unwrap();
+        let coverage = ToggleCoverage::from_vcd(&vcd, &Default::default());
+        let names: Vec<&str> = coverage
+            .signals
+            .iter()
+            .flat_map(|signal| &signal.bits)
+            .map(|bit| bit.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "top.up[0]",
+                "top.up[1]",
+                "top.shifted[5]",
+                "top.shifted[4]",
+                "top.sel[7]",
+                "top.plain[2]",
+                "top.plain[1]",
+                "top.plain[0]"
+            ]
+        );
+        // up[0] rose, shifted[5] rose, plain[2] went through x so never rose
+        let rises: Vec<usize> = coverage
+            .signals
+            .iter()
+            .flat_map(|signal| &signal.bits)
+            .map(|bit| bit.rises)
+            .collect();
+        assert_eq!(rises, [1, 0, 1, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_parameters() {
+        let vcd = fixture("NextCoreTest.vcd");
+        let all = ToggleCoverage::from_vcd(&vcd, &Default::default());
+        let options = CoverageOptions {
+            exclude_parameters: true,
+            ..Default::default()
+        };
+        let without = ToggleCoverage::from_vcd(&vcd, &options);
+        assert!(all.signals.iter().any(|signal| signal.kind == "parameter"));
+        assert!(without
+            .signals
+            .iter()
+            .all(|signal| signal.kind != "parameter"));
+        assert!(without.bits < all.bits);
+        assert_eq!(without.toggled, all.toggled);
+        assert_snapshot!(without
+            .scopes
+            .iter()
+            .map(|s| format!("{} {}/{}", s.scope, s.toggled, s.bits))
+            .collect::<Vec<_>>()
+            .join("\n"));
+    }
+
+    #[test]
+    fn test_html() {
+        let options = CoverageOptions {
+            signals: vec!["*.count".to_owned()],
+            ..Default::default()
+        };
+        let coverage = ToggleCoverage::from_vcd(&fixture("Counter.vcd"), &options);
+        assert_snapshot!(coverage.to_html());
+    }
+}
@@ -0,0 +1,344 @@
+use std::{fmt, str::FromStr};
+use vcd_oxide_parser::{Radix, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave};
+
+/// Options controlling how signal values are exported as a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    /// Glob patterns selecting the signals to export. Every signal is exported when empty.
+    pub signals: Vec<String>,
+    pub layout: Layout,
+    /// First simulation time to export.
+    pub start: Option<usize>,
+    /// Simulation time at which output stops (exclusive).
+    pub end: Option<usize>,
+    /// Time units between rows of a sampled table. Defaults to every time unit.
+    pub period: Option<usize>,
+    /// Glob pattern of a clock signal. When set, a sampled table has one row per
+    /// clock edge instead of one per period.
+    pub clock: Option<String>,
+    pub edge: Edge,
+    /// Radix used for bus values.
+    pub radix: Radix,
+    pub delimiter: Delimiter,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            signals: vec![],
+            layout: Layout::Events,
+            start: None,
+            end: None,
+            period: None,
+            clock: None,
+            edge: Edge::Rising,
+            radix: Radix::Hex,
+            delimiter: Delimiter::Comma,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// One row per value change with `time`, `signal` and `value` columns
+    #[default]
+    Events,
+    /// One row per sample time with a column per signal
+    Sampled,
+}
+
+/// Clock edges that produce a row in a sampled table.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    #[default]
+    Rising,
+    Falling,
+    Both,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    #[default]
+    Comma,
+    Tab,
+}
+
+impl Delimiter {
+    fn as_char(&self) -> char {
+        match self {
+            Delimiter::Comma => ',',
+            Delimiter::Tab => '\t',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No signal matches the clock pattern
+    UnknownClock(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownClock(pattern) => write!(f, "no clock signal matches '{pattern}'"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Signal values laid out as rows of delimited text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvTable {
+    delimiter: Delimiter,
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// A table of arbitrary rows, e.g. decoded annotations.
+    pub fn new(header: Vec<String>, rows: Vec<Vec<String>>, delimiter: Delimiter) -> Self {
+        CsvTable {
+            delimiter,
+            header,
+            rows,
+        }
+    }
+
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &CsvOptions) -> Result<Self, Error> {
+        let start = options.start.unwrap_or(0);
+        let end = options.end.unwrap_or_else(|| vcd.end_time() + 1).max(start);
+        let signals: Vec<ValueChangeDumpSignal> = vcd
+            .signals()
+            .into_iter()
+            .filter(|sig| {
+                options.signals.is_empty() || options.signals.iter().any(|p| sig.matches(p))
+            })
+            .collect();
+
+        let (header, rows) = match options.layout {
+            Layout::Events => {
+                let header = ["time", "signal", "value"].map(str::to_owned).to_vec();
+                let mut rows: Vec<(usize, usize, Vec<String>)> = vec![];
+                for (index, sig) in signals.iter().enumerate() {
+                    let wave = vcd.wave(&sig.identifier);
+                    for value_change in &wave.value_changes {
+                        if (start..end).contains(&value_change.time) {
+                            let row = vec![
+                                value_change.time.to_string(),
+                                sig.reference.to_owned(),
+                                format_value(sig, &value_change.value, options.radix),
+                            ];
+                            rows.push((value_change.time, index, row));
+                        }
+                    }
+                }
+                // Changes at the same time stay in declaration order
+                rows.sort_by_key(|(time, index, _)| (*time, *index));
+                (header, rows.into_iter().map(|(_, _, row)| row).collect())
+            }
+            Layout::Sampled => {
+                let (samples, before_edge) = match &options.clock {
+                    Some(pattern) => {
+                        let clock = vcd
+                            .signals()
+                            .into_iter()
+                            .find(|sig| sig.matches(pattern))
+                            .ok_or_else(|| Error::UnknownClock(pattern.to_owned()))?;
+                        let wave = vcd.wave(&clock.identifier);
+                        (clock_edges(wave, options.edge, start, end), true)
+                    }
+                    None => {
+                        let period = options.period.unwrap_or(1).max(1);
+                        ((start..end).step_by(period).collect(), false)
+                    }
+                };
+                let header = ["time".to_owned()]
+                    .into_iter()
+                    .chain(signals.iter().map(|sig| sig.reference.to_owned()))
+                    .collect();
+                let rows = samples
+                    .iter()
+                    .map(|time| {
+                        let values = signals.iter().map(|sig| {
+                            let wave = vcd.wave(&sig.identifier);
+                            // Registers see the value from just before the clock edge
+                            let value_change = if before_edge {
+                                wave.previous_change(*time)
+                            } else {
+                                wave.value_at(*time)
+                            };
+                            value_change.map_or("x".to_owned(), |vc| {
+                                format_value(sig, &vc.value, options.radix)
+                            })
+                        });
+                        [time.to_string()].into_iter().chain(values).collect()
+                    })
+                    .collect();
+                (header, rows)
+            }
+        };
+
+        Ok(CsvTable {
+            delimiter: options.delimiter,
+            header,
+            rows,
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::new();
+        for row in [&self.header].into_iter().chain(&self.rows) {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|field| quote(field, self.delimiter))
+                .collect();
+            csv += &fields.join(&self.delimiter.as_char().to_string());
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+impl fmt::Display for CsvTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_csv())
+    }
+}
+
+/// Times in `start..end` at which the clock has the given edge. The first
+/// value of the clock is not an edge.
+fn clock_edges(wave: &ValueChangeDumpWave, edge: Edge, start: usize, end: usize) -> Vec<usize> {
+    let mut edges: Vec<usize> = match edge {
+        Edge::Rising => wave.edges(true).collect(),
+        Edge::Falling => wave.edges(false).collect(),
+        Edge::Both => wave.edges(true).chain(wave.edges(false)).collect(),
+    };
+    edges.sort_unstable();
+    edges.retain(|time| (start..end).contains(time));
+    edges
+}
+
+/// Scalars are exported as is, busses and reals in the given radix without a prefix
+/// so spreadsheets can parse decimal values as numbers.
+fn format_value(sig: &ValueChangeDumpSignal, value: &str, radix: Radix) -> String {
+    if sig.size > 1 || value.starts_with('r') || value.starts_with('R') {
+        radix.format(value, sig.size)
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Quote a field containing the delimiter, quotes or line breaks as described in RFC 4180.
+fn quote(field: &str, delimiter: Delimiter) -> String {
+    if field.contains([delimiter.as_char(), '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "events" => Ok(Layout::Events),
+            "sampled" => Ok(Layout::Sampled),
+            _ => Err(format!("unknown table layout '{s}'")),
+        }
+    }
+}
+
+impl FromStr for Edge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rising" => Ok(Edge::Rising),
+            "falling" => Ok(Edge::Falling),
+            "both" => Ok(Edge::Both),
+            _ => Err(format!("unknown clock edge '{s}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use vcd_oxide_parser::ParseOptions;
+
+    fn counter() -> ValueChangeDump {
+        ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/Counter.vcd")).unwrap()
+    }
+
+    #[test]
+    fn test_counter_events() {
+        let options = CsvOptions {
+            radix: Radix::Decimal,
+            ..Default::default()
+        };
+        assert_snapshot!(CsvTable::from_vcd(&counter(), &options).unwrap().to_csv())
+    }
+
+    #[test]
+    fn test_counter_sampled_on_clock() {
+        let options = CsvOptions {
+            signals: vec!["*.count".to_owned()],
+            layout: Layout::Sampled,
+            clock: Some("*.clk".to_owned()),
+            radix: Radix::Binary,
+            delimiter: Delimiter::Tab,
+            ..Default::default()
+        };
+        assert_snapshot!(CsvTable::from_vcd(&counter(), &options).unwrap().to_csv())
+    }
+
+    #[test]
+    fn test_filtered_time_axis() {
+        let input = include_str!("../../vcd-oxide-parser/test/UartRxTest.vcd");
+        let parse_options = ParseOptions {
+            signals: vec!["*.rst".to_owned()],
+            ..Default::default()
+        };
+        let options = CsvOptions {
+            signals: parse_options.signals.clone(),
+            layout: Layout::Sampled,
+            period: Some(1),
+            ..Default::default()
+        };
+        let filtered = ValueChangeDump::parse_with(input, &parse_options).unwrap();
+        let all = ValueChangeDump::parse(input).unwrap();
+        let table = CsvTable::from_vcd(&filtered, &options).unwrap();
+        assert_eq!(table, CsvTable::from_vcd(&all, &options).unwrap());
+        assert_eq!(table.rows.last().unwrap()[0], "6");
+    }
+
+    #[test]
+    fn test_sampled_period_and_unknown_clock() {
+        let options = CsvOptions {
+            layout: Layout::Sampled,
+            start: Some(1),
+            period: Some(2),
+            ..Default::default()
+        };
+        let table = CsvTable::from_vcd(&counter(), &options).unwrap();
+        assert_eq!(
+            table.to_csv(),
+            "time,Counter.count [3:0],Counter.clk\n1,0,1\n3,2,1\n5,4,1\n"
+        );
+
+        let options = CsvOptions {
+            layout: Layout::Sampled,
+            clock: Some("*.missing".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CsvTable::from_vcd(&counter(), &options),
+            Err(Error::UnknownClock("*.missing".to_owned()))
+        );
+        assert_eq!(quote("a,\"b\"", Delimiter::Comma), "\"a,\"\"b\"\"\"");
+    }
+}
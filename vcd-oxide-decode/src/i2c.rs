@@ -0,0 +1,90 @@
+use crate::{hex, word, Annotation, Decoder};
+use std::mem;
+use vcd_oxide_parser::ValueChangeDumpWave;
+
+/// Inter-integrated circuit bus with 7-bit addresses.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct I2c {
+    pub scl: String,
+    pub sda: String,
+}
+
+impl Decoder for I2c {
+    fn signals(&self) -> Vec<&str> {
+        vec![&self.scl, &self.sda]
+    }
+
+    fn decode(&self, waves: &[&ValueChangeDumpWave], _end: usize) -> Vec<Annotation> {
+        let (scl, sda) = (waves[0], waves[1]);
+        let mut times: Vec<usize> = scl
+            .value_changes
+            .iter()
+            .chain(&sda.value_changes)
+            .map(|vc| vc.time)
+            .collect();
+        times.sort_unstable();
+        times.dedup();
+
+        let mut annotations = vec![];
+        // Bits of the current byte with the time of the first one, `None` outside a transfer
+        let mut byte: Option<(usize, Vec<Option<bool>>)> = None;
+        let mut first_byte = false;
+        let mut previous = (None, None);
+        for time in times {
+            let current = (scl.level_at(time), sda.level_at(time));
+            match (mem::replace(&mut previous, current), current) {
+                // SDA falls while SCL is high
+                ((Some(true), Some(true)), (Some(true), Some(false))) => {
+                    let kind = if byte.is_some() {
+                        "repeated-start"
+                    } else {
+                        "start"
+                    };
+                    annotations.push(Annotation::new(time, time, "i2c", kind, kind));
+                    byte = Some((time, vec![]));
+                    first_byte = true;
+                }
+                // SDA rises while SCL is high
+                ((Some(true), Some(false)), (Some(true), Some(true))) if byte.is_some() => {
+                    annotations.push(Annotation::new(time, time, "i2c", "stop", "stop"));
+                    byte = None;
+                }
+                // Data is read on the rising edge of SCL
+                ((Some(false), _), (Some(true), bit)) => {
+                    let Some((start, bits)) = &mut byte else {
+                        continue;
+                    };
+                    if bits.is_empty() {
+                        *start = time;
+                    }
+                    bits.push(bit);
+                    if bits.len() == 8 {
+                        let value = word(bits, false);
+                        let (kind, text) = match value {
+                            Some(value) if first_byte => (
+                                "address",
+                                format!(
+                                    "0x{:02x} {}",
+                                    value >> 1,
+                                    if value & 1 == 1 { "read" } else { "write" }
+                                ),
+                            ),
+                            _ if first_byte => ("address", hex(value, 8)),
+                            _ => ("data", hex(value, 8)),
+                        };
+                        annotations.push(Annotation::new(*start, time, "i2c", kind, text));
+                        first_byte = false;
+                    } else if bits.len() == 9 {
+                        // The acknowledge bit lasts until SCL falls again
+                        let end = scl.next_change(time).map_or(time, |vc| vc.time);
+                        let kind = if bit == Some(false) { "ack" } else { "nack" };
+                        annotations.push(Annotation::new(time, end, "i2c", kind, kind));
+                        bits.clear();
+                    }
+                }
+                _ => {}
+            }
+        }
+        annotations
+    }
+}
@@ -0,0 +1,351 @@
+mod i2c;
+mod spi;
+mod uart;
+
+pub use crate::i2c::*;
+pub use crate::spi::*;
+pub use crate::uart::*;
+
+use serde::Serialize;
+use std::fmt;
+use vcd_oxide_csv::{CsvTable, Delimiter};
+use vcd_oxide_parser::{ValueChangeDump, ValueChangeDumpWave};
+use vcd_oxide_wavejson::DataLane;
+
+/// A decoded event, e.g. a byte received on a serial line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Annotation {
+    pub start: usize,
+    pub end: usize,
+    /// Lane the annotation is drawn on, e.g. `uart` or `mosi`
+    pub lane: String,
+    /// What the annotation marks, e.g. `data`, `start` or `parity-error`
+    pub kind: String,
+    /// Decoded value, e.g. `0x55`
+    pub text: String,
+}
+
+impl Annotation {
+    fn new(start: usize, end: usize, lane: &str, kind: &str, text: impl Into<String>) -> Self {
+        Annotation {
+            start,
+            end,
+            lane: lane.to_owned(),
+            kind: kind.to_owned(),
+            text: text.into(),
+        }
+    }
+}
+
+/// A protocol decoder reading a fixed set of signals.
+pub trait Decoder {
+    /// Glob patterns of the signals the decoder reads, in the order their waves
+    /// are passed to `decode`.
+    fn signals(&self) -> Vec<&str>;
+
+    /// Decode the waves of the selected signals. `end` is the last time of the
+    /// dump, past which frames are incomplete.
+    fn decode(&self, waves: &[&ValueChangeDumpWave], end: usize) -> Vec<Annotation>;
+
+    /// Check the configuration before any wave is decoded.
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Longest word decoders can assemble.
+const MAX_WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No signal matches a pattern of the decoder
+    UnknownSignal(String),
+    /// Words longer than the 64 bits decoded values hold
+    WordTooLong(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownSignal(pattern) => write!(f, "no signal matches '{pattern}'"),
+            Error::WordTooLong(bits) => {
+                write!(
+                    f,
+                    "words of {bits} bits are longer than the {MAX_WORD_BITS} supported"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Run a decoder over the first signal matching each of its patterns.
+pub fn decode(vcd: &ValueChangeDump, decoder: &dyn Decoder) -> Result<Vec<Annotation>, Error> {
+    decoder.validate()?;
+    let signals = vcd.signals();
+    let waves = decoder
+        .signals()
+        .into_iter()
+        .map(|pattern| {
+            signals
+                .iter()
+                .find(|sig| sig.matches(pattern))
+                .map(|sig| vcd.wave(&sig.identifier))
+                .ok_or_else(|| Error::UnknownSignal(pattern.to_owned()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut annotations = decoder.decode(&waves, vcd.end_time());
+    annotations.sort_by_key(|annotation| annotation.start);
+    Ok(annotations)
+}
+
+/// Annotations as a table with `start`, `end`, `lane`, `kind` and `text` columns.
+pub fn to_csv(annotations: &[Annotation], delimiter: Delimiter) -> String {
+    let header = ["start", "end", "lane", "kind", "text"]
+        .map(str::to_owned)
+        .to_vec();
+    let rows = annotations
+        .iter()
+        .map(|annotation| {
+            vec![
+                annotation.start.to_string(),
+                annotation.end.to_string(),
+                annotation.lane.to_owned(),
+                annotation.kind.to_owned(),
+                annotation.text.to_owned(),
+            ]
+        })
+        .collect();
+    CsvTable::new(header, rows, delimiter).to_csv()
+}
+
+/// One WaveJson data lane per annotation lane, in order of first appearance.
+pub fn to_lanes(annotations: &[Annotation]) -> Vec<DataLane> {
+    let mut lanes: Vec<DataLane> = vec![];
+    for annotation in annotations {
+        let interval = (annotation.start, annotation.end, annotation.text.to_owned());
+        match lanes.iter_mut().find(|lane| lane.name == annotation.lane) {
+            Some(lane) => lane.intervals.push(interval),
+            None => lanes.push(DataLane {
+                name: annotation.lane.to_owned(),
+                intervals: vec![interval],
+            }),
+        }
+    }
+    lanes
+}
+
+/// Combine bits into a word, `None` if any bit is unknown.
+fn word(bits: &[Option<bool>], lsb_first: bool) -> Option<u64> {
+    let fold = |word: Option<u64>, bit: &Option<bool>| Some((word? << 1) | bit.map(u64::from)?);
+    if lsb_first {
+        bits.iter().rev().fold(Some(0), fold)
+    } else {
+        bits.iter().fold(Some(0), fold)
+    }
+}
+
+/// A word in hex with as many digits as its bits need, or `x` if unknown.
+fn hex(word: Option<u64>, bits: usize) -> String {
+    match word {
+        Some(word) => format!("0x{:0width$x}", word, width = bits.div_ceil(4)),
+        None => "x".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::{assert_json_snapshot, assert_snapshot};
+    use vcd_oxide_parser::testing::{clocked_dump, dump};
+    use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions};
+
+    /// Level changes of a serial line sending `bytes` LSB first with even parity, idle high.
+    fn serial(bytes: &[(u8, bool)], bit_time: usize) -> Vec<(usize, char)> {
+        let mut levels = vec![(0, '1')];
+        let mut time = bit_time;
+        for (byte, stop) in bytes {
+            let parity = byte.count_ones() % 2 == 1;
+            let bits = [false]
+                .into_iter()
+                .chain((0..8).map(|i| byte & (1 << i) != 0))
+                .chain([parity, *stop, true]);
+            for bit in bits {
+                levels.push((time, if bit { '1' } else { '0' }));
+                time += bit_time;
+            }
+        }
+        levels
+    }
+
+    fn fixture(name: &str) -> ValueChangeDump {
+        let path = format!(
+            "{}/../vcd-oxide-parser/test/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            name
+        );
+        ValueChangeDump::parse(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_uart_clocked() {
+        // "Hi" sent 8N1 at one bit per clock cycle, after an idle cycle
+        let mut bits = vec![1];
+        for byte in b"Hi" {
+            bits.push(0);
+            bits.extend((0..8).map(|i| u64::from(byte >> i & 1)));
+            bits.push(1);
+        }
+        let vcd = clocked_dump(&[("tx", 1, &bits)]);
+        let uart = Uart {
+            line: "tb.tx".to_owned(),
+            bit_time: 10,
+            ..Default::default()
+        };
+        let annotations = decode(&vcd, &uart).unwrap();
+        let frames: Vec<_> = annotations
+            .iter()
+            .map(|a| (a.start, a.kind.as_str(), a.text.as_str()))
+            .collect();
+        assert_eq!(frames, [(10, "data", "0x48"), (110, "data", "0x69")]);
+
+        let uart = Uart {
+            data_bits: 65,
+            ..uart
+        };
+        assert_eq!(decode(&vcd, &uart), Err(Error::WordTooLong(65)));
+    }
+
+    #[test]
+    fn test_uart_fixtures() {
+        // The receiver only sees a glitch shorter than a bit
+        let rx = Uart {
+            line: "UartRxTest.uart_rx".to_owned(),
+            bit_time: 20,
+            ..Default::default()
+        };
+        assert_eq!(decode(&fixture("UartRxTest.vcd"), &rx), Ok(vec![]));
+        let missing = Uart {
+            line: "*.missing".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            decode(&fixture("UartRxTest.vcd"), &missing),
+            Err(Error::UnknownSignal("*.missing".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_uart_frames() {
+        let vcd = dump(&[(
+            "tx",
+            1,
+            serial(&[(0x55, true), (0xa3, true), (0x0f, false)], 4),
+        )]);
+        let uart = Uart {
+            line: "tb.tx".to_owned(),
+            bit_time: 4,
+            parity: Parity::Even,
+            ..Default::default()
+        };
+        assert_snapshot!(to_csv(&decode(&vcd, &uart).unwrap(), Delimiter::Comma));
+
+        // Odd parity flags every frame
+        let uart = Uart {
+            parity: Parity::Odd,
+            ..uart
+        };
+        let annotations = decode(&vcd, &uart).unwrap();
+        assert!(annotations.iter().all(|a| a.kind.ends_with("error")));
+    }
+
+    #[test]
+    fn test_spi_mode_0() {
+        // 0xa5 out and 0x3c in, data changing on falling edges and sampled on rising ones
+        let (mosi, miso) = (0xa5u8, 0x3cu8);
+        let mut sclk = vec![(0, '0')];
+        let mut mosi_levels = vec![];
+        let mut miso_levels = vec![];
+        for i in 0..8 {
+            let time = 10 + i * 10;
+            let bit = |byte: u8| if byte & (0x80 >> i) != 0 { '1' } else { '0' };
+            mosi_levels.push((time, bit(mosi)));
+            miso_levels.push((time, bit(miso)));
+            sclk.push((time + 5, '1'));
+            sclk.push((time + 10, '0'));
+        }
+        let vcd = dump(&[
+            ("sclk", 1, sclk),
+            ("mosi", 1, mosi_levels),
+            ("miso", 1, miso_levels),
+            ("cs_n", 1, vec![(0, '1'), (5, '0'), (95, '1')]),
+        ]);
+        let spi = Spi {
+            sclk: "*.sclk".to_owned(),
+            mosi: Some("*.mosi".to_owned()),
+            miso: Some("*.miso".to_owned()),
+            cs: Some("*.cs_n".to_owned()),
+            ..Default::default()
+        };
+        assert_snapshot!(to_csv(&decode(&vcd, &spi).unwrap(), Delimiter::Comma));
+
+        let spi = Spi {
+            word_bits: 65,
+            ..spi
+        };
+        assert_eq!(decode(&vcd, &spi), Err(Error::WordTooLong(65)));
+    }
+
+    #[test]
+    fn test_i2c_write() {
+        // Start, address 0x50 write, ack, data 0x12, nack, stop
+        let mut scl = vec![(0, '1')];
+        let mut sda = vec![(0, '1'), (5, '0')];
+        let bits = [0xa0u16 << 1, 0x12 << 1 | 1]
+            .iter()
+            .flat_map(|byte| (0..9).rev().map(move |i| byte & (1 << i) != 0))
+            .collect::<Vec<_>>();
+        let mut time = 10;
+        for bit in bits {
+            scl.push((time, '0'));
+            sda.push((time + 2, if bit { '1' } else { '0' }));
+            scl.push((time + 5, '1'));
+            time += 10;
+        }
+        scl.push((time, '0'));
+        sda.push((time + 2, '0'));
+        scl.push((time + 5, '1'));
+        sda.push((time + 8, '1'));
+        let vcd = dump(&[("scl", 1, scl), ("sda", 1, sda)]);
+        let i2c = I2c {
+            scl: "*.scl".to_owned(),
+            sda: "*.sda".to_owned(),
+        };
+        assert_json_snapshot!(decode(&vcd, &i2c).unwrap());
+
+        // An SCL pulse while the bus is idle doesn't hide the start that follows
+        let scl = vec![(0, '1'), (2, '0'), (4, '1')];
+        let sda = vec![(0, '1'), (6, '0')];
+        let vcd = dump(&[("scl", 1, scl), ("sda", 1, sda)]);
+        assert_eq!(
+            decode(&vcd, &i2c).unwrap(),
+            [Annotation::new(6, 6, "i2c", "start", "start")]
+        );
+    }
+
+    #[test]
+    fn test_wavejson_lanes() {
+        let vcd = dump(&[("tx", 1, serial(&[(0x55, true)], 2))]);
+        let uart = Uart {
+            line: "tb.tx".to_owned(),
+            bit_time: 2,
+            parity: Parity::Even,
+            ..Default::default()
+        };
+        let lanes = to_lanes(&decode(&vcd, &uart).unwrap());
+        let wave = WaveJson::from_vcd_with_lanes(&vcd, &WaveJsonOptions::default(), &lanes);
+        assert_snapshot!(wave.to_json());
+    }
+}
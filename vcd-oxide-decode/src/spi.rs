@@ -0,0 +1,110 @@
+use crate::{hex, word, Annotation, Decoder, Error, MAX_WORD_BITS};
+use vcd_oxide_parser::ValueChangeDumpWave;
+
+/// Serial peripheral interface, with data lines sampled on the clock edge selected by the mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spi {
+    /// Glob pattern of the clock
+    pub sclk: String,
+    pub mosi: Option<String>,
+    pub miso: Option<String>,
+    /// Active low chip select. Bits are only read while it is low and words
+    /// restart each time it is asserted.
+    pub cs: Option<String>,
+    /// SPI mode 0 to 3, the clock polarity in bit 1 and the clock phase in bit 0.
+    pub mode: u8,
+    pub word_bits: usize,
+    pub lsb_first: bool,
+}
+
+impl Default for Spi {
+    fn default() -> Self {
+        Self {
+            sclk: String::new(),
+            mosi: None,
+            miso: None,
+            cs: None,
+            mode: 0,
+            word_bits: 8,
+            lsb_first: false,
+        }
+    }
+}
+
+impl Decoder for Spi {
+    fn signals(&self) -> Vec<&str> {
+        [
+            Some(&self.sclk),
+            self.mosi.as_ref(),
+            self.miso.as_ref(),
+            self.cs.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::as_str)
+        .collect()
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.word_bits > MAX_WORD_BITS {
+            return Err(Error::WordTooLong(self.word_bits));
+        }
+        Ok(())
+    }
+
+    fn decode(&self, waves: &[&ValueChangeDumpWave], _end: usize) -> Vec<Annotation> {
+        let mut waves = waves.iter();
+        let sclk = waves.next().unwrap();
+        let mut data_lanes = vec![];
+        if self.mosi.is_some() {
+            data_lanes.push(("mosi", *waves.next().unwrap()));
+        }
+        if self.miso.is_some() {
+            data_lanes.push(("miso", *waves.next().unwrap()));
+        }
+        let cs = self.cs.as_ref().map(|_| *waves.next().unwrap());
+
+        // Modes 0 and 3 sample on the rising edge, 1 and 2 on the falling one
+        let sample_level = (self.mode >> 1) & 1 == self.mode & 1;
+        let word_bits = self.word_bits.max(1);
+        let mut annotations = vec![];
+        let mut edges: Vec<usize> = vec![];
+        let mut bits: Vec<Vec<Option<bool>>> = vec![vec![]; data_lanes.len()];
+        for time in sclk.edges(sample_level) {
+            if let Some(cs) = cs {
+                let selected = cs.level_at(time) == Some(false);
+                let reselected = cs
+                    .value_at(time)
+                    .zip(edges.first())
+                    .is_some_and(|(vc, first)| vc.time > *first);
+                if !selected || reselected {
+                    edges.clear();
+                    bits.iter_mut().for_each(Vec::clear);
+                }
+                if !selected {
+                    continue;
+                }
+            }
+
+            edges.push(time);
+            for ((_, wave), bits) in data_lanes.iter().zip(&mut bits) {
+                bits.push(wave.level_before(time));
+            }
+            if edges.len() == word_bits {
+                for ((lane, _), bits) in data_lanes.iter().zip(&mut bits) {
+                    let value = word(bits, self.lsb_first);
+                    annotations.push(Annotation::new(
+                        edges[0],
+                        time,
+                        lane,
+                        "data",
+                        hex(value, word_bits),
+                    ));
+                    bits.clear();
+                }
+                edges.clear();
+            }
+        }
+        annotations
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{hex, word, Annotation, Decoder, Error, MAX_WORD_BITS};
+use std::str::FromStr;
+use vcd_oxide_parser::ValueChangeDumpWave;
+
+/// Asynchronous serial line, sampled in the middle of each bit after a start bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uart {
+    /// Glob pattern of the serial line, e.g. `*.uart_tx`
+    pub line: String,
+    /// Time units per bit, i.e. the inverse of the baud rate in the dump's timescale.
+    pub bit_time: usize,
+    pub data_bits: usize,
+    pub parity: Parity,
+    pub stop_bits: usize,
+    /// Decode a line that idles low.
+    pub inverted: bool,
+}
+
+impl Default for Uart {
+    fn default() -> Self {
+        Self {
+            line: String::new(),
+            bit_time: 1,
+            data_bits: 8,
+            parity: Parity::None,
+            stop_bits: 1,
+            inverted: false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Parity {
+    #[default]
+    None,
+    Even,
+    Odd,
+}
+
+impl Decoder for Uart {
+    fn signals(&self) -> Vec<&str> {
+        vec![&self.line]
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        if self.data_bits > MAX_WORD_BITS {
+            return Err(Error::WordTooLong(self.data_bits));
+        }
+        Ok(())
+    }
+
+    fn decode(&self, waves: &[&ValueChangeDumpWave], end: usize) -> Vec<Annotation> {
+        let line = waves[0];
+        let bit_time = self.bit_time.max(1);
+        let idle = !self.inverted;
+        let parity_bits = usize::from(self.parity != Parity::None);
+        let frame_bits = 1 + self.data_bits + parity_bits + self.stop_bits;
+        // Bit values are read in the middle of each bit
+        let sample = |start: usize, bit: usize| {
+            line.level_at(start + bit * bit_time + bit_time / 2)
+                .map(|level| level != idle)
+        };
+
+        let mut annotations = vec![];
+        // A new start bit can only begin after the stop bits of the previous frame were read
+        let mut earliest = 0;
+        for start in line.edges(!idle) {
+            if start < earliest {
+                continue;
+            }
+            if start + frame_bits * bit_time > end + 1 {
+                break;
+            }
+            // Glitches shorter than half a bit are not start bits
+            if sample(start, 0) != Some(true) {
+                continue;
+            }
+            earliest = start + (frame_bits - 1) * bit_time + bit_time / 2;
+
+            // Data is active high on the line, i.e. the inverse of the start bit
+            let data: Vec<Option<bool>> = (1..=self.data_bits)
+                .map(|bit| sample(start, bit).map(|active| !active))
+                .collect();
+            let value = word(&data, true);
+            let parity_ok = match self.parity {
+                Parity::None => true,
+                parity => {
+                    let parity_bit = sample(start, 1 + self.data_bits).map(|active| !active);
+                    let ones = data.iter().filter(|bit| **bit == Some(true)).count()
+                        + usize::from(parity_bit == Some(true));
+                    parity_bit.is_some() && (ones % 2 == 0) == (parity == Parity::Even)
+                }
+            };
+            let stop_ok = (1 + self.data_bits + parity_bits..frame_bits)
+                .all(|bit| sample(start, bit) == Some(false));
+
+            let kind = if !stop_ok {
+                "framing-error"
+            } else if !parity_ok {
+                "parity-error"
+            } else {
+                "data"
+            };
+            annotations.push(Annotation::new(
+                start,
+                start + frame_bits * bit_time,
+                "uart",
+                kind,
+                hex(value, self.data_bits),
+            ));
+        }
+        annotations
+    }
+}
+
+impl FromStr for Parity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Parity::None),
+            "even" => Ok(Parity::Even),
+            "odd" => Ok(Parity::Odd),
+            _ => Err(format!("unknown parity '{s}'")),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+#ifndef VCD_OXIDE_H
+#define VCD_OXIDE_H
+
+/* Generated by cbindgen from vcd-oxide-ffi, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/* Scope index of signals outside any scope and parent index of top level scopes */
+#define VCD_NONE SIZE_MAX
+
+
+
+typedef enum VcdStatus {
+  VCD_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null
+   */
+  VCD_STATUS_NULL_ARGUMENT,
+  /**
+   * The file could not be read or decompressed
+   */
+  VCD_STATUS_IO,
+  /**
+   * The input is not valid UTF-8
+   */
+  VCD_STATUS_UTF8,
+  /**
+   * The input is not a valid dump
+   */
+  VCD_STATUS_PARSE,
+  /**
+   * No signal matches the path
+   */
+  VCD_STATUS_NOT_FOUND,
+  /**
+   * A scope, signal or value change index is past the end
+   */
+  VCD_STATUS_OUT_OF_RANGE,
+  /**
+   * An internal error of the library, nothing was changed
+   */
+  VCD_STATUS_PANIC,
+} VcdStatus;
+
+/**
+ * An open dump. Create with `vcd_open` or `vcd_parse` and release with `vcd_free`.
+ */
+typedef struct VcdDump VcdDump;
+
+typedef struct VcdScopeInfo {
+  const char *name;
+  /**
+   * Scope type, e.g. `module`
+   */
+  const char *kind;
+  /**
+   * Index of the enclosing scope, or `VCD_NONE`
+   */
+  size_t parent;
+} VcdScopeInfo;
+
+typedef struct VcdSignalInfo {
+  /**
+   * Hierarchical reference, e.g. `Counter.count [3:0]`
+   */
+  const char *reference;
+  /**
+   * Reference without the scope hierarchy, e.g. `count [3:0]`
+   */
+  const char *name;
+  /**
+   * Var type, e.g. `wire`
+   */
+  const char *kind;
+  const char *identifier;
+  size_t size;
+  /**
+   * Index of the declaring scope, or `VCD_NONE`
+   */
+  size_t scope;
+} VcdSignalInfo;
+
+typedef struct VcdValueChange {
+  uint64_t time;
+  /**
+   * Value as written in the dump, e.g. `1` or `b1010`
+   */
+  const char *value;
+} VcdValueChange;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Read and parse the dump at `path`, decompressing it if needed. `out` is
+ * set to null when the dump can't be opened.
+ *
+ * # Safety
+ * `path` must be a NUL terminated string and `out` a valid pointer.
+ */
+enum VcdStatus vcd_open(const char *path, struct VcdDump **out);
+
+/**
+ * Parse a dump from `length` bytes of memory. `out` is set to null when the
+ * dump can't be parsed.
+ *
+ * # Safety
+ * `data` must point to `length` readable bytes and `out` must be a valid pointer.
+ */
+enum VcdStatus vcd_parse(const char *data, size_t length, struct VcdDump **out);
+
+/**
+ * Release a dump and every string handed out for it. Null is ignored.
+ *
+ * # Safety
+ * `dump` must come from `vcd_open` or `vcd_parse` and not be used afterwards.
+ */
+void vcd_free(struct VcdDump *dump);
+
+/**
+ * Message of the last failure on this thread, valid until the next failure.
+ */
+const char *vcd_last_error(void);
+
+/**
+ * Null for a null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+const char *vcd_date(const struct VcdDump *dump);
+
+/**
+ * Null for a null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+const char *vcd_version(const struct VcdDump *dump);
+
+/**
+ * Null for a null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+const char *vcd_timescale(const struct VcdDump *dump);
+
+/**
+ * Time of the last value change in the dump, or 0 for a null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+uint64_t vcd_end_time(const struct VcdDump *dump);
+
+/**
+ * Number of scopes, listed depth first, or 0 for a null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+size_t vcd_scope_count(const struct VcdDump *dump);
+
+/**
+ * # Safety
+ * `dump` must be a valid handle and `out` a valid pointer.
+ */
+enum VcdStatus vcd_scope(const struct VcdDump *dump, size_t index, struct VcdScopeInfo *out);
+
+/**
+ * Number of signals, in declaration order, or 0 for a null handle. Aliases
+ * are listed separately.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+size_t vcd_signal_count(const struct VcdDump *dump);
+
+/**
+ * # Safety
+ * `dump` must be a valid handle and `out` a valid pointer.
+ */
+enum VcdStatus vcd_signal(const struct VcdDump *dump, size_t index, struct VcdSignalInfo *out);
+
+/**
+ * Index of the first signal whose reference matches `path`, with or
+ * without its bit select, e.g. `Counter.count`. Glob patterns are accepted.
+ *
+ * # Safety
+ * `dump` must be a valid handle, `path` a NUL terminated string and `index` a valid pointer.
+ */
+enum VcdStatus vcd_find_signal(const struct VcdDump *dump, const char *path, size_t *index);
+
+/**
+ * Value of a signal at `time`. `value` is set to null before the first change.
+ *
+ * # Safety
+ * `dump` must be a valid handle and `value` a valid pointer.
+ */
+enum VcdStatus vcd_value_at(const struct VcdDump *dump,
+                            size_t signal,
+                            uint64_t time,
+                            const char **value);
+
+/**
+ * Number of value changes of a signal, or 0 past the last signal or for a
+ * null handle.
+ *
+ * # Safety
+ * `dump` must be a valid handle or null.
+ */
+size_t vcd_change_count(const struct VcdDump *dump, size_t signal);
+
+/**
+ * The `index`th value change of a signal, in time order.
+ *
+ * # Safety
+ * `dump` must be a valid handle and `out` a valid pointer.
+ */
+enum VcdStatus vcd_change(const struct VcdDump *dump,
+                          size_t signal,
+                          size_t index,
+                          struct VcdValueChange *out);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* VCD_OXIDE_H */
@@ -0,0 +1,515 @@
+//! C API for reading dumps with the parser.
+//!
+//! A dump is opened into a `VcdDump` handle which owns every string handed
+//! out for it, so pointers stay valid until the handle is freed. Scopes,
+//! signals and value changes are addressed by index. Functions that can fail
+//! return a `VcdStatus`; the message of the last failure on the calling thread
+//! is available from `vcd_last_error`. Panics never unwind into the caller,
+//! they are reported as `VcdStatus::Panic`.
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_char, CStr, CString},
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+use vcd_oxide_parser::{
+    decompress, ValueChangeDump, ValueChangeDumpScope, ValueChangeDumpSignal, ValueChangeDumpWave,
+};
+
+/// Scope index of signals declared outside any scope and parent index of top
+/// level scopes. Written to the header as `SIZE_MAX` through `cbindgen.toml`.
+pub const VCD_NONE: usize = usize::MAX;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcdStatus {
+    Ok = 0,
+    /// A required pointer argument was null
+    NullArgument,
+    /// The file could not be read or decompressed
+    Io,
+    /// The input is not valid UTF-8
+    Utf8,
+    /// The input is not a valid dump
+    Parse,
+    /// No signal matches the path
+    NotFound,
+    /// A scope, signal or value change index is past the end
+    OutOfRange,
+    /// An internal error of the library, nothing was changed
+    Panic,
+}
+
+/// An open dump. Create with `vcd_open` or `vcd_parse` and release with `vcd_free`.
+pub struct VcdDump {
+    header: Header,
+    scopes: Vec<Scope>,
+    signals: Vec<Signal>,
+    waves: Vec<Wave>,
+}
+
+struct Header {
+    date: CString,
+    version: CString,
+    timescale: CString,
+    end_time: u64,
+}
+
+struct Scope {
+    name: CString,
+    kind: CString,
+    parent: usize,
+}
+
+struct Signal {
+    source: ValueChangeDumpSignal,
+    reference: CString,
+    name: CString,
+    kind: CString,
+    identifier: CString,
+    scope: usize,
+    wave: usize,
+}
+
+/// Value changes of an identifier code, shared by its aliases.
+struct Wave {
+    times: Vec<u64>,
+    values: Vec<CString>,
+}
+
+#[repr(C)]
+pub struct VcdScopeInfo {
+    pub name: *const c_char,
+    /// Scope type, e.g. `module`
+    pub kind: *const c_char,
+    /// Index of the enclosing scope, or `VCD_NONE`
+    pub parent: usize,
+}
+
+#[repr(C)]
+pub struct VcdSignalInfo {
+    /// Hierarchical reference, e.g. `Counter.count [3:0]`
+    pub reference: *const c_char,
+    /// Reference without the scope hierarchy, e.g. `count [3:0]`
+    pub name: *const c_char,
+    /// Var type, e.g. `wire`
+    pub kind: *const c_char,
+    pub identifier: *const c_char,
+    pub size: usize,
+    /// Index of the declaring scope, or `VCD_NONE`
+    pub scope: usize,
+}
+
+#[repr(C)]
+pub struct VcdValueChange {
+    pub time: u64,
+    /// Value as written in the dump, e.g. `1` or `b1010`
+    pub value: *const c_char,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn fail(status: VcdStatus, message: impl ToString) -> VcdStatus {
+    let message = CString::new(message.to_string().replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = message);
+    status
+}
+
+/// Run the body of an exported function, reporting a panic as a status
+/// instead of unwinding into the caller.
+fn guard(body: impl FnOnce() -> VcdStatus) -> VcdStatus {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        fail(VcdStatus::Panic, format!("internal error: {message}"))
+    })
+}
+
+/// The dump behind a handle, recording a failure for null.
+unsafe fn dump_ref<'a>(dump: *const VcdDump) -> Option<&'a VcdDump> {
+    if dump.is_null() {
+        fail(VcdStatus::NullArgument, "null argument");
+    }
+    dump.as_ref()
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value.trim().replace('\0', "")).unwrap_or_default()
+}
+
+impl VcdDump {
+    fn new(vcd: ValueChangeDump) -> Self {
+        let mut wave_ids: HashMap<String, usize> = HashMap::new();
+        let mut dump = VcdDump {
+            header: Header {
+                date: c_string(&vcd.date),
+                version: c_string(&vcd.version),
+                timescale: c_string(&vcd.timescale),
+                end_time: vcd.end_time() as u64,
+            },
+            scopes: vec![],
+            signals: vec![],
+            waves: vec![],
+        };
+        dump.add_scope(&vcd, &vcd.root_scope.borrow(), VCD_NONE, &mut wave_ids);
+        dump
+    }
+
+    /// Add the signals and child scopes of `scope` depth first, in declaration order.
+    fn add_scope(
+        &mut self,
+        vcd: &ValueChangeDump,
+        scope: &ValueChangeDumpScope,
+        index: usize,
+        wave_ids: &mut HashMap<String, usize>,
+    ) {
+        for sig in &scope.signals {
+            let wave = *wave_ids
+                .entry(sig.identifier.to_owned())
+                .or_insert_with(|| {
+                    self.waves.push(Wave::new(vcd.wave(&sig.identifier)));
+                    self.waves.len() - 1
+                });
+            self.signals.push(Signal {
+                source: sig.clone(),
+                reference: c_string(&sig.reference),
+                name: c_string(sig.short_reference()),
+                kind: c_string(&sig.kind),
+                identifier: c_string(&sig.identifier),
+                scope: index,
+                wave,
+            });
+        }
+        for child in &scope.scopes {
+            let child = child.borrow();
+            self.scopes.push(Scope {
+                name: c_string(&child.name),
+                kind: c_string(&child.kind),
+                parent: index,
+            });
+            let child_index = self.scopes.len() - 1;
+            self.add_scope(vcd, &child, child_index, wave_ids);
+        }
+    }
+}
+
+impl Wave {
+    fn new(wave: &ValueChangeDumpWave) -> Self {
+        Wave {
+            times: wave.value_changes.iter().map(|vc| vc.time as u64).collect(),
+            values: wave
+                .value_changes
+                .iter()
+                .map(|vc| c_string(&vc.value))
+                .collect(),
+        }
+    }
+
+    /// Index of the value change in effect at `time`.
+    fn index_at(&self, time: u64) -> Option<usize> {
+        self.times.partition_point(|t| *t <= time).checked_sub(1)
+    }
+}
+
+fn parse_bytes(bytes: &[u8], out: *mut *mut VcdDump) -> VcdStatus {
+    let bytes = match decompress(bytes) {
+        Ok(bytes) => bytes,
+        Err(e) => return fail(VcdStatus::Io, e),
+    };
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        Err(e) => return fail(VcdStatus::Utf8, e),
+    };
+    match ValueChangeDump::parse(text) {
+        Ok(vcd) => {
+            // SAFETY: checked to be non-null by the callers
+            unsafe { *out = Box::into_raw(Box::new(VcdDump::new(vcd))) };
+            VcdStatus::Ok
+        }
+        Err(e) => fail(VcdStatus::Parse, e),
+    }
+}
+
+/// Read and parse the dump at `path`, decompressing it if needed. `out` is
+/// set to null when the dump can't be opened.
+///
+/// # Safety
+/// `path` must be a NUL terminated string and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_open(path: *const c_char, out: *mut *mut VcdDump) -> VcdStatus {
+    if path.is_null() || out.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    *out = ptr::null_mut();
+    guard(|| {
+        let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+        match std::fs::read(&path) {
+            Ok(bytes) => parse_bytes(&bytes, out),
+            Err(e) => fail(VcdStatus::Io, format!("{path}: {e}")),
+        }
+    })
+}
+
+/// Parse a dump from `length` bytes of memory. `out` is set to null when the
+/// dump can't be parsed.
+///
+/// # Safety
+/// `data` must point to `length` readable bytes and `out` must be a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_parse(
+    data: *const c_char,
+    length: usize,
+    out: *mut *mut VcdDump,
+) -> VcdStatus {
+    if data.is_null() || out.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    *out = ptr::null_mut();
+    guard(|| parse_bytes(slice::from_raw_parts(data.cast(), length), out))
+}
+
+/// Release a dump and every string handed out for it. Null is ignored.
+///
+/// # Safety
+/// `dump` must come from `vcd_open` or `vcd_parse` and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_free(dump: *mut VcdDump) {
+    if !dump.is_null() {
+        drop(Box::from_raw(dump));
+    }
+}
+
+/// Message of the last failure on this thread, valid until the next failure.
+#[no_mangle]
+pub extern "C" fn vcd_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ptr())
+}
+
+/// Null for a null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_date(dump: *const VcdDump) -> *const c_char {
+    dump_ref(dump).map_or(ptr::null(), |dump| dump.header.date.as_ptr())
+}
+
+/// Null for a null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_version(dump: *const VcdDump) -> *const c_char {
+    dump_ref(dump).map_or(ptr::null(), |dump| dump.header.version.as_ptr())
+}
+
+/// Null for a null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_timescale(dump: *const VcdDump) -> *const c_char {
+    dump_ref(dump).map_or(ptr::null(), |dump| dump.header.timescale.as_ptr())
+}
+
+/// Time of the last value change in the dump, or 0 for a null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_end_time(dump: *const VcdDump) -> u64 {
+    dump_ref(dump).map_or(0, |dump| dump.header.end_time)
+}
+
+/// Number of scopes, listed depth first, or 0 for a null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_scope_count(dump: *const VcdDump) -> usize {
+    dump_ref(dump).map_or(0, |dump| dump.scopes.len())
+}
+
+/// # Safety
+/// `dump` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_scope(
+    dump: *const VcdDump,
+    index: usize,
+    out: *mut VcdScopeInfo,
+) -> VcdStatus {
+    if dump.is_null() || out.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    guard(|| {
+        let dump = &*dump;
+        let Some(scope) = dump.scopes.get(index) else {
+            return fail(VcdStatus::OutOfRange, format!("no scope {index}"));
+        };
+        *out = VcdScopeInfo {
+            name: scope.name.as_ptr(),
+            kind: scope.kind.as_ptr(),
+            parent: scope.parent,
+        };
+        VcdStatus::Ok
+    })
+}
+
+/// Number of signals, in declaration order, or 0 for a null handle. Aliases
+/// are listed separately.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_signal_count(dump: *const VcdDump) -> usize {
+    dump_ref(dump).map_or(0, |dump| dump.signals.len())
+}
+
+/// # Safety
+/// `dump` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_signal(
+    dump: *const VcdDump,
+    index: usize,
+    out: *mut VcdSignalInfo,
+) -> VcdStatus {
+    if dump.is_null() || out.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    guard(|| {
+        let dump = &*dump;
+        let Some(sig) = dump.signals.get(index) else {
+            return fail(VcdStatus::OutOfRange, format!("no signal {index}"));
+        };
+        *out = VcdSignalInfo {
+            reference: sig.reference.as_ptr(),
+            name: sig.name.as_ptr(),
+            kind: sig.kind.as_ptr(),
+            identifier: sig.identifier.as_ptr(),
+            size: sig.source.size,
+            scope: sig.scope,
+        };
+        VcdStatus::Ok
+    })
+}
+
+/// Index of the first signal whose reference matches `path`, with or
+/// without its bit select, e.g. `Counter.count`. Glob patterns are accepted.
+///
+/// # Safety
+/// `dump` must be a valid handle, `path` a NUL terminated string and `index` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_find_signal(
+    dump: *const VcdDump,
+    path: *const c_char,
+    index: *mut usize,
+) -> VcdStatus {
+    if dump.is_null() || path.is_null() || index.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    guard(|| {
+        let path = CStr::from_ptr(path).to_string_lossy();
+        let dump = &*dump;
+        let found = dump
+            .signals
+            .iter()
+            .position(|sig| sig.source.matches(&path));
+        match found {
+            Some(found) => {
+                *index = found;
+                VcdStatus::Ok
+            }
+            None => fail(VcdStatus::NotFound, format!("no signal matches '{path}'")),
+        }
+    })
+}
+
+/// Value of a signal at `time`. `value` is set to null before the first change.
+///
+/// # Safety
+/// `dump` must be a valid handle and `value` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_value_at(
+    dump: *const VcdDump,
+    signal: usize,
+    time: u64,
+    value: *mut *const c_char,
+) -> VcdStatus {
+    if dump.is_null() || value.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    guard(|| {
+        let Some(wave) = wave(&*dump, signal) else {
+            return fail(VcdStatus::OutOfRange, format!("no signal {signal}"));
+        };
+        *value = wave
+            .index_at(time)
+            .map_or(ptr::null(), |index| wave.values[index].as_ptr());
+        VcdStatus::Ok
+    })
+}
+
+/// Number of value changes of a signal, or 0 past the last signal or for a
+/// null handle.
+///
+/// # Safety
+/// `dump` must be a valid handle or null.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_change_count(dump: *const VcdDump, signal: usize) -> usize {
+    dump_ref(dump)
+        .and_then(|dump| wave(dump, signal))
+        .map_or(0, |wave| wave.times.len())
+}
+
+/// The `index`th value change of a signal, in time order.
+///
+/// # Safety
+/// `dump` must be a valid handle and `out` a valid pointer.
+#[no_mangle]
+pub unsafe extern "C" fn vcd_change(
+    dump: *const VcdDump,
+    signal: usize,
+    index: usize,
+    out: *mut VcdValueChange,
+) -> VcdStatus {
+    if dump.is_null() || out.is_null() {
+        return fail(VcdStatus::NullArgument, "null argument");
+    }
+    guard(|| {
+        let Some(wave) = wave(&*dump, signal) else {
+            return fail(VcdStatus::OutOfRange, format!("no signal {signal}"));
+        };
+        if index >= wave.times.len() {
+            return fail(VcdStatus::OutOfRange, format!("no value change {index}"));
+        }
+        *out = VcdValueChange {
+            time: wave.times[index],
+            value: wave.values[index].as_ptr(),
+        };
+        VcdStatus::Ok
+    })
+}
+
+fn wave(dump: &VcdDump, signal: usize) -> Option<&Wave> {
+    dump.signals.get(signal).map(|sig| &dump.waves[sig.wave])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_panics_become_a_status() {
+        let status = guard(|| panic!("unexpected"));
+        assert_eq!(status, VcdStatus::Panic);
+        let message = unsafe { CStr::from_ptr(vcd_last_error()) };
+        assert_eq!(message.to_str().unwrap(), "internal error: unexpected");
+    }
+}
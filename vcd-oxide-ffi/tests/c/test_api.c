@@ -0,0 +1,108 @@
+/* Exercises the C API against Counter.vcd, whose path is the first argument. */
+#include <stdio.h>
+#include <string.h>
+
+#include "vcd_oxide.h"
+
+static int failures = 0;
+
+#define CHECK(condition)                                                                 \
+    do {                                                                                 \
+        if (!(condition)) {                                                              \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+            failures++;                                                                  \
+        }                                                                                \
+    } while (0)
+
+static void test_open(const char *path) {
+    VcdDump *dump = NULL;
+    CHECK(vcd_open(path, &dump) == VCD_STATUS_OK);
+    if (dump == NULL) {
+        return;
+    }
+    CHECK(strcmp(vcd_timescale(dump), "1s") == 0);
+    CHECK(strcmp(vcd_version(dump), "Icarus Verilog") == 0);
+    CHECK(vcd_end_time(dump) == 6);
+
+    VcdScopeInfo scope;
+    CHECK(vcd_scope_count(dump) == 1);
+    CHECK(vcd_scope(dump, 0, &scope) == VCD_STATUS_OK);
+    CHECK(strcmp(scope.name, "Counter") == 0);
+    CHECK(strcmp(scope.kind, "module") == 0);
+    CHECK(scope.parent == VCD_NONE);
+    CHECK(vcd_scope(dump, 1, &scope) == VCD_STATUS_OUT_OF_RANGE);
+
+    VcdSignalInfo signal;
+    size_t count;
+    CHECK(vcd_signal_count(dump) == 2);
+    CHECK(vcd_find_signal(dump, "Counter.count", &count) == VCD_STATUS_OK);
+    CHECK(vcd_signal(dump, count, &signal) == VCD_STATUS_OK);
+    CHECK(strcmp(signal.reference, "Counter.count [3:0]") == 0);
+    CHECK(strcmp(signal.name, "count [3:0]") == 0);
+    CHECK(strcmp(signal.kind, "reg") == 0);
+    CHECK(strcmp(signal.identifier, "!") == 0);
+    CHECK(signal.size == 4);
+    CHECK(signal.scope == 0);
+
+    const char *value = NULL;
+    CHECK(vcd_value_at(dump, count, 3, &value) == VCD_STATUS_OK);
+    CHECK(value != NULL && strcmp(value, "b10") == 0);
+
+    size_t clk;
+    VcdValueChange change;
+    CHECK(vcd_find_signal(dump, "*.clk", &clk) == VCD_STATUS_OK);
+    CHECK(vcd_change_count(dump, clk) == 7);
+    for (size_t i = 0; i < vcd_change_count(dump, clk); i++) {
+        CHECK(vcd_change(dump, clk, i, &change) == VCD_STATUS_OK);
+        CHECK(change.time == i);
+        CHECK(strcmp(change.value, i % 2 ? "1" : "0") == 0);
+    }
+    CHECK(vcd_change(dump, clk, 7, &change) == VCD_STATUS_OUT_OF_RANGE);
+
+    CHECK(vcd_find_signal(dump, "Counter.missing", &clk) == VCD_STATUS_NOT_FOUND);
+    CHECK(strstr(vcd_last_error(), "Counter.missing") != NULL);
+    vcd_free(dump);
+}
+
+static void test_parse(void) {
+    const char text[] = "$scope module top $end $var wire 1 ! a $end $upscope $end\n"
+                        "$enddefinitions $end\n#0\n1!\n#5\n0!\n";
+    VcdDump *dump = NULL;
+    const char *value = NULL;
+    CHECK(vcd_parse(text, strlen(text), &dump) == VCD_STATUS_OK);
+    CHECK(vcd_value_at(dump, 0, 4, &value) == VCD_STATUS_OK);
+    CHECK(value != NULL && strcmp(value, "1") == 0);
+    vcd_free(dump);
+
+    CHECK(vcd_parse("not a dump", 10, &dump) == VCD_STATUS_PARSE);
+    CHECK(strstr(vcd_last_error(), "syntax error") != NULL);
+    CHECK(dump == NULL);
+    const char overflow[] = "$var wire 1 ! a $end $enddefinitions $end\n#99999999999999999999999\n";
+    CHECK(vcd_parse(overflow, strlen(overflow), &dump) == VCD_STATUS_PARSE);
+    CHECK(strstr(vcd_last_error(), "number too large") != NULL);
+    CHECK(vcd_open("missing.vcd", &dump) == VCD_STATUS_IO);
+    CHECK(vcd_open(NULL, &dump) == VCD_STATUS_NULL_ARGUMENT);
+    vcd_free(NULL);
+}
+
+static void test_null_handle(void) {
+    CHECK(vcd_date(NULL) == NULL);
+    CHECK(vcd_version(NULL) == NULL);
+    CHECK(vcd_timescale(NULL) == NULL);
+    CHECK(strcmp(vcd_last_error(), "null argument") == 0);
+    CHECK(vcd_end_time(NULL) == 0);
+    CHECK(vcd_scope_count(NULL) == 0);
+    CHECK(vcd_signal_count(NULL) == 0);
+    CHECK(vcd_change_count(NULL, 0) == 0);
+}
+
+int main(int argc, char **argv) {
+    if (argc < 2) {
+        fprintf(stderr, "usage: %s Counter.vcd\n", argv[0]);
+        return 2;
+    }
+    test_open(argv[1]);
+    test_parse();
+    test_null_handle();
+    return failures == 0 ? 0 : 1;
+}
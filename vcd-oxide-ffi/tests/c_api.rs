@@ -0,0 +1,37 @@
+use std::{env, path::Path, process::Command};
+
+/// Build the C tests with the system compiler against the shared library and run them.
+#[test]
+fn test_c_api() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    // Integration tests run from target/<profile>/deps, next to the library
+    let exe = env::current_exe().unwrap();
+    let lib_dir = exe.parent().unwrap();
+    let binary = Path::new(env!("CARGO_TARGET_TMPDIR")).join("test_api");
+
+    let status = Command::new(env::var("CC").unwrap_or_else(|_| "cc".to_owned()))
+        .arg(crate_dir.join("tests/c/test_api.c"))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(crate_dir.join("include"))
+        .arg("-L")
+        .arg(lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lvcd_oxide_ffi")
+        .arg("-o")
+        .arg(&binary)
+        .status()
+        .expect("failed to run the C compiler");
+    assert!(status.success(), "C tests failed to compile");
+
+    // Cargo's library path lists target/<profile> first, where the library may be
+    // from an older build, so only the rpath to the one next to the test is used
+    let status = Command::new(&binary)
+        .env_remove("LD_LIBRARY_PATH")
+        .arg(crate_dir.join("../vcd-oxide-parser/test/Counter.vcd"))
+        .status()
+        .unwrap();
+    assert!(status.success(), "C tests failed");
+}
@@ -0,0 +1,185 @@
+mod reader;
+mod writer;
+
+pub use crate::reader::*;
+pub use crate::writer::*;
+
+use std::fmt;
+
+/// VCD var types and their FST codes.
+const VAR_TYPES: [(&str, u8); 30] = [
+    ("event", 0),
+    ("integer", 1),
+    ("parameter", 2),
+    ("real", 3),
+    ("real_parameter", 4),
+    ("reg", 5),
+    ("supply0", 6),
+    ("supply1", 7),
+    ("time", 8),
+    ("tri", 9),
+    ("triand", 10),
+    ("trior", 11),
+    ("trireg", 12),
+    ("tri0", 13),
+    ("tri1", 14),
+    ("wand", 15),
+    ("wire", 16),
+    ("wor", 17),
+    ("port", 18),
+    ("sparray", 19),
+    ("realtime", 20),
+    ("string", 21),
+    ("bit", 22),
+    ("logic", 23),
+    ("int", 24),
+    ("shortint", 25),
+    ("longint", 26),
+    ("byte", 27),
+    ("enum", 28),
+    ("shortreal", 29),
+];
+
+/// VCD scope types and their FST codes.
+const SCOPE_TYPES: [(&str, u8); 12] = [
+    ("module", 0),
+    ("task", 1),
+    ("function", 2),
+    ("begin", 3),
+    ("fork", 4),
+    ("generate", 5),
+    ("struct", 6),
+    ("union", 7),
+    ("class", 8),
+    ("interface", 9),
+    ("package", 10),
+    ("program", 11),
+];
+
+/// Powers of ten of the VCD time units, largest first.
+const TIME_UNITS: [(&str, i8); 6] = [
+    ("s", 0),
+    ("ms", -3),
+    ("us", -6),
+    ("ns", -9),
+    ("ps", -12),
+    ("fs", -15),
+];
+
+fn is_real(kind: &str) -> bool {
+    matches!(kind, "real" | "real_parameter" | "realtime" | "shortreal")
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not a well formed FST file
+    Fst(fst_reader::ReaderError),
+    /// The FST file declares a kind of variable that has no VCD equivalent
+    UnsupportedVariable(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Fst(e) => write!(f, "invalid FST file: {e}"),
+            Error::UnsupportedVariable(name) => {
+                write!(f, "variable '{name}' has no VCD equivalent")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<fst_reader::ReaderError> for Error {
+    fn from(e: fst_reader::ReaderError) -> Self {
+        Error::Fst(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use std::io::Cursor;
+    use vcd_oxide_parser::ValueChangeDump;
+
+    fn round_trip(vcd: &ValueChangeDump) -> ValueChangeDump {
+        let mut fst = vec![];
+        write_fst(vcd, &mut fst).unwrap();
+        assert!(is_fst(&fst));
+        read_fst(Cursor::new(fst)).unwrap()
+    }
+
+    #[test]
+    fn test_counter_round_trip() {
+        let input = include_str!("../../vcd-oxide-parser/test/Counter.vcd");
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        assert_eq!(round_trip(&vcd).to_vcd(), input);
+    }
+
+    #[test]
+    fn test_uart_round_trip() {
+        let input = include_str!("../../vcd-oxide-parser/test/UartRxTest.vcd");
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        assert_snapshot!(round_trip(&vcd).to_vcd());
+    }
+
+    #[test]
+    fn test_root_signal_round_trip() {
+        let input = "$timescale\n\t1ns\n$end\n$var wire 1 ! top $end\n$scope module tb $end\n\
+                     $var wire 1 \" clk $end\n$upscope $end\n$enddefinitions $end\n\
+                     #0\n$dumpvars\n1!\n0\"\n$end\n#5\n0!\n1\"\n";
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        let read = round_trip(&vcd);
+        assert_eq!(read.root_scope.borrow().signals[0].reference, "top");
+        assert_eq!(read.to_vcd(), vcd.to_vcd());
+    }
+
+    #[test]
+    fn test_timescale_exponents() {
+        for timescale in ["1s", "10ms", "100us", "1ns", "10ps", "100fs"] {
+            assert_eq!(
+                reader::timescale(writer::timescale_exponent(timescale).unwrap()),
+                timescale
+            );
+        }
+        assert_eq!(writer::timescale_exponent("1 ps").unwrap(), -12);
+        for timescale in ["3ns", "20us", "1 day", "ns"] {
+            let error = writer::timescale_exponent(timescale).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+        assert!(!is_fst(b"$date"));
+    }
+
+    /// Reads `test/Counter.fst`, written from the parser's `Counter.vcd` by
+    /// `vcd2fst ../vcd-oxide-parser/test/Counter.vcd test/Counter.fst`.
+    #[test]
+    #[ignore = "needs test/Counter.fst written by GTKWave's vcd2fst"]
+    fn test_reference_fst() {
+        let fst = std::fs::read(concat!(env!("CARGO_MANIFEST_DIR"), "/test/Counter.fst")).unwrap();
+        let input = include_str!("../../vcd-oxide-parser/test/Counter.vcd");
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        let read = read_fst(Cursor::new(fst)).unwrap();
+        assert_eq!(read.end_time(), vcd.end_time());
+        for signal in vcd.signals() {
+            let read_signal = read.find_signal(&signal.reference).unwrap();
+            assert_eq!(
+                format!("{:?}", read.wave(&read_signal.identifier)),
+                format!("{:?}", vcd.wave(&signal.identifier)),
+            );
+        }
+    }
+
+    #[test]
+    fn test_unsupported_kinds() {
+        let input = "$timescale 1ns $end\n$scope module tb $end\n\
+                     $var wire 1 ! clk $end\n$upscope $end\n$enddefinitions $end\n#0\n0!\n";
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        vcd.root_scope.borrow().scopes[0].borrow_mut().signals[0].kind = "wiggle".to_owned();
+        let error = write_fst(&vcd, &mut vec![]).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        vcd.root_scope.borrow().scopes[0].borrow_mut().kind = "box".to_owned();
+        assert!(write_fst(&vcd, &mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{is_real, Error, SCOPE_TYPES, TIME_UNITS, VAR_TYPES};
+use fst_reader::{FstFilter, FstHierarchyEntry, FstReader, FstSignalValue};
+use std::{
+    cell::RefCell,
+    io::{BufRead, Seek},
+    rc::Rc,
+};
+use vcd_oxide_parser::{
+    compact_bits, identifier_code, ValueChange, ValueChangeDump, ValueChangeDumpScope,
+    ValueChangeDumpSignal, ValueChangeDumpWave,
+};
+
+/// FST files start with a header block, or a gzip wrapper around the whole file.
+const HEADER_BLOCK: [u8; 9] = [0, 0, 0, 0, 0, 0, 0, 1, 73];
+const GZIP_WRAPPER_BLOCK: u8 = 254;
+
+/// Whether the bytes look like the start of an FST file.
+pub fn is_fst(bytes: &[u8]) -> bool {
+    bytes.starts_with(&HEADER_BLOCK) || bytes.first() == Some(&GZIP_WRAPPER_BLOCK)
+}
+
+/// Read an FST file into the same model as a parsed VCD file. Identifier
+/// codes are assigned in declaration order, as a VCD writer would.
+pub fn read_fst(input: impl BufRead + Seek) -> Result<ValueChangeDump, Error> {
+    let mut reader = FstReader::open(input)?;
+    let header = reader.get_header();
+    let mut dump = ValueChangeDump {
+        date: header.date,
+        version: header.version,
+        timescale: timescale(header.timescale_exponent),
+        last_time: header.end_time as usize,
+        ..Default::default()
+    };
+
+    // Size and realness of each signal handle, indexed from zero
+    let mut handles: Vec<(usize, bool)> = vec![];
+    let mut active_scope = dump.root_scope.clone();
+    let mut path: Vec<String> = vec![];
+    let mut unsupported = None;
+    reader.read_hierarchy(|entry| match entry {
+        FstHierarchyEntry::Scope { tpe, name, .. } => {
+            let kind = SCOPE_TYPES
+                .iter()
+                .find(|(_, code)| *code == tpe as u8)
+                .map_or("module", |(kind, _)| kind);
+            let scope = Rc::new(RefCell::new(ValueChangeDumpScope {
+                name: name.to_owned(),
+                kind: kind.to_owned(),
+                parent: Some(Rc::downgrade(&active_scope)),
+                ..Default::default()
+            }));
+            active_scope.borrow_mut().scopes.push(scope.clone());
+            active_scope = scope;
+            path.push(name);
+        }
+        FstHierarchyEntry::UpScope => {
+            let parent = active_scope
+                .borrow()
+                .parent
+                .as_ref()
+                .and_then(|p| p.upgrade());
+            if let Some(parent) = parent {
+                active_scope = parent;
+                path.pop();
+            }
+        }
+        FstHierarchyEntry::Var {
+            tpe,
+            name,
+            length,
+            handle,
+            is_alias,
+            ..
+        } => {
+            let kind = VAR_TYPES
+                .iter()
+                .find(|(_, code)| *code == tpe as u8)
+                .map_or("wire", |(kind, _)| kind);
+            if length == 0 {
+                unsupported.get_or_insert(name.to_owned());
+            }
+            let identifier = identifier_code(handle.get_index());
+            if !is_alias {
+                handles.push((length as usize, is_real(kind)));
+                dump.wave_map
+                    .insert(identifier.to_owned(), ValueChangeDumpWave::default());
+            }
+            let mut reference = path.clone();
+            reference.push(name);
+            active_scope
+                .borrow_mut()
+                .signals
+                .push(ValueChangeDumpSignal {
+                    kind: kind.to_owned(),
+                    identifier,
+                    reference: reference.join("."),
+                    size: length as usize,
+                });
+        }
+        _ => {}
+    })?;
+    if let Some(name) = unsupported {
+        return Err(Error::UnsupportedVariable(name));
+    }
+
+    let mut waves: Vec<Vec<ValueChange>> = handles.iter().map(|_| vec![]).collect();
+    reader.read_signals(&FstFilter::all(), |time, handle, value| {
+        let index = handle.get_index();
+        let value = match value {
+            FstSignalValue::String(bytes) => {
+                let bits = String::from_utf8_lossy(bytes);
+                if handles[index].0 == 1 {
+                    bits.into_owned()
+                } else {
+                    format!("b{}", compact_bits(&bits))
+                }
+            }
+            FstSignalValue::Real(value) => format!("r{value}"),
+        };
+        waves[index].push(ValueChange {
+            time: time as usize,
+            value,
+        });
+    })?;
+    for (index, value_changes) in waves.into_iter().enumerate() {
+        dump.wave_map
+            .get_mut(&identifier_code(index))
+            .unwrap()
+            .value_changes = value_changes;
+    }
+    Ok(dump)
+}
+
+/// Timescale such as `10ns` for a power of ten of a second. Exponents below
+/// the smallest VCD unit are rounded up to `1fs`.
+pub(crate) fn timescale(exponent: i8) -> String {
+    let (unit, unit_exponent) = TIME_UNITS
+        .iter()
+        .find(|(_, unit_exponent)| *unit_exponent <= exponent)
+        .unwrap_or(&TIME_UNITS[TIME_UNITS.len() - 1]);
+    let number = 10usize.pow(exponent.saturating_sub(*unit_exponent).max(0) as u32);
+    format!("{number}{unit}")
+}
@@ -0,0 +1,337 @@
+use crate::{is_real, SCOPE_TYPES, VAR_TYPES};
+use flate2::{write::GzEncoder, Compression};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    io::{self, Write},
+    rc::Rc,
+};
+use vcd_oxide_parser::{
+    extend_bits, timescale_femtoseconds, ValueChange, ValueChangeDump, ValueChangeDumpScope,
+};
+
+const HEADER_LENGTH: u64 = 329;
+const HEADER_VERSION_LENGTH: usize = 128;
+const HEADER_DATE_LENGTH: usize = 119;
+/// Written in place of the floating point byte order so readers can detect it
+const ENDIAN_TEST: f64 = std::f64::consts::E;
+
+const BLOCK_HEADER: u8 = 0;
+const BLOCK_VALUE_CHANGES: u8 = 1;
+const BLOCK_GEOMETRY: u8 = 3;
+const BLOCK_HIERARCHY: u8 = 4;
+
+const HIERARCHY_SCOPE: u8 = 254;
+const HIERARCHY_UPSCOPE: u8 = 255;
+const VAR_TYPE_PORT: u8 = 18;
+/// Values of single bit signals besides 0 and 1, in the order of their FST codes
+const SCALAR_VALUES: &[u8; 8] = b"xzhuwl-?";
+
+/// A signal with its own value changes. Aliases share the handle of the
+/// signal first declared with their identifier code.
+struct Handle<'a> {
+    size: usize,
+    real: bool,
+    value_changes: &'a [ValueChange],
+}
+
+/// Write the dump as an FST file with a single value change block.
+/// Value change data is stored uncompressed; the hierarchy is gzip compressed.
+pub fn write_fst(vcd: &ValueChangeDump, mut output: impl Write) -> io::Result<()> {
+    let mut handles: Vec<Handle> = vec![];
+    let mut handle_ids: HashMap<String, usize> = HashMap::new();
+    let mut hierarchy = vec![];
+    let mut scope_count = 0;
+    let mut var_count = 0;
+
+    // Walk the scopes depth first, writing signals before child scopes like the VCD writer.
+    // Signals declared outside any scope come first. `None` marks the end of a scope.
+    let mut pending: Vec<Option<Rc<RefCell<ValueChangeDumpScope>>>> =
+        vec![Some(vcd.root_scope.clone())];
+    while let Some(next) = pending.pop() {
+        let Some(scope) = next else {
+            hierarchy.push(HIERARCHY_UPSCOPE);
+            continue;
+        };
+        let is_root = Rc::ptr_eq(&scope, &vcd.root_scope);
+        let scope = scope.borrow();
+        if !is_root {
+            scope_count += 1;
+            hierarchy.push(HIERARCHY_SCOPE);
+            hierarchy.push(code(&SCOPE_TYPES, &scope.kind)?);
+            write_c_str(&mut hierarchy, &scope.name);
+            write_c_str(&mut hierarchy, "");
+            pending.push(None);
+        }
+
+        for sig in &scope.signals {
+            var_count += 1;
+            let var_type = code(&VAR_TYPES, &sig.kind)?;
+            hierarchy.push(var_type);
+            hierarchy.push(0); // implicit direction
+            write_c_str(&mut hierarchy, sig.short_reference());
+            let length = if var_type == VAR_TYPE_PORT {
+                3 * sig.size + 2
+            } else {
+                sig.size
+            };
+            write_varint(&mut hierarchy, length as u64);
+            match handle_ids.get(&sig.identifier) {
+                Some(handle) => write_varint(&mut hierarchy, *handle as u64 + 1),
+                None => {
+                    handle_ids.insert(sig.identifier.to_owned(), handles.len());
+                    handles.push(Handle {
+                        size: sig.size,
+                        real: is_real(&sig.kind),
+                        value_changes: &vcd.wave(&sig.identifier).value_changes,
+                    });
+                    write_varint(&mut hierarchy, 0);
+                }
+            }
+        }
+
+        pending.extend(scope.scopes.iter().rev().map(|child| Some(child.clone())));
+    }
+
+    let mut times: Vec<usize> = handles
+        .iter()
+        .flat_map(|handle| handle.value_changes.iter().map(|vc| vc.time))
+        .collect();
+    times.sort_unstable();
+    times.dedup();
+    let start_time = times.first().copied().unwrap_or(0) as u64;
+    let end_time = vcd.end_time() as u64;
+
+    // Header
+    output.write_all(&[BLOCK_HEADER])?;
+    output.write_all(&HEADER_LENGTH.to_be_bytes())?;
+    output.write_all(&start_time.to_be_bytes())?;
+    output.write_all(&end_time.to_be_bytes())?;
+    output.write_all(&ENDIAN_TEST.to_le_bytes())?;
+    output.write_all(&0u64.to_be_bytes())?; // memory used by the writer
+    output.write_all(&(scope_count as u64).to_be_bytes())?;
+    output.write_all(&(var_count as u64).to_be_bytes())?;
+    output.write_all(&(handles.len() as u64).to_be_bytes())?;
+    output.write_all(&1u64.to_be_bytes())?; // value change blocks
+    output.write_all(&timescale_exponent(&vcd.timescale)?.to_be_bytes())?;
+    output.write_all(&fixed_c_str(vcd.version.trim(), HEADER_VERSION_LENGTH))?;
+    output.write_all(&fixed_c_str(vcd.date.trim(), HEADER_DATE_LENGTH))?;
+    output.write_all(&[0])?; // Verilog
+    output.write_all(&0u64.to_be_bytes())?; // time zero
+
+    write_value_changes(&mut output, &handles, &times)?;
+
+    // Geometry, stored uncompressed
+    let mut geometry = vec![];
+    for handle in &handles {
+        // Reals are marked with a zero length
+        let length = if handle.real { 0 } else { handle.size as u64 };
+        write_varint(&mut geometry, length);
+    }
+    output.write_all(&[BLOCK_GEOMETRY])?;
+    output.write_all(&(geometry.len() as u64 + 24).to_be_bytes())?;
+    output.write_all(&(geometry.len() as u64).to_be_bytes())?;
+    output.write_all(&(handles.len() as u64).to_be_bytes())?;
+    output.write_all(&geometry)?;
+
+    // Hierarchy
+    let mut encoder = GzEncoder::new(vec![], Compression::default());
+    encoder.write_all(&hierarchy)?;
+    let compressed = encoder.finish()?;
+    output.write_all(&[BLOCK_HIERARCHY])?;
+    output.write_all(&(compressed.len() as u64 + 16).to_be_bytes())?;
+    output.write_all(&(hierarchy.len() as u64).to_be_bytes())?;
+    output.write_all(&compressed)?;
+    output.flush()
+}
+
+fn write_value_changes(
+    output: &mut impl Write,
+    handles: &[Handle],
+    times: &[usize],
+) -> io::Result<()> {
+    let start_time = times.first().copied().unwrap_or(0) as u64;
+    let end_time = times.last().copied().unwrap_or(0) as u64;
+    let mut section = vec![];
+    section.extend(start_time.to_be_bytes());
+    section.extend(end_time.to_be_bytes());
+    section.extend(0u64.to_be_bytes()); // memory required for traversal
+
+    // Initial values, only used by readers when nothing changes at the start time
+    let mut frame = vec![];
+    for handle in handles {
+        if handle.real {
+            frame.extend(f64::NAN.to_le_bytes());
+        } else {
+            frame.extend(b"x".repeat(handle.size));
+        }
+    }
+    write_varint(&mut section, frame.len() as u64);
+    write_varint(&mut section, frame.len() as u64);
+    write_varint(&mut section, handles.len() as u64);
+    section.extend(frame);
+    write_varint(&mut section, handles.len() as u64);
+
+    // Value change data of each handle, with offsets relative to the pack type
+    let data_start = section.len();
+    section.push(b'Z');
+    let mut chain = vec![];
+    let mut previous_offset = 0;
+    let mut without_changes = 0;
+    for handle in handles {
+        if handle.value_changes.is_empty() {
+            without_changes += 1;
+            continue;
+        }
+        if without_changes > 0 {
+            write_varint(&mut chain, without_changes << 1);
+            without_changes = 0;
+        }
+        let offset = section.len() - data_start;
+        write_varint(&mut chain, (((offset - previous_offset) as u64) << 1) | 1);
+        previous_offset = offset;
+
+        // A zero length marks the data as uncompressed
+        write_varint(&mut section, 0);
+        write_handle_changes(&mut section, handle, times);
+    }
+    if without_changes > 0 {
+        write_varint(&mut chain, without_changes << 1);
+    }
+    section.extend(&chain);
+    section.extend((chain.len() as u64).to_be_bytes());
+
+    // Times are delta encoded and stored uncompressed
+    let mut time_table = vec![];
+    let mut previous_time = 0;
+    for time in times {
+        write_varint(&mut time_table, (time - previous_time) as u64);
+        previous_time = *time;
+    }
+    section.extend(&time_table);
+    section.extend((time_table.len() as u64).to_be_bytes());
+    section.extend((time_table.len() as u64).to_be_bytes());
+    section.extend((times.len() as u64).to_be_bytes());
+
+    output.write_all(&[BLOCK_VALUE_CHANGES])?;
+    output.write_all(&(section.len() as u64 + 8).to_be_bytes())?;
+    output.write_all(&section)
+}
+
+/// Encode the value changes of a handle, each prefixed with the number of
+/// time table entries since the previous change.
+fn write_handle_changes(output: &mut Vec<u8>, handle: &Handle, times: &[usize]) {
+    let mut previous_index = 0;
+    let value_changes = handle.value_changes;
+    for (index, value_change) in value_changes.iter().enumerate() {
+        // Only the last of several changes at the same time takes effect
+        if value_changes
+            .get(index + 1)
+            .is_some_and(|next| next.time == value_change.time)
+        {
+            continue;
+        }
+        let time_index = times.binary_search(&value_change.time).unwrap();
+        let delta = (time_index - previous_index) as u64;
+        previous_index = time_index;
+
+        if handle.real {
+            let value = value_change.value[1..].parse::<f64>().unwrap_or(f64::NAN);
+            write_varint(output, (delta << 1) | 1);
+            output.extend(value.to_le_bytes());
+            continue;
+        }
+
+        let bits = extend_bits(&value_change.value, handle.size);
+        let bits = &bits.as_bytes()[bits.len().saturating_sub(handle.size)..];
+        if handle.size == 1 {
+            match bits[0] {
+                b'0' | b'1' => {
+                    write_varint(output, (delta << 2) | (((bits[0] - b'0') as u64) << 1))
+                }
+                bit => {
+                    let value = SCALAR_VALUES.iter().position(|v| *v == bit).unwrap_or(0);
+                    write_varint(output, (delta << 4) | ((value as u64) << 1) | 1);
+                }
+            }
+        } else if bits.iter().all(|bit| matches!(bit, b'0' | b'1')) {
+            // Two state values are packed eight bits to a byte
+            write_varint(output, delta << 1);
+            for chunk in bits.chunks(8) {
+                let byte = chunk.iter().enumerate().fold(0u8, |byte, (index, bit)| {
+                    byte | ((bit - b'0') << (7 - index))
+                });
+                output.push(byte);
+            }
+        } else {
+            write_varint(output, (delta << 1) | 1);
+            output.extend(bits);
+        }
+    }
+}
+
+/// Power of ten of a second for a timescale such as `10ns`. FST only stores
+/// powers of ten, so timescales such as `3ns` can't be written. Dumps without
+/// a timescale are written in nanoseconds.
+pub(crate) fn timescale_exponent(timescale: &str) -> io::Result<i8> {
+    if timescale.trim().is_empty() {
+        return Ok(-9);
+    }
+    let mut femtoseconds = timescale_femtoseconds(timescale).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid timescale {timescale:?}"),
+        )
+    })?;
+    let mut exponent = -15;
+    while femtoseconds % 10 == 0 {
+        femtoseconds /= 10;
+        exponent += 1;
+    }
+    if femtoseconds != 1 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("timescale {timescale:?} is not a power of ten, which FST requires"),
+        ));
+    }
+    Ok(exponent)
+}
+
+/// FST code of a scope or var kind.
+fn code(table: &[(&str, u8)], name: &str) -> io::Result<u8> {
+    table
+        .iter()
+        .find(|(kind, _)| *kind == name)
+        .map(|(_, code)| *code)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{name:?} has no FST equivalent"),
+            )
+        })
+}
+
+/// LEB128 encoding used for most integers in FST files
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn write_c_str(output: &mut Vec<u8>, value: &str) {
+    output.extend(value.as_bytes());
+    output.push(0);
+}
+
+/// A zero padded string field of `length` bytes, truncated to leave room for the terminator.
+fn fixed_c_str(value: &str, length: usize) -> Vec<u8> {
+    let mut bytes = value.as_bytes()[..value.len().min(length - 1)].to_vec();
+    bytes.resize(length, 0);
+    bytes
+}
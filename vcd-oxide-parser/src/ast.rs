@@ -1,12 +1,6 @@
-#[derive(Debug)]
-pub struct ValueChangeDumpDefinition {
-    pub declaration_commands: Vec<DeclarationCommand>,
-    pub simulation_commands: Vec<SimulationCommand>,
-}
-
 #[derive(Debug)]
 pub enum DeclarationCommand {
-    Comment(GenericComment),
+    Comment,
     Date(DeclarationDate),
     EndDefinitions,
     Scope(DeclarationScope),
@@ -49,25 +43,16 @@ pub struct DeclarationVar {
 #[derive(Debug)]
 pub enum SimulationCommand {
     KeywordCommand(SimulationKeywordCommand),
-    Comment(GenericComment),
+    Comment,
     SimulationTime(SimulationTime),
     ValueChange(SimulationValueChange),
 }
 
 #[derive(Debug)]
 pub struct SimulationKeywordCommand {
-    pub ty: SimulationType,
     pub value_changes: Vec<SimulationValueChange>,
 }
 
-#[derive(Debug)]
-pub enum SimulationType {
-    DumpAll,
-    DumpOff,
-    DumpOn,
-    DumpVars,
-}
-
 #[derive(Debug)]
 pub enum SimulationValueChange {
     Scalar(ScalarValueChange),
@@ -102,8 +87,3 @@ pub struct RealVectorValueChange {
 pub struct SimulationTime {
     pub value: usize,
 }
-
-#[derive(Debug)]
-pub struct GenericComment {
-    pub value: String,
-}
@@ -0,0 +1,145 @@
+use crate::ValueChangeDump;
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{self, Write},
+    str::FromStr,
+};
+
+/// Compression formats recognised by their magic numbers. Each needs the
+/// cargo feature of the same name to be decompressed or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl Compression {
+    /// The compression of the bytes, if any, from their first few bytes.
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Some(Compression::Gzip)
+        } else if bytes.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Compression::Zstd)
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0]) {
+            Some(Compression::Xz)
+        } else {
+            None
+        }
+    }
+
+    /// Conventional file extension, without the dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+            Compression::Xz => "xz",
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "xz")),
+        allow(unused_variables)
+    )]
+    pub fn decompress(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => read_all(flate2::read::MultiGzDecoder::new(bytes)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::decode_all(bytes),
+            #[cfg(feature = "xz")]
+            Compression::Xz => read_all(xz2::read::XzDecoder::new_multi_decoder(bytes)),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    #[cfg_attr(
+        not(any(feature = "gzip", feature = "zstd", feature = "xz")),
+        allow(unused_variables)
+    )]
+    pub fn compress(&self, bytes: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => zstd::encode_all(bytes, 0),
+            #[cfg(feature = "xz")]
+            Compression::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(vec![], 6);
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unsupported()),
+        }
+    }
+
+    #[cfg_attr(
+        all(feature = "gzip", feature = "zstd", feature = "xz"),
+        allow(dead_code)
+    )]
+    fn unsupported(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{self} support is not enabled, rebuild with the '{self}' feature"),
+        )
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "xz"))]
+fn read_all(mut reader: impl io::Read) -> io::Result<Vec<u8>> {
+    let mut output = vec![];
+    reader.read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// Decompress the bytes if they start with a known magic number, otherwise
+/// return them untouched.
+pub fn decompress(bytes: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+    match Compression::detect(bytes) {
+        Some(compression) => compression.decompress(bytes).map(Cow::Owned),
+        None => Ok(Cow::Borrowed(bytes)),
+    }
+}
+
+impl ValueChangeDump {
+    /// Write the dump in VCD format through the given compression.
+    pub fn write_vcd_compressed(
+        &self,
+        mut writer: impl Write,
+        compression: Compression,
+    ) -> io::Result<()> {
+        writer.write_all(&compression.compress(self.to_vcd().as_bytes())?)
+    }
+}
+
+impl FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gz" | "gzip" => Ok(Compression::Gzip),
+            "zst" | "zstd" => Ok(Compression::Zstd),
+            "xz" => Ok(Compression::Xz),
+            _ => Err(format!("unknown compression '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+            Compression::Xz => "xz",
+        };
+        f.write_str(name)
+    }
+}
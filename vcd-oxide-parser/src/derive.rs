@@ -0,0 +1,172 @@
+use crate::{
+    compact_bits, identifier_code, Error, Expression, Samples, ValueChange, ValueChangeDump,
+    ValueChangeDumpScope, ValueChangeDumpSignal, ValueChangeDumpWave,
+};
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::BTreeSet,
+    rc::Rc,
+    str::FromStr,
+};
+
+/// Scope that [`ValueChangeDump::add_derived`] declares derived signals in.
+pub const DERIVED_SCOPE: &str = "derived";
+
+/// A signal computed from an [`Expression`] over the signals of a dump, e.g.
+/// `handshake = tb.valid && tb.ready` or `page = tb.addr[15:8]`.
+///
+/// Parsing only checks the definition. Adding it to a dump declares the
+/// signal, and its wave is computed the first time it's read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivedSignal {
+    pub name: String,
+    pub expression: Expression,
+}
+
+impl DerivedSignal {
+    /// Parse a definition such as `handshake = tb.valid && tb.ready`.
+    pub fn parse(definition: &str) -> Result<Self, Error> {
+        let (name, expression) = Expression::parse_definition(definition)?;
+        Ok(DerivedSignal { name, expression })
+    }
+
+    /// The declaration of the signal over `vcd`, checking that the signals
+    /// and selects of the expression exist. It has no identifier code and its
+    /// reference has no scope, as neither is known until the signal is added
+    /// to a dump.
+    pub fn declare(&self, vcd: &ValueChangeDump) -> Result<ValueChangeDumpSignal, Error> {
+        let width = self.expression.resolve(vcd)?.width();
+        let reference = if width == 1 {
+            self.name.to_owned()
+        } else {
+            format!("{} [{}:0]", self.name, width - 1)
+        };
+        Ok(ValueChangeDumpSignal {
+            kind: "wire".to_owned(),
+            identifier: String::new(),
+            reference,
+            size: width,
+        })
+    }
+
+    /// The wave of the signal over `vcd`.
+    ///
+    /// The expression is evaluated at every change of the signals it uses.
+    /// With functions such as `rose` it is also evaluated at the next time
+    /// anything in the dump changes, so an edge is a pulse lasting one time
+    /// step.
+    pub fn evaluate(&self, vcd: &ValueChangeDump) -> Result<ValueChangeDumpWave, Error> {
+        let expr = self.expression.resolve(vcd)?;
+        let mut times: BTreeSet<usize> = expr
+            .waves()
+            .iter()
+            .flat_map(|wave| wave.value_changes.iter().map(|vc| vc.time))
+            .collect();
+        if expr.has_history() {
+            let steps: BTreeSet<usize> = vcd
+                .wave_map
+                .values()
+                .flat_map(|wave| wave.value_changes.iter().map(|vc| vc.time))
+                .collect();
+            let next: Vec<usize> = times
+                .iter()
+                .filter_map(|time| steps.range(time + 1..).next().copied())
+                .collect();
+            times.extend(next);
+        }
+        let samples = Samples {
+            times: times.into_iter().collect(),
+            before: false,
+        };
+
+        let width = expr.width();
+        let mut wave = ValueChangeDumpWave::default();
+        for (index, time) in samples.times.iter().enumerate() {
+            let bits = expr.eval(&samples, index);
+            let value = if width == 1 {
+                bits
+            } else {
+                format!("b{}", compact_bits(&bits))
+            };
+            if wave.value_changes.last().map(|vc| &vc.value) != Some(&value) {
+                wave.value_changes.push(ValueChange { time: *time, value });
+            }
+        }
+        Ok(wave)
+    }
+}
+
+impl FromStr for DerivedSignal {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DerivedSignal::parse(s)
+    }
+}
+
+/// The wave of a derived signal in a dump, computed the first time it's read
+/// through [`ValueChangeDump::wave`].
+#[derive(Debug)]
+pub struct DerivedWave {
+    pub signal: DerivedSignal,
+    pub(crate) wave: OnceCell<ValueChangeDumpWave>,
+}
+
+impl ValueChangeDump {
+    /// Declare a derived signal in the top level `derived` scope, e.g. as
+    /// `derived.handshake`, so exporters treat it like any other signal. Its
+    /// wave is computed the first time it's read. Later definitions can refer
+    /// to earlier ones.
+    pub fn add_derived(&mut self, derived: &DerivedSignal) -> Result<ValueChangeDumpSignal, Error> {
+        let reference = format!("{}.{}", DERIVED_SCOPE, derived.name);
+        if self.signals().iter().any(|sig| sig.matches(&reference)) {
+            return Err(Error::DuplicateSignal(reference));
+        }
+        let mut signal = derived.declare(self)?;
+        signal.identifier = self.unused_identifier();
+        signal.reference = format!("{}.{}", DERIVED_SCOPE, signal.reference);
+        self.derived_scope()
+            .borrow_mut()
+            .signals
+            .push(signal.clone());
+        self.derived_waves.insert(
+            signal.identifier.to_owned(),
+            DerivedWave {
+                signal: derived.clone(),
+                wave: OnceCell::new(),
+            },
+        );
+        Ok(signal)
+    }
+
+    fn derived_scope(&self) -> Rc<RefCell<ValueChangeDumpScope>> {
+        let existing = self
+            .root_scope
+            .borrow()
+            .scopes
+            .iter()
+            .find(|scope| scope.borrow().name == DERIVED_SCOPE)
+            .cloned();
+        existing.unwrap_or_else(|| {
+            let scope = Rc::new(RefCell::new(ValueChangeDumpScope {
+                name: DERIVED_SCOPE.to_owned(),
+                kind: "module".to_owned(),
+                parent: Some(Rc::downgrade(&self.root_scope)),
+                ..Default::default()
+            }));
+            self.root_scope.borrow_mut().scopes.push(scope.clone());
+            scope
+        })
+    }
+
+    /// The shortest identifier code no wave uses yet.
+    fn unused_identifier(&self) -> String {
+        (0..)
+            .map(identifier_code)
+            .find(|identifier| {
+                !self.wave_map.contains_key(identifier)
+                    && !self.derived_waves.contains_key(identifier)
+            })
+            .unwrap()
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{extend_bits, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Options controlling how two dumps are compared.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Glob patterns selecting the signals to compare, matched after names
+    /// are stripped and mapped. Every signal is compared when empty.
+    pub signals: Vec<String>,
+    /// Prefixes removed from references in both dumps before matching,
+    /// e.g. `tb.dut.` when only one of the runs wraps the design in a testbench.
+    pub strip_prefixes: Vec<String>,
+    /// Signals of the first dump that go by another name in the second one,
+    /// keyed by their name in the first dump.
+    pub rename: BTreeMap<String, String>,
+    /// Mismatches lasting no more than this many time units are ignored.
+    pub tolerance: usize,
+    /// Treat `x` bits as matching any value.
+    pub x_wildcard: bool,
+    /// Number of mismatches reported per signal.
+    pub max_mismatches: usize,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            signals: vec![],
+            strip_prefixes: vec![],
+            rename: BTreeMap::new(),
+            tolerance: 0,
+            x_wildcard: false,
+            max_mismatches: 10,
+        }
+    }
+}
+
+/// Differences between two dumps, with signals matched by hierarchical name.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct DumpDiff {
+    /// Signals of the first dump without a counterpart in the second
+    pub only_in_a: Vec<String>,
+    /// Signals of the second dump without a counterpart in the first
+    pub only_in_b: Vec<String>,
+    /// Signals whose values differ, in declaration order of the first dump
+    pub signals: Vec<SignalDiff>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct SignalDiff {
+    pub name: String,
+    /// Total number of mismatches, including those beyond `max_mismatches`
+    pub mismatch_count: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// A period during which the two dumps disagree, with the values at its start.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Mismatch {
+    pub time: usize,
+    pub a: String,
+    pub b: String,
+}
+
+impl DumpDiff {
+    pub fn from_vcds(a: &ValueChangeDump, b: &ValueChangeDump, options: &DiffOptions) -> Self {
+        let b_signals: BTreeMap<String, ValueChangeDumpSignal> = b
+            .signals()
+            .into_iter()
+            .map(|sig| (strip(&sig.reference, options), sig))
+            .filter(|(name, _)| selected(name, options))
+            .collect();
+
+        let mut diff = DumpDiff::default();
+        let mut matched = BTreeSet::new();
+        for sig in a.signals() {
+            let name = strip(&sig.reference, options);
+            let name = options.rename.get(&name).cloned().unwrap_or(name);
+            if !selected(&name, options) || matched.contains(&name) {
+                continue;
+            }
+            let Some(b_sig) = b_signals.get(&name) else {
+                diff.only_in_a.push(name);
+                continue;
+            };
+            let size = sig.size.max(b_sig.size);
+            let mismatches = compare(
+                a.wave(&sig.identifier),
+                b.wave(&b_sig.identifier),
+                size,
+                options,
+            );
+            if !mismatches.is_empty() {
+                diff.signals.push(SignalDiff {
+                    name: name.to_owned(),
+                    mismatch_count: mismatches.len(),
+                    mismatches: mismatches
+                        .into_iter()
+                        .take(options.max_mismatches)
+                        .collect(),
+                });
+            }
+            matched.insert(name);
+        }
+        diff.only_in_b = b_signals
+            .into_keys()
+            .filter(|name| !matched.contains(name))
+            .collect();
+        diff
+    }
+
+    /// Whether the dumps agree on every signal.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.signals.is_empty()
+    }
+}
+
+impl ValueChangeDump {
+    /// Compare this dump against `other`.
+    pub fn diff(&self, other: &ValueChangeDump, options: &DiffOptions) -> DumpDiff {
+        DumpDiff::from_vcds(self, other, options)
+    }
+}
+
+fn strip(reference: &str, options: &DiffOptions) -> String {
+    options
+        .strip_prefixes
+        .iter()
+        .find_map(|prefix| reference.strip_prefix(prefix.as_str()))
+        .unwrap_or(reference)
+        .to_owned()
+}
+
+fn selected(name: &str, options: &DiffOptions) -> bool {
+    let sig = ValueChangeDumpSignal {
+        reference: name.to_owned(),
+        ..Default::default()
+    };
+    options.signals.is_empty() || options.signals.iter().any(|p| sig.matches(p))
+}
+
+/// Periods during which the waves disagree. A period still open at the last
+/// value change is always reported, as the disagreement may outlast the dump.
+fn compare(
+    a: &ValueChangeDumpWave,
+    b: &ValueChangeDumpWave,
+    size: usize,
+    options: &DiffOptions,
+) -> Vec<Mismatch> {
+    let mut times: Vec<usize> = a
+        .value_changes
+        .iter()
+        .chain(&b.value_changes)
+        .map(|value_change| value_change.time)
+        .collect();
+    times.sort_unstable();
+    times.dedup();
+
+    let value = |wave: &ValueChangeDumpWave, time| {
+        wave.value_at(time).map_or_else(
+            || "x".to_owned(),
+            |value_change| value_change.value.to_owned(),
+        )
+    };
+
+    let mut mismatches = vec![];
+    let mut open: Option<Mismatch> = None;
+    for (index, time) in times.iter().enumerate() {
+        let (a_value, b_value) = (value(a, *time), value(b, *time));
+        let equal = values_match(&a_value, &b_value, size, options.x_wildcard);
+        match (open.take(), equal) {
+            (None, false) => {
+                open = Some(Mismatch {
+                    time: *time,
+                    a: a_value,
+                    b: b_value,
+                })
+            }
+            (Some(mismatch), true) => {
+                if time - mismatch.time > options.tolerance {
+                    mismatches.push(mismatch);
+                }
+            }
+            (mismatch, _) => open = mismatch,
+        }
+        if index == times.len() - 1 {
+            mismatches.extend(open.take());
+        }
+    }
+    mismatches
+}
+
+fn values_match(a: &str, b: &str, size: usize, x_wildcard: bool) -> bool {
+    let is_real = |value: &str| value.starts_with('r') || value.starts_with('R');
+    if is_real(a) || is_real(b) {
+        return match (a[1..].parse::<f64>(), b[1..].parse::<f64>()) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a.eq_ignore_ascii_case(b),
+        };
+    }
+    let size = size.max(a.len()).max(b.len());
+    let (a, b) = (extend_bits(a, size), extend_bits(b, size));
+    a.chars()
+        .zip(b.chars())
+        .all(|(a, b)| a == b || (x_wildcard && (a == 'x' || b == 'x')))
+}
+
+impl fmt::Display for DumpDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for name in &self.only_in_a {
+            writeln!(f, "only in a: {name}")?;
+        }
+        for name in &self.only_in_b {
+            writeln!(f, "only in b: {name}")?;
+        }
+        for sig in &self.signals {
+            let plural = if sig.mismatch_count == 1 { "" } else { "es" };
+            writeln!(f, "{}: {} mismatch{}", sig.name, sig.mismatch_count, plural)?;
+            for mismatch in &sig.mismatches {
+                writeln!(f, "  #{}  {} != {}", mismatch.time, mismatch.a, mismatch.b)?;
+            }
+            if sig.mismatch_count > sig.mismatches.len() {
+                writeln!(
+                    f,
+                    "  ... {} more",
+                    sig.mismatch_count - sig.mismatches.len()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The input does not follow the VCD grammar
+    Syntax(String),
+    /// A value change refers to an identifier code without a `$var` declaration
+    UnknownIdentifier(String),
+    /// An `$upscope` without a matching `$scope`
+    UnbalancedScope,
+    /// A derived signal refers to a signal the dump doesn't declare
+    UnknownSignal(String),
+    /// A bit or part select outside the declared range of a signal
+    InvalidSelect { signal: String, select: String },
+    /// A signal has the same reference as an existing one
+    DuplicateSignal(String),
+    /// A timescale such as `1 day` that isn't a number of s, ms, us, ns, ps or fs
+    InvalidTimescale(String),
+    /// A time that doesn't fit in the model once offset or converted to another timescale
+    TimeOverflow,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(message) => write!(f, "syntax error\n{message}"),
+            Error::UnknownIdentifier(identifier) => {
+                write!(
+                    f,
+                    "value change for undeclared identifier code '{identifier}'"
+                )
+            }
+            Error::UnbalancedScope => write!(f, "$upscope without a matching $scope"),
+            Error::UnknownSignal(name) => write!(f, "unknown signal '{name}'"),
+            Error::InvalidSelect { signal, select } => {
+                write!(f, "'{select}' is outside the declared range of '{signal}'")
+            }
+            Error::DuplicateSignal(reference) => {
+                write!(f, "signal '{reference}' is already declared")
+            }
+            Error::InvalidTimescale(timescale) => write!(f, "invalid timescale '{timescale}'"),
+            Error::TimeOverflow => write!(f, "time is too large to represent"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
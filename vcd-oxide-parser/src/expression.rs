@@ -0,0 +1,641 @@
+use crate::{extend_bits, Error, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave};
+use pest::{
+    iterators::{Pair, Pairs},
+    pratt_parser::{Assoc, Op, PrattParser},
+    Parser,
+};
+use pest_derive::Parser;
+use std::{cell::OnceCell, ops::Range, str::FromStr, sync::LazyLock};
+
+/// Widest sized literal, the least Verilog tools must support.
+const MAX_LITERAL_WIDTH: usize = 1 << 16;
+
+#[derive(Parser)]
+#[grammar = "grammar/expression.pest"]
+struct ExpressionParser;
+
+/// Operator precedence, loosest first.
+static PRATT: LazyLock<PrattParser<Rule>> = LazyLock::new(|| {
+    PrattParser::new()
+        .op(Op::infix(Rule::or, Assoc::Left))
+        .op(Op::infix(Rule::and, Assoc::Left))
+        .op(Op::infix(Rule::bit_or, Assoc::Left))
+        .op(Op::infix(Rule::bit_xor, Assoc::Left))
+        .op(Op::infix(Rule::bit_and, Assoc::Left))
+        .op(Op::infix(Rule::eq, Assoc::Left) | Op::infix(Rule::ne, Assoc::Left))
+        .op(Op::infix(Rule::lt, Assoc::Left)
+            | Op::infix(Rule::le, Assoc::Left)
+            | Op::infix(Rule::gt, Assoc::Left)
+            | Op::infix(Rule::ge, Assoc::Left))
+        .op(Op::infix(Rule::shl, Assoc::Left) | Op::infix(Rule::shr, Assoc::Left))
+        .op(Op::infix(Rule::add, Assoc::Left) | Op::infix(Rule::sub, Assoc::Left))
+        .op(Op::infix(Rule::mul, Assoc::Left)
+            | Op::infix(Rule::div, Assoc::Left)
+            | Op::infix(Rule::rem, Assoc::Left))
+        .op(Op::prefix(Rule::not) | Op::prefix(Rule::bit_not) | Op::prefix(Rule::neg))
+});
+
+/// An expression over the signals of a dump, e.g. `valid && ready` or
+/// `addr[15:8] + 1`, as used by derived signals and property checks.
+///
+/// Expressions use Verilog operators with unsigned, four state arithmetic,
+/// concatenation with `{a, b}`, bit and part selects following the declared
+/// range and the functions `rose`, `fell`, `stable` and `past`, which compare
+/// with the previous sample. Edges look at the least significant bit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    /// The expression as written
+    pub text: String,
+    expr: Expr<Select>,
+}
+
+/// An [`Expression`] with its signals resolved against a dump.
+pub struct ResolvedExpression<'a> {
+    expr: Expr<Operand<'a>>,
+}
+
+/// Times to evaluate expressions at, e.g. the edges of a clock.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Samples {
+    pub times: Vec<usize>,
+    /// Read values just before each time, as a register clocked then would,
+    /// instead of at it
+    pub before: bool,
+}
+
+/// A signal an expression refers to, e.g. `tb.addr[15:8]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Select {
+    name: String,
+    range: Option<(i64, i64)>,
+}
+
+/// The bits of a signal an expression reads, resolved against a dump.
+struct Operand<'a> {
+    vcd: &'a ValueChangeDump,
+    identifier: String,
+    /// Looked up when first read, so resolving doesn't compute derived waves
+    wave: OnceCell<&'a ValueChangeDumpWave>,
+    size: usize,
+    /// Positions from the most significant bit
+    bits: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr<T> {
+    Signal(T),
+    /// Bits from the most significant one
+    Literal(String),
+    Unary(UnaryOp, Box<Expr<T>>),
+    Binary(BinaryOp, Box<Expr<T>>, Box<Expr<T>>),
+    Concat(Vec<Expr<T>>),
+    Function(Function, Box<Expr<T>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnaryOp {
+    Not,
+    BitNot,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BinaryOp {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Rose,
+    Fell,
+    Stable,
+    Past,
+}
+
+impl Expression {
+    /// Parse an expression such as `valid && ready`.
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        let pairs = ExpressionParser::parse(Rule::expression, input)
+            .map_err(|e| Error::Syntax(e.to_string()))?;
+        visit_expression(pairs.peek().unwrap())
+    }
+
+    /// Parse a definition such as `handshake = valid && ready` into its name
+    /// and expression.
+    pub(crate) fn parse_definition(input: &str) -> Result<(String, Self), Error> {
+        let mut pairs = ExpressionParser::parse(Rule::definition, input)
+            .map_err(|e| Error::Syntax(e.to_string()))?;
+        let name = pairs.next().unwrap().as_str().to_owned();
+        Ok((name, visit_expression(pairs.next().unwrap())?))
+    }
+
+    /// Look up the signals the expression uses in `vcd`, see
+    /// [`ValueChangeDump::find_signal`].
+    pub fn resolve<'a>(&self, vcd: &'a ValueChangeDump) -> Result<ResolvedExpression<'a>, Error> {
+        let expr = self.expr.try_map(&mut |select: &Select| {
+            let sig = vcd
+                .find_signal(&select.name)
+                .ok_or_else(|| Error::UnknownSignal(select.name.to_owned()))?;
+            let bits = match select.range {
+                Some((left, right)) => {
+                    select_bits(&sig, left, right).ok_or_else(|| Error::InvalidSelect {
+                        signal: sig.reference.to_owned(),
+                        select: select.to_string(),
+                    })?
+                }
+                None => 0..sig.size,
+            };
+            Ok(Operand {
+                vcd,
+                identifier: sig.identifier,
+                wave: OnceCell::new(),
+                size: sig.size,
+                bits,
+            })
+        })?;
+        Ok(ResolvedExpression { expr })
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::parse(s)
+    }
+}
+
+impl<'a> ResolvedExpression<'a> {
+    /// Width in bits of the value, following Verilog's rules for unsigned
+    /// operands: relational and logical results are 1 bit, shifts keep the
+    /// width of the left operand and other operators the widest one.
+    pub fn width(&self) -> usize {
+        self.expr.width()
+    }
+
+    /// Waves of the signals the expression reads.
+    pub fn waves(&self) -> Vec<&'a ValueChangeDumpWave> {
+        let mut waves = vec![];
+        self.expr
+            .for_each_operand(&mut |operand| waves.push(operand.wave()));
+        waves
+    }
+
+    /// Whether the value depends on earlier samples, through `rose`, `fell`,
+    /// `stable` or `past`.
+    pub fn has_history(&self) -> bool {
+        self.expr.has_function()
+    }
+
+    /// Bits of the value at the `index`th sample, from the most significant
+    /// one. Functions see the first sample's previous value as unknown, but
+    /// never report an edge there.
+    pub fn eval(&self, samples: &Samples, index: usize) -> String {
+        self.expr.eval(samples, index)
+    }
+}
+
+fn visit_expression(rule: Pair<Rule>) -> Result<Expression, Error> {
+    let literals = rule.clone().into_inner().flatten();
+    for literal in literals.filter(|pair| pair.as_rule() == Rule::literal) {
+        let size = match literal.as_str().split_once('\'') {
+            Some((size, _)) if !size.is_empty() => size.parse().unwrap_or(usize::MAX),
+            _ => continue,
+        };
+        if !(1..=MAX_LITERAL_WIDTH).contains(&size) {
+            return Err(Error::Syntax(format!(
+                "literal {} must be 1 to {} bits wide",
+                literal.as_str(),
+                MAX_LITERAL_WIDTH
+            )));
+        }
+    }
+    Ok(Expression {
+        text: rule.as_str().trim().to_owned(),
+        expr: visit_expr(rule),
+    })
+}
+
+/// Positions of the bits `[left:right]` of a signal, counted from its most
+/// significant bit through the declared range, e.g. `[15:8]` of `addr [15:0]`
+/// gives `0..8`. `None` if the select is outside the range or reversed.
+fn select_bits(sig: &ValueChangeDumpSignal, left: i64, right: i64) -> Option<Range<usize>> {
+    let declared = sig
+        .reference
+        .split_once(" [")
+        .and_then(|(_, range)| range.strip_suffix(']'))
+        .and_then(|range| match range.split_once(':') {
+            Some((msb, lsb)) => Some((msb.trim().parse().ok()?, lsb.trim().parse().ok()?)),
+            None => range.trim().parse().ok().map(|index: i64| (index, index)),
+        })
+        .filter(|(msb, lsb): &(i64, i64)| msb.abs_diff(*lsb) as usize + 1 == sig.size)
+        .unwrap_or((sig.size as i64 - 1, 0));
+    let position = |index: i64| {
+        let (msb, lsb) = declared;
+        let inside = (msb.min(lsb)..=msb.max(lsb)).contains(&index);
+        inside.then(|| msb.abs_diff(index) as usize)
+    };
+    let (start, end) = (position(left)?, position(right)?);
+    (start <= end).then_some(start..end + 1)
+}
+
+impl std::fmt::Display for Select {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.range {
+            Some((left, right)) if left == right => write!(f, "{}[{}]", self.name, left),
+            Some((left, right)) => write!(f, "{}[{}:{}]", self.name, left, right),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+fn visit_expr(rule: Pair<Rule>) -> Expr<Select> {
+    parse_expr(rule.into_inner())
+}
+
+fn parse_expr(pairs: Pairs<Rule>) -> Expr<Select> {
+    PRATT
+        .map_primary(|primary| match primary.as_rule() {
+            Rule::expr => visit_expr(primary),
+            Rule::select => {
+                let mut inner = primary.into_inner();
+                let name = inner.next().unwrap().as_str().to_owned();
+                let indices: Vec<i64> = inner
+                    .map(|index| index.as_str().parse().unwrap_or(i64::MAX))
+                    .collect();
+                let range = indices
+                    .first()
+                    .map(|left| (*left, *indices.last().unwrap()));
+                Expr::Signal(Select { name, range })
+            }
+            Rule::literal => Expr::Literal(visit_literal(primary.as_str())),
+            Rule::concat => Expr::Concat(primary.into_inner().map(visit_expr).collect()),
+            Rule::function => {
+                let mut inner = primary.into_inner();
+                let function = match inner.next().unwrap().as_str() {
+                    "rose" => Function::Rose,
+                    "fell" => Function::Fell,
+                    "stable" => Function::Stable,
+                    _ => Function::Past,
+                };
+                Expr::Function(function, Box::new(visit_expr(inner.next().unwrap())))
+            }
+            _ => unreachable!("{:#?}", primary),
+        })
+        .map_prefix(|op, operand| {
+            let op = match op.as_rule() {
+                Rule::not => UnaryOp::Not,
+                Rule::bit_not => UnaryOp::BitNot,
+                _ => UnaryOp::Neg,
+            };
+            Expr::Unary(op, Box::new(operand))
+        })
+        .map_infix(|lhs, op, rhs| {
+            let op = match op.as_rule() {
+                Rule::or => BinaryOp::Or,
+                Rule::and => BinaryOp::And,
+                Rule::bit_or => BinaryOp::BitOr,
+                Rule::bit_xor => BinaryOp::BitXor,
+                Rule::bit_and => BinaryOp::BitAnd,
+                Rule::eq => BinaryOp::Eq,
+                Rule::ne => BinaryOp::Ne,
+                Rule::lt => BinaryOp::Lt,
+                Rule::le => BinaryOp::Le,
+                Rule::gt => BinaryOp::Gt,
+                Rule::ge => BinaryOp::Ge,
+                Rule::shl => BinaryOp::Shl,
+                Rule::shr => BinaryOp::Shr,
+                Rule::add => BinaryOp::Add,
+                Rule::sub => BinaryOp::Sub,
+                Rule::mul => BinaryOp::Mul,
+                Rule::div => BinaryOp::Div,
+                Rule::rem => BinaryOp::Rem,
+                _ => unreachable!("{:#?}", op),
+            };
+            Expr::Binary(op, Box::new(lhs), Box::new(rhs))
+        })
+        .parse(pairs)
+}
+
+/// Bits of a literal such as `4'b10x0`, `'hff`, `0x1f` or `12`. Unsized
+/// literals are as wide as their digits.
+fn visit_literal(text: &str) -> String {
+    let text = text.replace('_', "");
+    let (size, radix, digits) = if let Some(hex) = text.strip_prefix("0x") {
+        (None, 16, hex)
+    } else if let Some((size, digits)) = text.split_once('\'') {
+        let radix = match &digits[..1] {
+            "b" => 2,
+            "o" => 8,
+            "d" => 10,
+            _ => 16,
+        };
+        (size.parse().ok(), radix, &digits[1..])
+    } else {
+        (None, 10, text.as_str())
+    };
+    let bits = match radix {
+        10 => match digits.parse::<u128>() {
+            Ok(value) => format!("{value:b}"),
+            // `'dx`, `'dz` or too wide
+            Err(_) => "x".to_owned(),
+        },
+        _ => {
+            let width = match radix {
+                2 => 1,
+                8 => 3,
+                _ => 4,
+            };
+            digits
+                .chars()
+                .map(|digit| match digit.to_digit(radix) {
+                    Some(value) => format!("{value:0width$b}"),
+                    None => digit.to_string().repeat(width),
+                })
+                .collect()
+        }
+    };
+    match size {
+        Some(size) => resize(&bits, size),
+        None => bits,
+    }
+}
+
+impl<'a> Operand<'a> {
+    fn wave(&self) -> &'a ValueChangeDumpWave {
+        self.wave.get_or_init(|| self.vcd.wave(&self.identifier))
+    }
+}
+
+impl<T> Expr<T> {
+    fn try_map<U>(&self, f: &mut impl FnMut(&T) -> Result<U, Error>) -> Result<Expr<U>, Error> {
+        Ok(match self {
+            Expr::Signal(signal) => Expr::Signal(f(signal)?),
+            Expr::Literal(bits) => Expr::Literal(bits.to_owned()),
+            Expr::Unary(op, operand) => Expr::Unary(*op, Box::new(operand.try_map(f)?)),
+            Expr::Binary(op, lhs, rhs) => {
+                Expr::Binary(*op, Box::new(lhs.try_map(f)?), Box::new(rhs.try_map(f)?))
+            }
+            Expr::Concat(parts) => Expr::Concat(
+                parts
+                    .iter()
+                    .map(|part| part.try_map(f))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::Function(function, operand) => {
+                Expr::Function(*function, Box::new(operand.try_map(f)?))
+            }
+        })
+    }
+
+    fn has_function(&self) -> bool {
+        match self {
+            Expr::Signal(_) | Expr::Literal(_) => false,
+            Expr::Function(..) => true,
+            Expr::Unary(_, operand) => operand.has_function(),
+            Expr::Binary(_, lhs, rhs) => lhs.has_function() || rhs.has_function(),
+            Expr::Concat(parts) => parts.iter().any(Expr::has_function),
+        }
+    }
+
+    fn for_each_operand(&self, f: &mut impl FnMut(&T)) {
+        match self {
+            Expr::Signal(signal) => f(signal),
+            Expr::Literal(_) => {}
+            Expr::Unary(_, operand) | Expr::Function(_, operand) => operand.for_each_operand(f),
+            Expr::Binary(_, lhs, rhs) => {
+                lhs.for_each_operand(f);
+                rhs.for_each_operand(f);
+            }
+            Expr::Concat(parts) => parts.iter().for_each(|part| part.for_each_operand(f)),
+        }
+    }
+}
+
+impl Expr<Operand<'_>> {
+    fn width(&self) -> usize {
+        match self {
+            Expr::Signal(operand) => operand.bits.len(),
+            Expr::Literal(bits) => bits.len(),
+            Expr::Unary(UnaryOp::Not, _) => 1,
+            Expr::Unary(_, operand) | Expr::Function(Function::Past, operand) => operand.width(),
+            Expr::Function(..) => 1,
+            Expr::Binary(op, lhs, rhs) => match op {
+                BinaryOp::Or
+                | BinaryOp::And
+                | BinaryOp::Eq
+                | BinaryOp::Ne
+                | BinaryOp::Lt
+                | BinaryOp::Le
+                | BinaryOp::Gt
+                | BinaryOp::Ge => 1,
+                BinaryOp::Shl | BinaryOp::Shr => lhs.width(),
+                _ => lhs.width().max(rhs.width()),
+            },
+            Expr::Concat(parts) => parts.iter().map(Expr::width).sum(),
+        }
+    }
+
+    fn eval(&self, samples: &Samples, index: usize) -> String {
+        match self {
+            Expr::Signal(operand) => {
+                let time = samples.times[index];
+                let vc = if samples.before {
+                    operand.wave().previous_change(time)
+                } else {
+                    operand.wave().value_at(time)
+                };
+                let bits = match vc {
+                    Some(vc) if !vc.value.starts_with(['r', 'R']) => {
+                        let bits = extend_bits(&vc.value, operand.size);
+                        bits[bits.len() - operand.size..].to_owned()
+                    }
+                    _ => "x".repeat(operand.size),
+                };
+                bits[operand.bits.clone()].to_owned()
+            }
+            Expr::Literal(bits) => bits.to_owned(),
+            Expr::Unary(op, operand) => {
+                let bits = operand.eval(samples, index);
+                match op {
+                    UnaryOp::Not => not(truth(&bits)).to_string(),
+                    UnaryOp::BitNot => bits.chars().map(not).collect(),
+                    UnaryOp::Neg => {
+                        arithmetic(&bits, bits.len(), |value| Some(0u128.wrapping_sub(value)))
+                    }
+                }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let width = self.width().max(lhs.width()).max(rhs.width());
+                let a = resize(&lhs.eval(samples, index), width);
+                let b = resize(&rhs.eval(samples, index), width);
+                let bitwise = |f: fn(char, char) -> char| -> String {
+                    a.chars().zip(b.chars()).map(|(a, b)| f(a, b)).collect()
+                };
+                let compare = |f: fn(&String, &String) -> bool| -> String {
+                    if number(&a).is_some() && number(&b).is_some() {
+                        // Same width binary strings order like the numbers
+                        (if f(&a, &b) { "1" } else { "0" }).to_owned()
+                    } else {
+                        "x".to_owned()
+                    }
+                };
+                let bits = match op {
+                    BinaryOp::Or => or(truth(&a), truth(&b)).to_string(),
+                    BinaryOp::And => and(truth(&a), truth(&b)).to_string(),
+                    BinaryOp::BitOr => bitwise(or),
+                    BinaryOp::BitXor => bitwise(xor),
+                    BinaryOp::BitAnd => bitwise(and),
+                    BinaryOp::Eq => compare(|a, b| a == b),
+                    BinaryOp::Ne => compare(|a, b| a != b),
+                    BinaryOp::Lt => compare(|a, b| a < b),
+                    BinaryOp::Le => compare(|a, b| a <= b),
+                    BinaryOp::Gt => compare(|a, b| a > b),
+                    BinaryOp::Ge => compare(|a, b| a >= b),
+                    BinaryOp::Shl | BinaryOp::Shr => {
+                        let bits = lhs.eval(samples, index);
+                        let width = bits.len();
+                        match number(&b).map(|amount| amount.min(width as u128) as usize) {
+                            Some(amount) if *op == BinaryOp::Shl => {
+                                bits[amount..].to_owned() + &"0".repeat(amount)
+                            }
+                            Some(amount) => "0".repeat(amount) + &bits[..width - amount],
+                            None => "x".repeat(width),
+                        }
+                    }
+                    _ => {
+                        let b = number(&b);
+                        arithmetic(&a, width, |a| match op {
+                            BinaryOp::Add => Some(a.wrapping_add(b?)),
+                            BinaryOp::Sub => Some(a.wrapping_sub(b?)),
+                            BinaryOp::Mul => Some(a.wrapping_mul(b?)),
+                            BinaryOp::Div => a.checked_div(b?),
+                            _ => a.checked_rem(b?),
+                        })
+                    }
+                };
+                resize(&bits, self.width())
+            }
+            Expr::Concat(parts) => parts.iter().map(|part| part.eval(samples, index)).collect(),
+            Expr::Function(function, operand) => {
+                let now = operand.eval(samples, index);
+                let before = match index.checked_sub(1) {
+                    Some(previous) => operand.eval(samples, previous),
+                    None if *function == Function::Past => return "x".repeat(now.len()),
+                    None if *function == Function::Stable => return "x".to_owned(),
+                    None => return "0".to_owned(),
+                };
+                let edge = |level: char| lsb(&now) == level && lsb(&before) != level;
+                match function {
+                    Function::Rose => u8::from(edge('1')).to_string(),
+                    Function::Fell => u8::from(edge('0')).to_string(),
+                    Function::Stable if number(&now).is_none() || number(&before).is_none() => {
+                        "x".to_owned()
+                    }
+                    Function::Stable => u8::from(now == before).to_string(),
+                    Function::Past => before,
+                }
+            }
+        }
+    }
+}
+
+/// Bits resized to `width`, keeping the least significant ones or extending
+/// as [`extend_bits`] does.
+fn resize(bits: &str, width: usize) -> String {
+    if bits.len() > width {
+        bits[bits.len() - width..].to_owned()
+    } else {
+        extend_bits(bits, width)
+    }
+}
+
+/// The value of known bits, `None` if any bit is `x` or `z` or the value
+/// doesn't fit.
+fn number(bits: &str) -> Option<u128> {
+    let bits = bits.trim_start_matches('0');
+    if bits.is_empty() {
+        return Some(0);
+    }
+    if bits.len() > 128 || !bits.chars().all(|bit| matches!(bit, '0' | '1')) {
+        return None;
+    }
+    u128::from_str_radix(bits, 2).ok()
+}
+
+/// Apply `f` to the value of `bits`, all `x` if it or the result is unknown.
+fn arithmetic(bits: &str, width: usize, f: impl FnOnce(u128) -> Option<u128>) -> String {
+    match number(bits).and_then(f) {
+        Some(value) => resize(&format!("{value:b}"), width),
+        None => "x".repeat(width),
+    }
+}
+
+fn lsb(bits: &str) -> char {
+    bits.chars().last().unwrap_or('x')
+}
+
+/// `1` if any bit is set, `0` if all bits are clear and `x` otherwise.
+fn truth(bits: &str) -> char {
+    if bits.contains('1') {
+        '1'
+    } else if bits.chars().all(|bit| bit == '0') {
+        '0'
+    } else {
+        'x'
+    }
+}
+
+fn not(bit: char) -> char {
+    match bit {
+        '0' => '1',
+        '1' => '0',
+        _ => 'x',
+    }
+}
+
+fn and(a: char, b: char) -> char {
+    match (a, b) {
+        ('0', _) | (_, '0') => '0',
+        ('1', '1') => '1',
+        _ => 'x',
+    }
+}
+
+fn or(a: char, b: char) -> char {
+    match (a, b) {
+        ('1', _) | (_, '1') => '1',
+        ('0', '0') => '0',
+        _ => 'x',
+    }
+}
+
+fn xor(a: char, b: char) -> char {
+    match (a, b) {
+        ('0' | '1', '0' | '1') => {
+            if a == b {
+                '0'
+            } else {
+                '1'
+            }
+        }
+        _ => 'x',
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{ValueChangeDumpScope, ValueChangeDumpSignal};
+
+/// Signals to keep while parsing. Value changes of other signals are
+/// checked against the declarations but not stored, and their declarations
+/// are left out of the model together with scopes that end up empty.
+/// Without any selection every signal is kept.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Globs over signal references, e.g. `tb.dut.*` or `*.count`
+    pub signals: Vec<String>,
+    /// Identifier codes as written in the dump, e.g. `!`
+    pub identifiers: Vec<String>,
+}
+
+impl ParseOptions {
+    /// Whether some signals are selected, as opposed to all of them.
+    pub fn is_filtering(&self) -> bool {
+        !self.signals.is_empty() || !self.identifiers.is_empty()
+    }
+
+    /// Whether the signal is kept.
+    pub fn selects(&self, signal: &ValueChangeDumpSignal) -> bool {
+        !self.is_filtering()
+            || self.identifiers.contains(&signal.identifier)
+            || self.signals.iter().any(|pattern| signal.matches(pattern))
+    }
+}
+
+impl ValueChangeDumpScope {
+    /// Drop the child scopes without signals at any depth.
+    pub(crate) fn prune(&mut self) {
+        self.scopes.retain(|scope| {
+            let mut scope = scope.borrow_mut();
+            scope.prune();
+            !scope.signals.is_empty() || !scope.scopes.is_empty()
+        });
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{ValueChangeDump, ValueChangeDumpScope, ValueChangeDumpSignal};
+use std::fmt;
+
+/// Options selecting which parts of the hierarchy are listed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HierarchyOptions {
+    /// Glob patterns selecting the signals to list. Scopes without a
+    /// selected signal are left out. Every signal is listed when empty.
+    pub signals: Vec<String>,
+    /// Number of scope levels to descend into. Top level scopes are at depth 1.
+    pub depth: Option<usize>,
+}
+
+/// A scope and everything declared in it, detached from the dump so it can
+/// be printed or serialized.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct HierarchyScope {
+    pub name: String,
+    /// Scope type, e.g. `module` or `task`
+    pub kind: String,
+    pub scopes: Vec<HierarchyScope>,
+    pub signals: Vec<HierarchySignal>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct HierarchySignal {
+    /// Name without the scope hierarchy or bit select, e.g. `count`
+    pub name: String,
+    /// Fully qualified reference, e.g. `Counter.count [3:0]`
+    pub reference: String,
+    /// Var type, e.g. `wire` or `reg`
+    pub kind: String,
+    pub size: usize,
+    /// Bit select following the name, e.g. `[3:0]`
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub range: Option<String>,
+    pub identifier: String,
+}
+
+impl HierarchyScope {
+    /// The scopes and signals of `vcd` under an unnamed root scope.
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &HierarchyOptions) -> Self {
+        let root = vcd.root_scope.borrow();
+        Self::from_scope(&root, options, 0).unwrap_or_default()
+    }
+
+    fn from_scope(
+        scope: &ValueChangeDumpScope,
+        options: &HierarchyOptions,
+        depth: usize,
+    ) -> Option<Self> {
+        let scopes = if options.depth.is_none_or(|max| depth < max) {
+            scope
+                .scopes
+                .iter()
+                .filter_map(|child| Self::from_scope(&child.borrow(), options, depth + 1))
+                .collect()
+        } else {
+            vec![]
+        };
+        let signals: Vec<HierarchySignal> = scope
+            .signals
+            .iter()
+            .filter(|sig| {
+                options.signals.is_empty() || options.signals.iter().any(|p| sig.matches(p))
+            })
+            .map(HierarchySignal::from)
+            .collect();
+
+        let filtered = !options.signals.is_empty();
+        if depth > 0 && filtered && scopes.is_empty() && signals.is_empty() {
+            return None;
+        }
+        Some(HierarchyScope {
+            name: scope.name.to_owned(),
+            kind: scope.kind.to_owned(),
+            scopes,
+            signals,
+        })
+    }
+
+    fn write_children(&self, f: &mut fmt::Formatter<'_>, indent: &str) -> fmt::Result {
+        let count = self.scopes.len() + self.signals.len();
+        let mut index = 0;
+        let mut branch = |f: &mut fmt::Formatter<'_>| {
+            index += 1;
+            let last = index == count;
+            write!(f, "{}{}", indent, if last { "└── " } else { "├── " })?;
+            Ok::<_, fmt::Error>(format!("{}{}", indent, if last { "    " } else { "│   " }))
+        };
+        for scope in &self.scopes {
+            let child_indent = branch(f)?;
+            writeln!(f, "{} ({})", scope.name, scope.kind)?;
+            scope.write_children(f, &child_indent)?;
+        }
+        for sig in &self.signals {
+            branch(f)?;
+            writeln!(f, "{sig}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&ValueChangeDumpSignal> for HierarchySignal {
+    fn from(sig: &ValueChangeDumpSignal) -> Self {
+        let (name, range) = match sig.short_reference().split_once(' ') {
+            Some((name, range)) => (name, Some(range.trim().to_owned())),
+            None => (sig.short_reference(), None),
+        };
+        HierarchySignal {
+            name: name.to_owned(),
+            reference: sig.reference.to_owned(),
+            kind: sig.kind.to_owned(),
+            size: sig.size,
+            range,
+            identifier: sig.identifier.to_owned(),
+        }
+    }
+}
+
+impl fmt::Display for HierarchySignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if let Some(range) = &self.range {
+            write!(f, " {range}")?;
+        }
+        write!(f, "  {} {} {}", self.kind, self.size, self.identifier)
+    }
+}
+
+/// Draws the hierarchy as a tree, scopes before signals.
+impl fmt::Display for HierarchyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.name.is_empty() {
+            writeln!(f, "{} ({})", self.name, self.kind)?;
+            return self.write_children(f, "");
+        }
+        // The unnamed root only holds the top level scopes
+        for scope in &self.scopes {
+            write!(f, "{scope}")?;
+        }
+        for sig in &self.signals {
+            writeln!(f, "{sig}")?;
+        }
+        Ok(())
+    }
+}
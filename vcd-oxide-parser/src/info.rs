@@ -0,0 +1,140 @@
+use crate::{ValueChangeDump, ValueChangeDumpScope};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+/// An overview of a dump's header, hierarchy and activity.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct DumpInfo {
+    pub date: String,
+    pub version: String,
+    pub timescale: String,
+    /// Time of the last value change
+    pub end_time: usize,
+    pub scopes: usize,
+    pub signals: usize,
+    /// Signals sharing their identifier code with a previously declared signal
+    pub aliases: usize,
+    /// Number of signals declared with each var type, e.g. `wire` or `reg`
+    pub var_types: BTreeMap<String, usize>,
+    pub value_changes: usize,
+    /// Signals with the most value changes, most active first
+    pub most_active: Vec<SignalActivity>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct SignalActivity {
+    pub reference: String,
+    pub value_changes: usize,
+}
+
+impl DumpInfo {
+    /// Summarise `vcd`, listing the `top` most active signals.
+    /// Aliased signals are reported once, under the first declared reference.
+    pub fn from_vcd(vcd: &ValueChangeDump, top: usize) -> Self {
+        let signals = vcd.signals();
+
+        let mut var_types = BTreeMap::new();
+        for sig in &signals {
+            *var_types.entry(sig.kind.to_owned()).or_insert(0) += 1;
+        }
+
+        let mut identifiers = HashSet::new();
+        let mut most_active: Vec<SignalActivity> = signals
+            .iter()
+            .filter(|sig| identifiers.insert(sig.identifier.as_str()))
+            .map(|sig| SignalActivity {
+                reference: sig.reference.to_owned(),
+                value_changes: vcd.wave(&sig.identifier).value_changes.len(),
+            })
+            .collect();
+        // Stable sort keeps declaration order between equally active signals
+        most_active.sort_by_key(|activity| Reverse(activity.value_changes));
+        most_active.truncate(top);
+
+        DumpInfo {
+            date: vcd.date.trim().to_owned(),
+            version: vcd.version.trim().to_owned(),
+            timescale: vcd.timescale.to_owned(),
+            end_time: vcd.end_time(),
+            scopes: count_scopes(&vcd.root_scope.borrow()),
+            signals: signals.len(),
+            aliases: signals.len() - identifiers.len(),
+            var_types,
+            value_changes: vcd
+                .waves()
+                .values()
+                .map(|wave| wave.value_changes.len())
+                .sum(),
+            most_active,
+        }
+    }
+}
+
+impl ValueChangeDump {
+    /// Summarise the dump, listing the `top` most active signals.
+    pub fn info(&self, top: usize) -> DumpInfo {
+        DumpInfo::from_vcd(self, top)
+    }
+}
+
+fn count_scopes(scope: &ValueChangeDumpScope) -> usize {
+    scope
+        .scopes
+        .iter()
+        .map(|child| 1 + count_scopes(&child.borrow()))
+        .sum()
+}
+
+impl fmt::Display for DumpInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "date:          {}", self.date)?;
+        writeln!(f, "version:       {}", self.version)?;
+        writeln!(f, "timescale:     {}", self.timescale)?;
+        writeln!(f, "end time:      {}", self.end_time)?;
+        writeln!(f, "scopes:        {}", self.scopes)?;
+        writeln!(f, "signals:       {}", self.signals)?;
+        writeln!(f, "aliases:       {}", self.aliases)?;
+        writeln!(f, "value changes: {}", self.value_changes)?;
+
+        if !self.var_types.is_empty() {
+            writeln!(f, "\nvar types:")?;
+            let width = self.var_types.keys().map(|ty| ty.len()).max().unwrap_or(0);
+            for (ty, count) in &self.var_types {
+                writeln!(f, "  {ty:<width$}  {count}")?;
+            }
+        }
+
+        if !self.most_active.is_empty() {
+            writeln!(f, "\nmost active:")?;
+            let width = self
+                .most_active
+                .iter()
+                .map(|activity| activity.value_changes.to_string().len())
+                .max()
+                .unwrap_or(0);
+            for activity in &self.most_active {
+                writeln!(
+                    f,
+                    "  {:>width$}  {}",
+                    activity.value_changes,
+                    activity.reference,
+                    width = width
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
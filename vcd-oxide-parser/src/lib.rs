@@ -1,103 +1,93 @@
 mod ast;
+mod compression;
+mod derive;
+mod diff;
+mod error;
+mod expression;
+mod filter;
+mod glob;
+mod hierarchy;
+mod info;
+mod measure;
+mod merge;
 mod model;
+mod radix;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod timescale;
+mod writer;
 
 extern crate pest;
 extern crate pest_derive;
 
+pub use crate::compression::*;
+pub use crate::derive::*;
+pub use crate::diff::*;
+pub use crate::error::*;
+pub use crate::expression::*;
+pub use crate::filter::*;
+pub use crate::glob::*;
+pub use crate::hierarchy::*;
+pub use crate::info::*;
+pub use crate::measure::*;
+pub use crate::merge::*;
 pub use crate::model::*;
+pub use crate::radix::*;
+pub use crate::timescale::*;
 
 use crate::ast::*;
-use pest::{iterators::Pair, Parser};
+use pest::{
+    error::{ErrorVariant, InputLocation},
+    iterators::Pair,
+    Parser, Position, Span,
+};
 use pest_derive::Parser;
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashSet},
+    rc::Rc,
+};
 
 #[derive(Parser, Debug)]
 #[grammar = "grammar/vcd.pest"]
 struct ValueChangeDumpParser;
 
-fn visit_value_change_dump_definitions(rule: Pair<Rule>) -> ValueChangeDumpDefinition {
-    let mut declaration_commands = vec![];
-    let mut simulation_commands = vec![];
+/// A failure to read a command that follows the grammar, such as a number
+/// too large for a `usize`.
+type VisitError = pest::error::Error<Rule>;
 
-    for item in rule.into_inner() {
-        match item.as_rule() {
-            Rule::declaration_command => {
-                let declaration_command = visit_declaration_command(item);
-                declaration_commands.push(declaration_command);
-            }
-            Rule::simulation_command => {
-                let simulation_command = visit_simulation_command(item);
-                simulation_commands.push(simulation_command);
-            }
-            _ => unreachable!("{:#?}", item),
-        };
-    }
-
-    ValueChangeDumpDefinition {
-        declaration_commands,
-        simulation_commands,
-    }
-}
-
-fn visit_simulation_command(rule: Pair<Rule>) -> SimulationCommand {
+fn visit_simulation_command(rule: Pair<Rule>) -> Result<SimulationCommand, VisitError> {
     let inner = rule.into_inner().next().unwrap();
-    match inner.as_rule() {
-        Rule::vcd_simulation_dumpall => visit_vcd_simulation_dumpall(inner),
-        Rule::vcd_simulation_dumpoff => visit_vcd_simulation_dumpoff(inner),
-        Rule::vcd_simulation_dumpon => visit_vcd_simulation_dumpon(inner),
-        Rule::vcd_simulation_dumpvars => visit_vcd_simulation_dumpvars(inner),
-        Rule::simulation_keyword_comment => visit_simulation_keyword_comment(inner),
-        Rule::simulation_time => visit_simulation_time(inner),
+    Ok(match inner.as_rule() {
+        Rule::vcd_simulation_dumpall
+        | Rule::vcd_simulation_dumpoff
+        | Rule::vcd_simulation_dumpon
+        | Rule::vcd_simulation_dumpvars => visit_simulation_keyword_command(inner),
+        Rule::simulation_keyword_comment => SimulationCommand::Comment,
+        Rule::simulation_time => visit_simulation_time(inner)?,
         Rule::value_change => SimulationCommand::ValueChange(visit_value_change(inner)),
         _ => unreachable!("{:#?}", inner),
-    }
-}
-
-fn visit_vcd_simulation_dumpall(rule: Pair<Rule>) -> SimulationCommand {
-    SimulationCommand::KeywordCommand(SimulationKeywordCommand {
-        ty: SimulationType::DumpAll,
-        value_changes: visit_value_change_list(rule),
     })
 }
 
-fn visit_vcd_simulation_dumpoff(rule: Pair<Rule>) -> SimulationCommand {
+fn visit_simulation_keyword_command(rule: Pair<Rule>) -> SimulationCommand {
     SimulationCommand::KeywordCommand(SimulationKeywordCommand {
-        ty: SimulationType::DumpOff,
         value_changes: visit_value_change_list(rule),
     })
 }
 
-fn visit_vcd_simulation_dumpon(rule: Pair<Rule>) -> SimulationCommand {
-    SimulationCommand::KeywordCommand(SimulationKeywordCommand {
-        ty: SimulationType::DumpOn,
-        value_changes: visit_value_change_list(rule),
-    })
+fn visit_simulation_time(rule: Pair<Rule>) -> Result<SimulationCommand, VisitError> {
+    let value = visit_number(rule.into_inner().next().unwrap())?;
+    Ok(SimulationCommand::SimulationTime(SimulationTime { value }))
 }
 
-fn visit_vcd_simulation_dumpvars(rule: Pair<Rule>) -> SimulationCommand {
-    SimulationCommand::KeywordCommand(SimulationKeywordCommand {
-        ty: SimulationType::DumpVars,
-        value_changes: visit_value_change_list(rule),
+fn visit_number(rule: Pair<Rule>) -> Result<usize, VisitError> {
+    rule.as_str().parse().map_err(|_| {
+        let message = "number too large".to_owned();
+        VisitError::new_from_span(ErrorVariant::CustomError { message }, rule.as_span())
     })
 }
 
-fn visit_simulation_keyword_comment(rule: Pair<Rule>) -> SimulationCommand {
-    SimulationCommand::Comment(GenericComment {
-        value: rule.as_str().to_owned(),
-    })
-}
-
-fn visit_simulation_time(rule: Pair<Rule>) -> SimulationCommand {
-    let value = rule
-        .into_inner()
-        .next()
-        .unwrap()
-        .as_str()
-        .parse::<usize>()
-        .unwrap();
-    SimulationCommand::SimulationTime(SimulationTime { value })
-}
-
 fn visit_value_change_list(rule: Pair<Rule>) -> Vec<SimulationValueChange> {
     let inner = rule.into_inner();
     let mut value_changes = vec![];
@@ -143,27 +133,25 @@ fn visit_vector_value_change(rule: Pair<Rule>) -> SimulationValueChange {
     })
 }
 
-fn visit_declaration_command(rule: Pair<Rule>) -> DeclarationCommand {
+fn visit_declaration_command(rule: Pair<Rule>) -> Result<DeclarationCommand, VisitError> {
     let inner = rule.into_inner().next().unwrap();
-    match inner.as_rule() {
-        Rule::vcd_declaration_vars => DeclarationCommand::Var(visit_vcd_declaration_vars(inner)),
-        Rule::vcd_declaration_comment => {
-            DeclarationCommand::Comment(visit_vcd_declaration_comment(inner))
-        }
+    Ok(match inner.as_rule() {
+        Rule::vcd_declaration_vars => DeclarationCommand::Var(visit_vcd_declaration_vars(inner)?),
+        Rule::vcd_declaration_comment => DeclarationCommand::Comment,
         Rule::vcd_declaration_date => DeclarationCommand::Date(visit_vcd_declaration_date(inner)),
         Rule::vcd_declaration_enddefinitions => DeclarationCommand::EndDefinitions,
         Rule::vcd_declaration_scope => {
             DeclarationCommand::Scope(visit_vcd_declaration_scope(inner))
         }
         Rule::vcd_declaration_timescale => {
-            DeclarationCommand::Timescale(visit_vcd_declaration_timescale(inner))
+            DeclarationCommand::Timescale(visit_vcd_declaration_timescale(inner)?)
         }
         Rule::vcd_declaration_upscope => DeclarationCommand::Upscope,
         Rule::vcd_declaration_version => {
             DeclarationCommand::Version(visit_vcd_declaration_version(inner))
         }
         _ => unreachable!("{:#?}", inner),
-    }
+    })
 }
 
 fn visit_vcd_declaration_date(rule: Pair<Rule>) -> DeclarationDate {
@@ -180,15 +168,15 @@ fn visit_vcd_declaration_version(rule: Pair<Rule>) -> DeclarationVersion {
     }
 }
 
-fn visit_vcd_declaration_timescale(rule: Pair<Rule>) -> DeclarationTimescale {
+fn visit_vcd_declaration_timescale(rule: Pair<Rule>) -> Result<DeclarationTimescale, VisitError> {
     let mut inner = rule.into_inner();
-    let time_number = inner.next().unwrap().as_str().parse::<usize>().unwrap();
+    let time_number = visit_number(inner.next().unwrap())?;
     let time_unit = inner.next().unwrap().as_str().to_owned();
 
-    DeclarationTimescale {
+    Ok(DeclarationTimescale {
         time_number,
         time_unit,
-    }
+    })
 }
 
 fn visit_vcd_declaration_scope(rule: Pair<Rule>) -> DeclarationScope {
@@ -202,140 +190,335 @@ fn visit_vcd_declaration_scope(rule: Pair<Rule>) -> DeclarationScope {
     }
 }
 
-fn visit_vcd_declaration_vars(rule: Pair<Rule>) -> DeclarationVar {
+fn visit_vcd_declaration_vars(rule: Pair<Rule>) -> Result<DeclarationVar, VisitError> {
     let mut inner = rule.into_inner();
     let var_type = inner.next().unwrap().as_str().to_owned();
-    let size = inner.next().unwrap().as_str().parse::<usize>().unwrap();
+    let size = visit_number(inner.next().unwrap())?;
     let identifier_code = inner.next().unwrap().as_str().to_owned();
     let reference = inner.next().unwrap().as_str().to_owned();
 
-    DeclarationVar {
+    Ok(DeclarationVar {
         var_type,
         size,
         identifier_code,
         reference,
+    })
+}
+
+/// The commands of a dump, parsed one at a time so that only the model
+/// grows with the size of the file.
+struct Commands<'i> {
+    input: &'i str,
+    /// Offset of the last command returned, or of the one failing to parse
+    start: usize,
+    /// Offset of the next command
+    position: usize,
+}
+
+impl<'i> Commands<'i> {
+    fn new(input: &'i str) -> Self {
+        Commands {
+            input,
+            start: 0,
+            position: 0,
+        }
+    }
+
+    /// A syntax error in the last command, placed in the whole input.
+    fn syntax_error(&self, error: VisitError) -> Error {
+        let error = match error.location {
+            InputLocation::Pos(offset) => {
+                let position = Position::new(self.input, self.start + offset).unwrap();
+                VisitError::new_from_pos(error.variant, position)
+            }
+            InputLocation::Span((start, end)) => {
+                let span = Span::new(self.input, self.start + start, self.start + end).unwrap();
+                VisitError::new_from_span(error.variant, span)
+            }
+        };
+        Error::Syntax(error.to_string())
     }
 }
 
-fn visit_vcd_declaration_comment(rule: Pair<Rule>) -> GenericComment {
-    let inner = rule.into_inner().next().unwrap();
-    GenericComment {
-        value: inner.as_str().to_owned(),
+impl<'i> Iterator for Commands<'i> {
+    type Item = Result<Pair<'i, Rule>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.input[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\r', '\n']).len();
+        if self.position == self.input.len() {
+            return None;
+        }
+        self.start = self.position;
+        match ValueChangeDumpParser::parse(Rule::command, &self.input[self.position..]) {
+            Ok(mut pairs) => {
+                let command = pairs.next().unwrap();
+                self.position += command.as_span().end();
+                Some(Ok(command))
+            }
+            Err(error) => {
+                let error = self.syntax_error(error);
+                self.position = self.input.len();
+                Some(Err(error))
+            }
+        }
     }
 }
 
-fn parse(input: &str) -> ValueChangeDumpDefinition {
-    let mut root = ValueChangeDumpParser::parse(Rule::file, input).unwrap();
-    let inner = root.next().unwrap();
-    match inner.as_rule() {
-        Rule::value_change_dump_definitions => visit_value_change_dump_definitions(inner),
-        _ => unreachable!("{:#?}", inner),
+/// The identifier code of a simulation command that is a single value
+/// change, read without building the change.
+fn value_change_identifier<'i>(rule: &Pair<'i, Rule>) -> Option<&'i str> {
+    let inner = rule.clone().into_inner().next()?;
+    if inner.as_rule() != Rule::value_change {
+        return None;
     }
+    let change = inner.into_inner().next()?;
+    change.into_inner().nth(1).map(|code| code.as_str())
 }
 
 impl ValueChangeDump {
-    pub fn parse(input: &str) -> Self {
-        let def = parse(input);
-        Self::from_definition(def)
-    }
-
-    fn from_definition(definition: ValueChangeDumpDefinition) -> Self {
-        let mut dump = ValueChangeDump::default();
-        let mut active_scope = dump.root_scope.clone();
-        for declaration in definition.declaration_commands {
-            match declaration {
-                DeclarationCommand::Comment(_) => {
-                    // Ignore
-                }
-                DeclarationCommand::EndDefinitions => {
-                    // Ignore
-                }
-                DeclarationCommand::Date(date) => dump.date = date.value,
-                DeclarationCommand::Timescale(timescale) => {
-                    dump.timescale = format!("{}{}", timescale.time_number, timescale.time_unit)
-                }
-                DeclarationCommand::Scope(scope) => {
-                    let scope = Rc::<RefCell<ValueChangeDumpScope>>::new(RefCell::new(
-                        ValueChangeDumpScope {
-                            name: scope.scope_identifier,
-                            parent: Some(Rc::<RefCell<ValueChangeDumpScope>>::downgrade(
-                                &active_scope,
-                            )),
-                            ..Default::default()
-                        },
-                    ));
-                    active_scope.borrow_mut().scopes.push(scope.clone());
-                    active_scope = scope;
-                }
-                DeclarationCommand::Upscope => {
-                    active_scope = active_scope
-                        .clone()
-                        .borrow()
-                        .parent
-                        .as_ref()
-                        .unwrap()
-                        .upgrade()
-                        .unwrap();
+    pub fn parse(input: &str) -> Result<Self, Error> {
+        Self::parse_with(input, &ParseOptions::default())
+    }
+
+    /// Parse a dump keeping only the signals selected by `options`. The whole
+    /// file is still checked, including that every value change refers to a
+    /// declared signal, but commands are parsed one at a time, so memory use
+    /// follows the signals kept rather than the size of the file.
+    pub fn parse_with(input: &str, options: &ParseOptions) -> Result<Self, Error> {
+        let mut builder = DumpBuilder::new(options);
+        let mut commands = Commands::new(input);
+        while let Some(item) = commands.next() {
+            let item = item?;
+            match item.as_rule() {
+                Rule::declaration_command => {
+                    let command =
+                        visit_declaration_command(item).map_err(|e| commands.syntax_error(e))?;
+                    builder.declaration(command)?;
                 }
-                DeclarationCommand::Var(var) => {
-                    let signal_id = var.identifier_code;
-                    let mut qualifiers = active_scope.borrow().reference_hierarchy();
-                    qualifiers.push(var.reference.trim().to_owned());
-
-                    active_scope
-                        .borrow_mut()
-                        .signals
-                        .push(ValueChangeDumpSignal {
-                            kind: var.var_type.to_owned(),
-                            identifier: signal_id.to_owned(),
-                            reference: qualifiers.join("."),
-                            size: var.size
-                        });
-
-                    dump.wave_map
-                        .insert(signal_id.to_owned(), ValueChangeDumpWave::default());
+                Rule::simulation_command => {
+                    if let Some(identifier) = value_change_identifier(&item) {
+                        if !builder.keeps(identifier)? {
+                            continue;
+                        }
+                    }
+                    let command =
+                        visit_simulation_command(item).map_err(|e| commands.syntax_error(e))?;
+                    builder.simulation(command)?;
                 }
-                DeclarationCommand::Version(version) => dump.version = version.value,
-            }
+                _ => unreachable!("{:#?}", item),
+            };
+        }
+        Ok(builder.finish())
+    }
+
+    fn wave_mut(&mut self, identifier_code: &str) -> Result<&mut ValueChangeDumpWave, Error> {
+        self.wave_map
+            .get_mut(identifier_code)
+            .ok_or_else(|| Error::UnknownIdentifier(identifier_code.to_owned()))
+    }
+
+    /// Time the dump ends at, its last `#time` or the last value change if
+    /// that is later.
+    pub fn end_time(&self) -> usize {
+        self.wave_map
+            .values()
+            .filter_map(|wave| wave.value_changes.last())
+            .map(|value_change| value_change.time)
+            .fold(self.last_time, usize::max)
+    }
+
+    pub fn signals(&self) -> Vec<ValueChangeDumpSignal> {
+        self.root_scope.borrow().all_signals()
+    }
+
+    /// The value changes of the signals with identifier code `identifier`,
+    /// computing the wave of a derived signal the first time it's read.
+    ///
+    /// # Panics
+    /// If no wave has the identifier code.
+    pub fn wave(&self, identifier: &str) -> &ValueChangeDumpWave {
+        if let Some(wave) = self.wave_map.get(identifier) {
+            return wave;
+        }
+        let derived = &self.derived_waves[identifier];
+        derived.wave.get_or_init(|| {
+            // The expression was checked against the dump when it was added
+            derived.signal.evaluate(self).unwrap()
+        })
+    }
+
+    /// Every wave by identifier code, including those of derived signals.
+    pub fn waves(&self) -> BTreeMap<&str, &ValueChangeDumpWave> {
+        let identifiers = self.wave_map.keys().chain(self.derived_waves.keys());
+        identifiers
+            .map(|identifier| (identifier.as_str(), self.wave(identifier)))
+            .collect()
+    }
+
+    /// The signal a name refers to, looked up as written first and then in
+    /// any scope, so `valid` finds `tb.dut.valid`. Names may be globs.
+    pub fn find_signal(&self, name: &str) -> Option<ValueChangeDumpSignal> {
+        let mut signals = self.signals();
+        let pattern = format!("*.{name}");
+        let index = signals
+            .iter()
+            .position(|sig| sig.matches(name))
+            .or_else(|| signals.iter().position(|sig| sig.matches(&pattern)))?;
+        Some(signals.swap_remove(index))
+    }
+}
+
+/// Builds the model one command at a time while parsing.
+struct DumpBuilder<'a> {
+    options: &'a ParseOptions,
+    dump: ValueChangeDump,
+    active_scope: Rc<RefCell<ValueChangeDumpScope>>,
+    /// Identifier codes of the signals left out, to tell them from undeclared ones
+    skipped: HashSet<String>,
+    simulation_time: usize,
+}
+
+impl<'a> DumpBuilder<'a> {
+    fn new(options: &'a ParseOptions) -> Self {
+        let dump = ValueChangeDump::default();
+        let active_scope = dump.root_scope.clone();
+        DumpBuilder {
+            options,
+            dump,
+            active_scope,
+            skipped: HashSet::new(),
+            simulation_time: 0,
         }
+    }
 
-        let mut simulation_time = 0;
-        for sim in definition.simulation_commands {
-            match sim {
-                SimulationCommand::KeywordCommand(command) => {
-                    for elem in &command.value_changes {
-                        dump.wave_map
-                            .get_mut(&elem.identifier_code())
-                            .unwrap()
-                            .value_changes
-                            .push(ValueChange {
-                                value: elem.value(),
-                                time: simulation_time,
-                            });
+    fn declaration(&mut self, declaration: DeclarationCommand) -> Result<(), Error> {
+        match declaration {
+            DeclarationCommand::Comment => {
+                // Ignore
+            }
+            DeclarationCommand::EndDefinitions => {
+                // Ignore
+            }
+            DeclarationCommand::Date(date) => self.dump.date = date.value,
+            DeclarationCommand::Timescale(timescale) => {
+                self.dump.timescale = format!("{}{}", timescale.time_number, timescale.time_unit)
+            }
+            DeclarationCommand::Scope(scope) => {
+                let scope =
+                    Rc::<RefCell<ValueChangeDumpScope>>::new(RefCell::new(ValueChangeDumpScope {
+                        name: scope.scope_identifier,
+                        kind: scope.scope_type,
+                        parent: Some(Rc::<RefCell<ValueChangeDumpScope>>::downgrade(
+                            &self.active_scope,
+                        )),
+                        ..Default::default()
+                    }));
+                self.active_scope.borrow_mut().scopes.push(scope.clone());
+                self.active_scope = scope;
+            }
+            DeclarationCommand::Upscope => {
+                self.active_scope = self
+                    .active_scope
+                    .clone()
+                    .borrow()
+                    .parent
+                    .as_ref()
+                    .and_then(|parent| parent.upgrade())
+                    .ok_or(Error::UnbalancedScope)?;
+            }
+            DeclarationCommand::Var(var) => {
+                let signal_id = var.identifier_code;
+                let mut qualifiers = self.active_scope.borrow().reference_hierarchy();
+                qualifiers.push(var.reference.trim().to_owned());
+                let signal = ValueChangeDumpSignal {
+                    kind: var.var_type.to_owned(),
+                    identifier: signal_id.to_owned(),
+                    reference: qualifiers.join("."),
+                    size: var.size,
+                };
+
+                if !self.options.selects(&signal) {
+                    if !self.dump.wave_map.contains_key(&signal_id) {
+                        self.skipped.insert(signal_id);
                     }
+                    return Ok(());
                 }
-                SimulationCommand::Comment(_) => {
-                    // Ignore
-                }
-                SimulationCommand::SimulationTime(time) => simulation_time = time.value,
-                SimulationCommand::ValueChange(value_change) => {
-                    dump.wave_map
-                        .get_mut(&value_change.identifier_code())
-                        .unwrap()
-                        .value_changes
-                        .push(ValueChange {
-                            value: value_change.value(),
-                            time: simulation_time,
-                        });
+                // An alias of a left out signal is selected, so its changes are kept after all
+                self.skipped.remove(&signal_id);
+                self.active_scope.borrow_mut().signals.push(signal);
+                self.dump.wave_map.entry(signal_id).or_default();
+            }
+            DeclarationCommand::Version(version) => self.dump.version = version.value,
+        }
+        Ok(())
+    }
+
+    fn simulation(&mut self, sim: SimulationCommand) -> Result<(), Error> {
+        match sim {
+            SimulationCommand::KeywordCommand(command) => {
+                for elem in &command.value_changes {
+                    self.value_change(elem)?;
                 }
             }
+            SimulationCommand::Comment => {
+                // Ignore
+            }
+            SimulationCommand::SimulationTime(time) => {
+                self.simulation_time = time.value;
+                self.dump.last_time = self.dump.last_time.max(time.value);
+            }
+            SimulationCommand::ValueChange(value_change) => self.value_change(&value_change)?,
+        }
+        Ok(())
+    }
+
+    fn value_change(&mut self, value_change: &SimulationValueChange) -> Result<(), Error> {
+        let identifier = value_change.identifier_code();
+        if !self.keeps(&identifier)? {
+            return Ok(());
         }
+        let time = self.simulation_time;
+        self.dump
+            .wave_mut(&identifier)?
+            .value_changes
+            .push(ValueChange {
+                value: value_change.value(),
+                time,
+            });
+        Ok(())
+    }
 
-        dump
+    /// Whether the changes of a signal are stored, failing for undeclared signals.
+    fn keeps(&self, identifier: &str) -> Result<bool, Error> {
+        if self.skipped.contains(identifier) {
+            Ok(false)
+        } else if self.dump.wave_map.contains_key(identifier) {
+            Ok(true)
+        } else {
+            Err(Error::UnknownIdentifier(identifier.to_owned()))
+        }
     }
 
-    pub fn signals(&self) -> Vec<ValueChangeDumpSignal> {
-        self.root_scope.borrow_mut().all_signals()
+    fn finish(self) -> ValueChangeDump {
+        if self.options.is_filtering() {
+            self.dump.root_scope.borrow_mut().prune();
+        }
+        self.dump
+    }
+}
+
+/// The `index`th identifier code, counting through the printable ASCII
+/// characters `!` to `~` and then through longer codes, as used for
+/// signals that get new identifier codes.
+pub fn identifier_code(mut index: usize) -> String {
+    let mut identifier = String::new();
+    loop {
+        identifier.push((b'!' + (index % 94) as u8) as char);
+        if index < 94 {
+            return identifier;
+        }
+        index = index / 94 - 1;
     }
 }
 
@@ -354,16 +537,102 @@ impl ValueChangeDumpScope {
         refs
     }
 
-    fn all_signals(&mut self) -> Vec<ValueChangeDumpSignal> {
+    fn all_signals(&self) -> Vec<ValueChangeDumpSignal> {
         let mut signals = vec![];
         for scope in &self.scopes {
-            signals.append(&mut scope.borrow_mut().all_signals());
+            signals.append(&mut scope.borrow().all_signals());
         }
-        signals.append(&mut self.signals);
+        signals.extend(self.signals.iter().cloned());
         signals
     }
 }
 
+impl ValueChangeDumpSignal {
+    /// The signal reference without the scope hierarchy, e.g. `count [3:0]`.
+    pub fn short_reference(&self) -> &str {
+        self.reference
+            .rsplit_once('.')
+            .map_or(self.reference.as_str(), |(_, name)| name)
+    }
+
+    /// The hierarchy of scopes containing the signal, e.g. `Counter`.
+    pub fn scope_reference(&self) -> &str {
+        self.reference
+            .rsplit_once('.')
+            .map_or("", |(scope, _)| scope)
+    }
+
+    /// Whether the reference matches the glob `pattern` either as written
+    /// or without its bit select, so `*.count` matches `Counter.count [3:0]`.
+    pub fn matches(&self, pattern: &str) -> bool {
+        let reference = self.reference.as_str();
+        let name = reference
+            .split_once(" [")
+            .map_or(reference, |(name, _)| name);
+        glob_match(pattern, reference) || glob_match(pattern, name)
+    }
+}
+
+impl ValueChangeDumpWave {
+    /// The value change in effect at `time`, if the signal has been assigned by then.
+    pub fn value_at(&self, time: usize) -> Option<&ValueChange> {
+        let index = self
+            .value_changes
+            .partition_point(|value_change| value_change.time <= time);
+        index.checked_sub(1).map(|index| &self.value_changes[index])
+    }
+
+    /// The first value change strictly after `time`.
+    pub fn next_change(&self, time: usize) -> Option<&ValueChange> {
+        let index = self
+            .value_changes
+            .partition_point(|value_change| value_change.time <= time);
+        self.value_changes.get(index)
+    }
+
+    /// The last value change strictly before `time`.
+    pub fn previous_change(&self, time: usize) -> Option<&ValueChange> {
+        let index = self
+            .value_changes
+            .partition_point(|value_change| value_change.time < time);
+        index.checked_sub(1).map(|index| &self.value_changes[index])
+    }
+
+    /// Logic level at `time`, `None` for `x`, `z` or before the first change.
+    pub fn level_at(&self, time: usize) -> Option<bool> {
+        self.value_at(time).and_then(|vc| level(&vc.value))
+    }
+
+    /// Logic level just before `time`, as seen by a register clocked at `time`.
+    pub fn level_before(&self, time: usize) -> Option<bool> {
+        self.previous_change(time).and_then(|vc| level(&vc.value))
+    }
+
+    /// Times at which the level rises from 0 to 1, or falls from 1 to 0 when
+    /// `rising` is false. Changes from or to `x` and `z` are not edges.
+    pub fn edges(&self, rising: bool) -> impl Iterator<Item = usize> + '_ {
+        self.value_changes
+            .windows(2)
+            .filter(move |pair| {
+                level(&pair[0].value) == Some(!rising) && level(&pair[1].value) == Some(rising)
+            })
+            .map(|pair| pair[1].time)
+    }
+}
+
+/// Logic level of a value, taken from the least significant bit of vectors.
+/// `x`, `z` and reals have none.
+pub fn level(value: &str) -> Option<bool> {
+    if value.starts_with(['r', 'R']) {
+        return None;
+    }
+    match value.chars().last() {
+        Some('1') => Some(true),
+        Some('0') => Some(false),
+        _ => None,
+    }
+}
+
 impl SimulationValueChange {
     fn identifier_code(&self) -> String {
         let s = match self {
@@ -391,35 +660,389 @@ impl SimulationValueChange {
 #[cfg(test)]
 mod test {
     use super::*;
-    use insta::assert_debug_snapshot;
+    use insta::{assert_debug_snapshot, assert_json_snapshot, assert_snapshot};
+
+    /// The commands of a dump as parsed, before they're built into the model.
+    /// Only read through the debug snapshots.
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    struct ValueChangeDumpDefinition {
+        declaration_commands: Vec<DeclarationCommand>,
+        simulation_commands: Vec<SimulationCommand>,
+    }
+
+    fn parse(input: &str) -> Result<ValueChangeDumpDefinition, Error> {
+        let mut declaration_commands = vec![];
+        let mut simulation_commands = vec![];
+        let mut commands = Commands::new(input);
+        while let Some(item) = commands.next() {
+            let item = item?;
+            match item.as_rule() {
+                Rule::declaration_command => declaration_commands
+                    .push(visit_declaration_command(item).map_err(|e| commands.syntax_error(e))?),
+                _ => simulation_commands
+                    .push(visit_simulation_command(item).map_err(|e| commands.syntax_error(e))?),
+            }
+        }
+        Ok(ValueChangeDumpDefinition {
+            declaration_commands,
+            simulation_commands,
+        })
+    }
 
     #[test]
     fn test_vcd_declaration_command() {
         let declerations = include_str!("../test/declaration_command.vcd.test");
-        let ast = parse(declerations);
+        let ast = parse(declerations).unwrap();
         assert_debug_snapshot!(ast)
     }
 
     #[test]
     fn test_vcd_file() {
         let declerations = include_str!("../test/UartRxTest.vcd");
-        let ast = parse(declerations);
+        let ast = parse(declerations).unwrap();
         assert_debug_snapshot!(ast)
     }
 
     #[test]
-    #[ignore = "Runs for > 350s. Requires parser rewrite to be reasonable"]
+    fn test_numbers_too_large() {
+        let errors = [
+            "$timescale 99999999999999999999999 ns $end",
+            "$var wire 99999999999999999999999 ! a $end",
+            "$var wire 1 ! a $end\n$enddefinitions $end\n#99999999999999999999999\n1!",
+        ]
+        .map(|input| ValueChangeDump::parse(input).unwrap_err().to_string());
+        assert_snapshot!(errors.join("\n\n"))
+    }
+
+    #[test]
     fn test_large_vcd_file() {
-        let declerations = include_str!("../test/NextCoreTest.vcd");
-        let ast = parse(declerations);
-        assert_debug_snapshot!(ast)
+        let vcd = ValueChangeDump::parse(include_str!("../test/NextCoreTest.vcd")).unwrap();
+        assert_snapshot!(vcd.info(5).to_string())
     }
 
     #[test]
     fn test_model_against_uart() {
         let declerations = include_str!("../test/UartRxTest.vcd");
-        let ast = parse(declerations);
-        let model = ValueChangeDump::from_definition(ast);
-        assert_debug_snapshot!(model)
+        let model = ValueChangeDump::parse(declerations).unwrap();
+        assert_json_snapshot!(model)
+    }
+
+    #[test]
+    fn test_model_json_round_trip() {
+        let vcd = ValueChangeDump::parse(include_str!("../test/UartRxTest.vcd")).unwrap();
+        let json = serde_json::to_string(&vcd).unwrap();
+        let read: ValueChangeDump = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.to_vcd(), vcd.to_vcd());
+        assert_eq!(read.signals()[0].reference, vcd.signals()[0].reference);
+        assert_eq!(serde_json::to_string(&read).unwrap(), json);
+
+        // A dump ending on a timestamp without changes keeps it
+        let input = "$var wire 1 # a $end\n$enddefinitions $end\n#0\n0#\n#1\n1#\n#5\n";
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        let read: ValueChangeDump =
+            serde_json::from_str(&serde_json::to_string(&vcd).unwrap()).unwrap();
+        assert_eq!(read.end_time(), 5);
+        assert_eq!(read.to_vcd(), vcd.to_vcd());
+        assert!(read.to_vcd().ends_with("#5\n"));
+    }
+
+    #[test]
+    fn test_info_against_uart() {
+        let vcd = ValueChangeDump::parse(include_str!("../test/UartRxTest.vcd")).unwrap();
+        assert_snapshot!(vcd.info(5).to_string())
+    }
+
+    #[test]
+    fn test_hierarchy_against_uart() {
+        let vcd = ValueChangeDump::parse(include_str!("../test/UartRxTest.vcd")).unwrap();
+        let options = HierarchyOptions {
+            signals: vec!["*.clk".to_owned(), "*.state*".to_owned()],
+            depth: None,
+        };
+        assert_snapshot!(HierarchyScope::from_vcd(&vcd, &options).to_string())
+    }
+
+    #[test]
+    fn test_diff_against_testbench() {
+        let a = ValueChangeDump::parse(include_str!("../test/Counter.vcd")).unwrap();
+        let b = ValueChangeDump::parse(include_str!("../test/CounterTb.vcd")).unwrap();
+        let options = DiffOptions {
+            strip_prefixes: vec!["tb.".to_owned()],
+            rename: [("Counter.clk".to_owned(), "Counter.clock".to_owned())].into(),
+            ..Default::default()
+        };
+        assert_snapshot!(a.diff(&b, &options).to_string());
+
+        let options = DiffOptions {
+            signals: vec!["*.count".to_owned()],
+            x_wildcard: true,
+            ..options
+        };
+        assert_snapshot!(a.diff(&b, &options).to_string());
+
+        let options = DiffOptions {
+            tolerance: 1,
+            ..options
+        };
+        assert!(a.diff(&b, &options).is_empty());
+    }
+
+    #[test]
+    fn test_measure_clocks() {
+        // clk has periods of 10, 12 and 10 with a 4 unit high phase, rst glitches at 31
+        let vcd = ValueChangeDump::parse(
+            "$timescale 1ns $end\n$scope module tb $end\n\
+             $var wire 1 ! clk $end\n$var wire 1 \" rst $end\n$var wire 8 # bus $end\n\
+             $upscope $end\n$enddefinitions $end\n\
+             #0\n0!\n1\"\nb0 #\n#10\n1!\n#14\n0!\n#20\n1!\n#24\n0!\n#30\n0\"\n\
+             #31\n1\"\n#32\n1!\n0\"\n#36\n0!\n#42\n1!\n#46\nx!\n#50\n1!\n",
+        )
+        .unwrap();
+        let options = MeasureOptions {
+            glitch_threshold: 2,
+            ..Default::default()
+        };
+        let report = vcd.measure(&options);
+        assert_eq!(report.signals.len(), 2);
+        assert_snapshot!(report.to_string());
+
+        let options = MeasureOptions {
+            signals: vec!["*.clk".to_owned()],
+            end: Some(30),
+            ..Default::default()
+        };
+        let clk = &vcd.measure(&options).signals[0];
+        assert_eq!(clk.period, Some(10.0));
+        assert_eq!(clk.frequency, Some(1e8));
+        assert_eq!(clk.duty_cycle, Some(40.0));
+        assert_eq!(timescale_seconds("100 ps"), Some(1e-10));
+        assert_eq!(timescale_seconds("1 day"), None);
+    }
+
+    #[test]
+    fn test_derived_signals() {
+        let mut vcd = ValueChangeDump::parse(
+            "$timescale 1ns $end\n$scope module tb $end\n\
+             $var wire 1 ! valid $end\n$var wire 1 \" ready $end\n\
+             $var wire 16 # addr [15:0] $end\n$upscope $end\n$enddefinitions $end\n\
+             #0\n0!\n0\"\nbx #\n#10\n1!\nb1010101111001101 #\n#20\n1\"\n\
+             #30\n0!\n0\"\nb1001000110100 #\n#40\n1!\n",
+        )
+        .unwrap();
+        for definition in [
+            "fire = valid && ready",
+            "page = tb.addr[15:8]",
+            "swapped = {addr[7:0], addr[15:8]}",
+            "next = page + 8'd1 == 'hac",
+            "start = rose(valid)",
+            "bit = addr[3]",
+            "nibble = -(addr >> 12) & 16'hf",
+        ] {
+            let derived: DerivedSignal = definition.parse().unwrap();
+            vcd.add_derived(&derived).unwrap();
+        }
+        assert_eq!(vcd.signals()[4].reference, "derived.page [7:0]");
+        // Waves are only computed once read
+        let computed = |vcd: &ValueChangeDump| -> Vec<String> {
+            vcd.derived_waves
+                .iter()
+                .filter(|(_, derived)| derived.wave.get().is_some())
+                .map(|(identifier, _)| identifier.to_owned())
+                .collect()
+        };
+        assert!(computed(&vcd).is_empty());
+        // Edges last until the next time step of the dump
+        let start: Vec<(usize, &str)> = vcd
+            .wave("(")
+            .value_changes
+            .iter()
+            .map(|vc| (vc.time, vc.value.as_str()))
+            .collect();
+        assert_eq!(start, [(0, "0"), (10, "1"), (20, "0"), (40, "1")]);
+        assert_eq!(computed(&vcd), ["("]);
+        vcd.wave("'");
+        assert_eq!(computed(&vcd), ["%", "'", "("]);
+        assert_snapshot!(vcd.to_vcd());
+
+        let mut error = |definition: &str| {
+            DerivedSignal::parse(definition)
+                .and_then(|derived| vcd.add_derived(&derived))
+                .unwrap_err()
+        };
+        assert_eq!(
+            error("a = tb.data"),
+            Error::UnknownSignal("tb.data".to_owned())
+        );
+        assert_eq!(
+            error("a = addr[16:8]"),
+            Error::InvalidSelect {
+                signal: "tb.addr [15:0]".to_owned(),
+                select: "addr[16:8]".to_owned()
+            }
+        );
+        assert_eq!(
+            error("fire = valid"),
+            Error::DuplicateSignal("derived.fire".to_owned())
+        );
+        assert!(matches!(error("a = valid &&"), Error::Syntax(_)));
+        assert!(matches!(error("a = 1000000000'b1"), Error::Syntax(_)));
+        assert!(matches!(error("a = 0'b1"), Error::Syntax(_)));
+    }
+
+    #[test]
+    fn test_merge_dumps() {
+        let counter = ValueChangeDump::parse(include_str!("../test/Counter.vcd")).unwrap();
+        let tb = ValueChangeDump::parse(
+            "$version sim $end\n$timescale 100ms $end\n$scope module Counter $end\n\
+             $var wire 1 ! tick $end\n$var wire 1 ! tick_alias $end\n$upscope $end\n\
+             $var wire 1 \" rst $end\n$enddefinitions $end\n#0\n1!\n1\"\n#5\n0!\n#15\n0\"\n",
+        )
+        .unwrap();
+        let dumps = [counter, tb];
+        let options = MergeOptions {
+            prefixes: vec!["".to_owned(), "soc.tb".to_owned()],
+            offsets: vec![0, 10],
+        };
+        let merged = ValueChangeDump::merge(&dumps, &options).unwrap();
+        assert_eq!(merged.timescale, "100ms");
+        assert_eq!(merged.signals().len(), 5);
+        assert_snapshot!(merged.to_vcd());
+
+        // Without a prefix the second Counter scope is merged into the first
+        let merged = ValueChangeDump::merge(&dumps, &MergeOptions::default()).unwrap();
+        let counter = merged.root_scope.borrow().scopes[0].clone();
+        assert_eq!(counter.borrow().signals.len(), 4);
+        let counter = || ValueChangeDump::parse(include_str!("../test/Counter.vcd")).unwrap();
+        assert_eq!(
+            ValueChangeDump::merge(&[counter(), counter()], &MergeOptions::default()).unwrap_err(),
+            Error::DuplicateSignal("Counter.count [3:0]".to_owned())
+        );
+        let mut other = counter();
+        other.timescale = "1 day".to_owned();
+        assert_eq!(
+            ValueChangeDump::merge(&[counter(), other], &options).unwrap_err(),
+            Error::InvalidTimescale("1 day".to_owned())
+        );
+        let options = MergeOptions {
+            offsets: vec![0, usize::MAX],
+            ..options
+        };
+        assert_eq!(
+            ValueChangeDump::merge(&[counter(), counter()], &options).unwrap_err(),
+            Error::TimeOverflow
+        );
+        let mut coarse = counter();
+        coarse.timescale = "1s".to_owned();
+        coarse.wave_map.values_mut().next().unwrap().value_changes[1].time = usize::MAX / 10;
+        let mut fine = counter();
+        fine.timescale = "1fs".to_owned();
+        assert_eq!(
+            ValueChangeDump::merge(&[coarse, fine], &MergeOptions::default()).unwrap_err(),
+            Error::TimeOverflow
+        );
+    }
+
+    #[test]
+    fn test_parse_selected_signals() {
+        let input = "$timescale 1ns $end\n$scope module tb $end\n$var wire 1 ! clk $end\n\
+                     $scope module dut $end\n$var wire 8 \" data [7:0] $end\n\
+                     $var wire 1 ! clk $end\n$upscope $end\n$scope module mem $end\n\
+                     $var wire 1 # we $end\n$upscope $end\n$upscope $end\n$enddefinitions $end\n\
+                     #0\n$dumpvars\n0!\nb0 \"\n0#\n$end\n#1\n1!\nb1010 \"\n1#\n#2\n0!\n";
+        let options = ParseOptions {
+            signals: vec!["tb.dut.*".to_owned()],
+            ..Default::default()
+        };
+        let vcd = ValueChangeDump::parse_with(input, &options).unwrap();
+        assert_snapshot!(vcd.to_vcd());
+
+        let options = ParseOptions {
+            identifiers: vec!["#".to_owned()],
+            ..Default::default()
+        };
+        let vcd = ValueChangeDump::parse_with(input, &options).unwrap();
+        let references: Vec<_> = vcd.signals().into_iter().map(|sig| sig.reference).collect();
+        assert_eq!(references, ["tb.mem.we"]);
+        assert_eq!(vcd.wave_map.keys().collect::<Vec<_>>(), ["#"]);
+        assert_eq!(vcd.wave("#").value_changes.len(), 2);
+        // The dump still ends at its last timestamp
+        assert_eq!(vcd.end_time(), 2);
+        assert!(vcd.to_vcd().ends_with("#1\n1#\n#2\n"));
+
+        // Left out signals are still checked against the declarations
+        let input = input.replace("#2\n0!", "#2\n0$");
+        assert_eq!(
+            ValueChangeDump::parse_with(&input, &options).unwrap_err(),
+            Error::UnknownIdentifier("$".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_write_vcd_round_trip() {
+        let vcd = ValueChangeDump::parse(include_str!("../test/UartRxTest.vcd")).unwrap();
+        let written = vcd.to_vcd();
+        assert_eq!(ValueChangeDump::parse(&written).unwrap().to_vcd(), written);
+
+        let vcd = ValueChangeDump::parse(include_str!("../test/Counter.vcd")).unwrap();
+        assert_eq!(vcd.to_vcd(), include_str!("../test/Counter.vcd"));
+
+        // Signals declared outside any scope
+        let input = "$var wire 1 ! top $end\n$scope module tb $end\n$var wire 1 \" clk $end\n\
+                     $upscope $end\n$enddefinitions $end\n#0\n$dumpvars\n1!\n0\"\n$end\n#5\n0!\n";
+        let written = ValueChangeDump::parse(input).unwrap().to_vcd();
+        assert!(written.starts_with("$var wire 1 ! top $end\n$scope module tb $end\n"));
+        assert_eq!(ValueChangeDump::parse(&written).unwrap().to_vcd(), written);
+    }
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let input = include_str!("../test/Counter.vcd");
+        let vcd = ValueChangeDump::parse(input).unwrap();
+        for compression in [Compression::Gzip, Compression::Zstd, Compression::Xz] {
+            let mut compressed = vec![];
+            vcd.write_vcd_compressed(&mut compressed, compression)
+                .unwrap();
+            assert_eq!(Compression::detect(&compressed), Some(compression));
+            assert_eq!(decompress(&compressed).unwrap(), input.as_bytes());
+        }
+        assert_eq!(Compression::detect(input.as_bytes()), None);
+    }
+
+    #[test]
+    fn test_wave_lookups() {
+        let wave = |changes: &[(usize, &str)]| ValueChangeDumpWave {
+            value_changes: changes
+                .iter()
+                .map(|(time, value)| ValueChange {
+                    time: *time,
+                    value: value.to_string(),
+                })
+                .collect(),
+        };
+        let clock = wave(&[(0, "0"), (5, "1"), (9, "0")]);
+        assert_eq!(clock.value_at(4).unwrap().value, "0");
+        assert_eq!(clock.value_at(5).unwrap().value, "1");
+        assert_eq!(clock.next_change(5).unwrap().time, 9);
+        assert!(clock.next_change(9).is_none());
+        assert_eq!(clock.previous_change(5).unwrap().time, 0);
+        assert!(clock.previous_change(0).is_none());
+        assert_eq!(clock.level_at(5), Some(true));
+        assert_eq!(clock.level_before(5), Some(false));
+
+        // Edges from or to x don't count
+        let clock = wave(&[
+            (0, "0"),
+            (5, "1"),
+            (9, "0"),
+            (12, "x"),
+            (14, "1"),
+            (16, "0"),
+        ]);
+        assert_eq!(clock.level_at(13), None);
+        assert_eq!(clock.edges(true).collect::<Vec<_>>(), [5]);
+        assert_eq!(clock.edges(false).collect::<Vec<_>>(), [9, 16]);
+        assert_eq!(level("b1010"), Some(false));
+        assert_eq!(level("r1"), None);
     }
 }
@@ -0,0 +1,275 @@
+use crate::{level, timescale_seconds, ValueChangeDump, ValueChangeDumpWave};
+use std::fmt;
+
+/// Options for measuring the timing of single bit signals such as clocks.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MeasureOptions {
+    /// Glob patterns selecting the signals. Every single bit signal is measured when empty.
+    pub signals: Vec<String>,
+    /// Pulses shorter than this many time units are reported as glitches.
+    /// Glitches aren't looked for when it is 0.
+    pub glitch_threshold: usize,
+    /// First simulation time to measure.
+    pub start: Option<usize>,
+    /// Simulation time at which measuring stops (exclusive).
+    pub end: Option<usize>,
+}
+
+/// Timing of the selected signals, in time units of the dump's timescale.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct TimingReport {
+    pub timescale: String,
+    pub signals: Vec<SignalTiming>,
+}
+
+/// Edges, pulse widths and period statistics of a single bit signal. Only
+/// pulses between two edges count, so the level a signal starts or ends the
+/// dump with is left out, and `x` or `z` restart the period measurement.
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct SignalTiming {
+    pub reference: String,
+    pub rising_edges: usize,
+    pub falling_edges: usize,
+    /// Mean time between rising edges
+    pub period: Option<f64>,
+    pub min_period: Option<usize>,
+    pub max_period: Option<usize>,
+    /// Inverse of the mean period in Hz, if the timescale is known
+    pub frequency: Option<f64>,
+    /// Mean high pulse width as a percentage of the mean high and low widths
+    pub duty_cycle: Option<f64>,
+    pub min_high: Option<usize>,
+    pub max_high: Option<usize>,
+    pub min_low: Option<usize>,
+    pub max_low: Option<usize>,
+    /// Difference between the longest and shortest period
+    pub jitter_peak_to_peak: Option<usize>,
+    /// Standard deviation of the period
+    pub jitter_rms: Option<f64>,
+    /// Largest difference between two consecutive periods
+    pub jitter_cycle_to_cycle: Option<usize>,
+    pub glitches: Vec<Glitch>,
+}
+
+/// A pulse shorter than the glitch threshold.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub struct Glitch {
+    pub time: usize,
+    pub width: usize,
+    /// Whether the pulse is high, i.e. between a rising and a falling edge
+    pub high: bool,
+}
+
+impl TimingReport {
+    /// Measure every selected single bit signal. Wider signals are skipped.
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &MeasureOptions) -> Self {
+        let seconds = timescale_seconds(&vcd.timescale);
+        let signals = vcd
+            .signals()
+            .into_iter()
+            .filter(|sig| sig.size == 1)
+            .filter(|sig| {
+                options.signals.is_empty()
+                    || options.signals.iter().any(|pattern| sig.matches(pattern))
+            })
+            .map(|sig| {
+                let mut timing = SignalTiming::from_wave(vcd.wave(&sig.identifier), options);
+                timing.reference = sig.reference;
+                timing.frequency = timing
+                    .period
+                    .zip(seconds)
+                    .map(|(period, seconds)| 1.0 / (period * seconds));
+                timing
+            })
+            .collect();
+        TimingReport {
+            timescale: vcd.timescale.to_owned(),
+            signals,
+        }
+    }
+}
+
+impl ValueChangeDump {
+    /// Measure the timing of the selected single bit signals.
+    pub fn measure(&self, options: &MeasureOptions) -> TimingReport {
+        TimingReport::from_vcd(self, options)
+    }
+}
+
+impl SignalTiming {
+    /// Measure a single bit wave. The reference is left empty and the frequency
+    /// unknown, as both come from the dump.
+    pub fn from_wave(wave: &ValueChangeDumpWave, options: &MeasureOptions) -> Self {
+        let start = options.start.unwrap_or(0);
+        let end = options.end.unwrap_or(usize::MAX);
+        // Level changes in the window, dropping repeated values
+        let mut levels: Vec<(usize, Option<bool>)> = vec![];
+        for vc in &wave.value_changes {
+            if vc.time < start || vc.time >= end {
+                continue;
+            }
+            let level = level(&vc.value);
+            if levels.last().map(|(_, last)| *last) != Some(level) {
+                levels.push((vc.time, level));
+            }
+        }
+
+        let mut timing = SignalTiming::default();
+        let mut periods = vec![];
+        let mut last_rise = None;
+        for pair in levels.windows(2) {
+            match (pair[0].1, pair[1].1) {
+                (Some(false), Some(true)) => {
+                    timing.rising_edges += 1;
+                    if let Some(last_rise) = last_rise {
+                        periods.push(pair[1].0 - last_rise);
+                    }
+                    last_rise = Some(pair[1].0);
+                }
+                (Some(true), Some(false)) => timing.falling_edges += 1,
+                (_, None) => last_rise = None,
+                _ => {}
+            }
+        }
+
+        let mut highs = vec![];
+        let mut lows = vec![];
+        for pulse in levels.windows(3) {
+            let [(_, before), (time, level), (next, after)] = [pulse[0], pulse[1], pulse[2]];
+            // Consecutive levels differ, so a pulse only needs known levels around it
+            let (Some(_), Some(level), Some(_)) = (before, level, after) else {
+                continue;
+            };
+            let width = next - time;
+            if width < options.glitch_threshold {
+                timing.glitches.push(Glitch {
+                    time,
+                    width,
+                    high: level,
+                });
+            }
+            if level {
+                highs.push(width);
+            } else {
+                lows.push(width);
+            }
+        }
+
+        timing.period = mean(&periods);
+        timing.min_period = periods.iter().copied().min();
+        timing.max_period = periods.iter().copied().max();
+        timing.duty_cycle = mean(&highs)
+            .zip(mean(&lows))
+            .map(|(high, low)| high * 100.0 / (high + low));
+        timing.min_high = highs.iter().copied().min();
+        timing.max_high = highs.iter().copied().max();
+        timing.min_low = lows.iter().copied().min();
+        timing.max_low = lows.iter().copied().max();
+        timing.jitter_peak_to_peak = timing.max_period.zip(timing.min_period).map(|(a, b)| a - b);
+        timing.jitter_rms = timing.period.map(|period| {
+            let variance = periods
+                .iter()
+                .map(|p| (*p as f64 - period).powi(2))
+                .sum::<f64>()
+                / periods.len() as f64;
+            variance.sqrt()
+        });
+        timing.jitter_cycle_to_cycle = periods.windows(2).map(|p| p[0].abs_diff(p[1])).max();
+        timing
+    }
+}
+
+fn mean(values: &[usize]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<usize>() as f64 / values.len() as f64)
+    }
+}
+
+/// A frequency with the SI prefix that keeps it between 1 and 1000.
+fn format_frequency(hz: f64) -> String {
+    let (scale, unit) = [(1e9, "GHz"), (1e6, "MHz"), (1e3, "kHz"), (1.0, "Hz")]
+        .into_iter()
+        .find(|(scale, _)| hz >= *scale)
+        .unwrap_or((1e-3, "mHz"));
+    format!("{:.3} {}", hz / scale, unit)
+}
+
+impl fmt::Display for TimingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let range = |min: Option<usize>, max: Option<usize>| match min.zip(max) {
+            Some((min, max)) => format!("{min} to {max}"),
+            None => "-".to_owned(),
+        };
+        for (index, timing) in self.signals.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{}", timing.reference)?;
+            writeln!(
+                f,
+                "  edges:        {} rising, {} falling",
+                timing.rising_edges, timing.falling_edges
+            )?;
+            if let Some(period) = timing.period {
+                writeln!(
+                    f,
+                    "  period:       {:.3} ({}, timescale {})",
+                    period,
+                    range(timing.min_period, timing.max_period),
+                    self.timescale
+                )?;
+            }
+            if let Some(frequency) = timing.frequency {
+                writeln!(f, "  frequency:    {}", format_frequency(frequency))?;
+            }
+            if let Some(duty_cycle) = timing.duty_cycle {
+                writeln!(f, "  duty cycle:   {duty_cycle:.1}%")?;
+            }
+            writeln!(
+                f,
+                "  high pulses:  {}",
+                range(timing.min_high, timing.max_high)
+            )?;
+            writeln!(
+                f,
+                "  low pulses:   {}",
+                range(timing.min_low, timing.max_low)
+            )?;
+            if let Some((peak_to_peak, rms)) = timing.jitter_peak_to_peak.zip(timing.jitter_rms) {
+                write!(
+                    f,
+                    "  jitter:       {peak_to_peak} peak-to-peak, {rms:.3} rms"
+                )?;
+                match timing.jitter_cycle_to_cycle {
+                    Some(cycle) => writeln!(f, ", {cycle} cycle-to-cycle")?,
+                    None => writeln!(f)?,
+                }
+            }
+            if !timing.glitches.is_empty() {
+                writeln!(f, "  glitches:     {}", timing.glitches.len())?;
+                for glitch in &timing.glitches {
+                    let level = if glitch.high { "high" } else { "low" };
+                    writeln!(f, "    #{}  {} for {}", glitch.time, level, glitch.width)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
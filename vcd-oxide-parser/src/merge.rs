@@ -0,0 +1,195 @@
+use crate::{
+    identifier_code,
+    timescale::{timescale_femtoseconds, UNITS},
+    Error, ValueChange, ValueChangeDump, ValueChangeDumpScope, ValueChangeDumpSignal,
+    ValueChangeDumpWave,
+};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+type VcdScopeNode = Rc<RefCell<ValueChangeDumpScope>>;
+
+/// Options for merging dumps, given per dump in the order they are merged.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Scope each dump is nested in, e.g. `soc.cpu`. Dumps without a prefix,
+    /// or with an empty one, keep their scopes at the top level, where scopes
+    /// of the same name are merged.
+    pub prefixes: Vec<String>,
+    /// Time added to each dump, in the dump's own time units.
+    pub offsets: Vec<usize>,
+}
+
+impl ValueChangeDump {
+    /// Merge dumps into one on the finest of their timescales. Identifier
+    /// codes are assigned anew, keeping aliases within a dump, and the date
+    /// is taken from the first dump.
+    pub fn merge(dumps: &[ValueChangeDump], options: &MergeOptions) -> Result<Self, Error> {
+        let (timescale, factors) = common_timescale(dumps)?;
+        let mut versions: Vec<&str> = vec![];
+        for vcd in dumps {
+            let version = vcd.version.trim();
+            if !version.is_empty() && !versions.contains(&version) {
+                versions.push(version);
+            }
+        }
+        let mut merged = ValueChangeDump {
+            date: dumps
+                .first()
+                .map(|vcd| vcd.date.to_owned())
+                .unwrap_or_default(),
+            version: versions.join(", "),
+            timescale,
+            ..Default::default()
+        };
+
+        let mut references = HashSet::new();
+        for (index, (vcd, factor)) in dumps.iter().zip(factors).enumerate() {
+            let prefix = options
+                .prefixes
+                .get(index)
+                .map(|prefix| prefix.trim_matches('.'))
+                .filter(|prefix| !prefix.is_empty());
+            let offset = options.offsets.get(index).copied().unwrap_or(0);
+            merged.last_time = merged.last_time.max(shift(vcd.end_time(), offset, factor)?);
+            let mut target = merged.root_scope.clone();
+            for name in prefix.iter().flat_map(|prefix| prefix.split('.')) {
+                target = child_scope(&target, name, "module");
+            }
+
+            let mut copy = ScopeCopy {
+                prefix,
+                identifiers: HashMap::new(),
+                first_identifier: merged.wave_map.len(),
+                references: &mut references,
+            };
+            copy.copy(&vcd.root_scope.borrow(), &target)?;
+            for (identifier, merged_identifier) in copy.identifiers {
+                let value_changes = vcd
+                    .wave(&identifier)
+                    .value_changes
+                    .iter()
+                    .map(|vc| {
+                        Ok(ValueChange {
+                            time: shift(vc.time, offset, factor)?,
+                            value: vc.value.to_owned(),
+                        })
+                    })
+                    .collect::<Result<_, Error>>()?;
+                merged
+                    .wave_map
+                    .insert(merged_identifier, ValueChangeDumpWave { value_changes });
+            }
+        }
+        Ok(merged)
+    }
+}
+
+/// Copies the scopes and signals of one dump into the merged tree.
+struct ScopeCopy<'a> {
+    prefix: Option<&'a str>,
+    /// Merged identifier codes by the dump's own
+    identifiers: HashMap<String, String>,
+    first_identifier: usize,
+    references: &'a mut HashSet<String>,
+}
+
+impl ScopeCopy<'_> {
+    fn copy(&mut self, source: &ValueChangeDumpScope, target: &VcdScopeNode) -> Result<(), Error> {
+        for sig in &source.signals {
+            let reference = match self.prefix {
+                Some(prefix) => format!("{}.{}", prefix, sig.reference),
+                None => sig.reference.to_owned(),
+            };
+            if !self.references.insert(reference.to_owned()) {
+                return Err(Error::DuplicateSignal(reference));
+            }
+            let next = self.first_identifier + self.identifiers.len();
+            let identifier = self
+                .identifiers
+                .entry(sig.identifier.to_owned())
+                .or_insert_with(|| identifier_code(next))
+                .to_owned();
+            target.borrow_mut().signals.push(ValueChangeDumpSignal {
+                kind: sig.kind.to_owned(),
+                identifier,
+                reference,
+                size: sig.size,
+            });
+        }
+        for scope in &source.scopes {
+            let scope = scope.borrow();
+            let child = child_scope(target, &scope.name, &scope.kind);
+            self.copy(&scope, &child)?;
+        }
+        Ok(())
+    }
+}
+
+/// The child scope called `name`, added if there isn't one yet.
+fn child_scope(parent: &VcdScopeNode, name: &str, kind: &str) -> VcdScopeNode {
+    let existing = parent
+        .borrow()
+        .scopes
+        .iter()
+        .find(|scope| scope.borrow().name == name)
+        .cloned();
+    existing.unwrap_or_else(|| {
+        let scope = Rc::new(RefCell::new(ValueChangeDumpScope {
+            name: name.to_owned(),
+            kind: kind.to_owned(),
+            parent: Some(Rc::downgrade(parent)),
+            ..Default::default()
+        }));
+        parent.borrow_mut().scopes.push(scope.clone());
+        scope
+    })
+}
+
+/// The coarsest timescale that all timescales of the dumps are multiples of,
+/// normally the finest of them, and the factor that converts the times
+/// of each dump to it. Dumps that all share a timescale, even an empty one,
+/// keep it as is.
+fn common_timescale(dumps: &[ValueChangeDump]) -> Result<(String, Vec<usize>), Error> {
+    let first = dumps.first().map_or("", |vcd| vcd.timescale.trim());
+    if dumps.iter().all(|vcd| vcd.timescale.trim() == first) {
+        return Ok((first.to_owned(), vec![1; dumps.len()]));
+    }
+    let lengths = dumps
+        .iter()
+        .map(|vcd| {
+            timescale_femtoseconds(&vcd.timescale)
+                .ok_or_else(|| Error::InvalidTimescale(vcd.timescale.to_owned()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // The finest timescale divides the others as they are powers of ten, but
+    // the common divisor also covers timescales such as 3ns
+    let finest = lengths.iter().copied().fold(0, gcd);
+    let factors = lengths
+        .iter()
+        .map(|length| usize::try_from(length / finest).map_err(|_| Error::TimeOverflow))
+        .collect::<Result<_, _>>()?;
+    let (unit, scale) = UNITS
+        .into_iter()
+        .find(|(_, scale)| finest % scale == 0)
+        .unwrap_or(UNITS[5]);
+    Ok((format!("{}{}", finest / scale, unit), factors))
+}
+
+/// A time of one dump in the merged timescale, offset by `offset` of the dump's units.
+fn shift(time: usize, offset: usize, factor: usize) -> Result<usize, Error> {
+    time.checked_add(offset)
+        .and_then(|time| time.checked_mul(factor))
+        .ok_or(Error::TimeOverflow)
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
@@ -1,3 +1,4 @@
+use crate::DerivedWave;
 use core::cell::RefCell;
 use std::{
     collections::BTreeMap,
@@ -6,6 +7,35 @@ use std::{
 
 type VcdScopeNode = Rc<RefCell<ValueChangeDumpScope>>;
 
+/// A parsed dump. With the `serde` feature it serializes as
+///
+/// ```json
+/// {
+///   "date": "Sat Feb  4 12:16:29 2023",
+///   "version": "Icarus Verilog",
+///   "timescale": "1s",
+///   "end-time": 6,
+///   "scopes": [
+///     {
+///       "name": "Counter",
+///       "kind": "module",
+///       "signals": [
+///         { "kind": "reg", "identifier": "!", "reference": "Counter.count [3:0]", "size": 4 }
+///       ],
+///       "scopes": []
+///     }
+///   ],
+///   "waves": {
+///     "!": [{ "time": 0, "value": "bx" }, { "time": 1, "value": "b0" }]
+///   }
+/// }
+/// ```
+///
+/// Scopes nest in declaration order and signals refer to their wave by
+/// identifier code. Values are kept as written in the dump, e.g. `1`, `b1010`
+/// or `r0.5`. `end-time` is the time the dump ends at, which can be after its
+/// last value change. Signals declared outside any scope are listed under a
+/// top level `signals` key, which is left out when empty.
 #[derive(Debug, Default)]
 pub struct ValueChangeDump {
     pub date: String,
@@ -15,6 +45,12 @@ pub struct ValueChangeDump {
     // This scope can have multiple child scopes
     pub root_scope: VcdScopeNode,
     pub wave_map: BTreeMap<String, ValueChangeDumpWave>,
+    /// The last `#time` of the dump, which can be after the last value change
+    /// kept, e.g. when only some signals are parsed
+    pub last_time: usize,
+    /// Waves of the signals added with [`ValueChangeDump::add_derived`] by
+    /// identifier code, read through [`ValueChangeDump::wave`]
+    pub derived_waves: BTreeMap<String, DerivedWave>,
 }
 
 #[derive(Debug, Default)]
@@ -26,7 +62,12 @@ pub struct ValueChangeDumpScope {
     pub signals: Vec<ValueChangeDumpSignal>,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub struct ValueChangeDumpSignal {
     pub kind: String,
     pub identifier: String,
@@ -35,12 +76,140 @@ pub struct ValueChangeDumpSignal {
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct ValueChangeDumpWave {
     pub value_changes: Vec<ValueChange>,
 }
 
 #[derive(Debug, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub struct ValueChange {
     pub time: usize,
     pub value: String,
 }
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::*;
+    use serde::{
+        ser::{SerializeSeq, SerializeStruct},
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    /// Child scopes, borrowed one at a time while serializing.
+    struct Scopes<'a>(&'a [VcdScopeNode]);
+
+    impl Serialize for Scopes<'_> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+            for scope in self.0 {
+                seq.serialize_element(&*scope.borrow())?;
+            }
+            seq.end()
+        }
+    }
+
+    impl Serialize for ValueChangeDumpScope {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("ValueChangeDumpScope", 4)?;
+            state.serialize_field("name", &self.name)?;
+            state.serialize_field("kind", &self.kind)?;
+            state.serialize_field("signals", &self.signals)?;
+            state.serialize_field("scopes", &Scopes(&self.scopes))?;
+            state.end()
+        }
+    }
+
+    impl Serialize for ValueChangeDump {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let root = self.root_scope.borrow();
+            let fields = if root.signals.is_empty() { 6 } else { 7 };
+            let mut state = serializer.serialize_struct("ValueChangeDump", fields)?;
+            state.serialize_field("date", &self.date)?;
+            state.serialize_field("version", &self.version)?;
+            state.serialize_field("timescale", &self.timescale)?;
+            state.serialize_field("end-time", &self.end_time())?;
+            if root.signals.is_empty() {
+                state.skip_field("signals")?;
+            } else {
+                state.serialize_field("signals", &root.signals)?;
+            }
+            state.serialize_field("scopes", &Scopes(&root.scopes))?;
+            state.serialize_field("waves", &self.waves())?;
+            state.end()
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct ScopeData {
+        name: String,
+        #[serde(default)]
+        kind: String,
+        #[serde(default)]
+        signals: Vec<ValueChangeDumpSignal>,
+        #[serde(default)]
+        scopes: Vec<ScopeData>,
+    }
+
+    #[derive(Deserialize)]
+    struct DumpData {
+        #[serde(default)]
+        date: String,
+        #[serde(default)]
+        version: String,
+        #[serde(default)]
+        timescale: String,
+        #[serde(default, rename = "end-time")]
+        end_time: usize,
+        #[serde(default)]
+        signals: Vec<ValueChangeDumpSignal>,
+        #[serde(default)]
+        scopes: Vec<ScopeData>,
+        #[serde(default)]
+        waves: BTreeMap<String, ValueChangeDumpWave>,
+    }
+
+    /// Build the scope tree with links back to each parent.
+    fn build_scopes(parent: &VcdScopeNode, scopes: Vec<ScopeData>) {
+        for data in scopes {
+            let scope = Rc::new(RefCell::new(ValueChangeDumpScope {
+                name: data.name,
+                kind: data.kind,
+                parent: Some(Rc::downgrade(parent)),
+                signals: data.signals,
+                ..Default::default()
+            }));
+            build_scopes(&scope, data.scopes);
+            parent.borrow_mut().scopes.push(scope);
+        }
+    }
+
+    impl<'de> Deserialize<'de> for ValueChangeDump {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let data = DumpData::deserialize(deserializer)?;
+            let mut dump = ValueChangeDump {
+                date: data.date,
+                version: data.version,
+                timescale: data.timescale,
+                wave_map: data.waves,
+                last_time: data.end_time,
+                ..Default::default()
+            };
+            dump.root_scope.borrow_mut().signals = data.signals;
+            build_scopes(&dump.root_scope, data.scopes);
+            // Every signal has a wave, even if it never changes
+            for sig in dump.signals() {
+                dump.wave_map.entry(sig.identifier).or_default();
+            }
+            Ok(dump)
+        }
+    }
+}
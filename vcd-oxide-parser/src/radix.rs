@@ -0,0 +1,131 @@
+use std::{fmt, str::FromStr};
+
+/// Number base used when presenting vector values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    #[default]
+    Hex,
+}
+
+impl Radix {
+    /// Format a raw VCD value (e.g. `b101`, `1`, `r1.5`) of the given bit width in this radix.
+    /// Real values are returned untouched. Digits covering unknown or high impedance
+    /// bits are rendered as `x` or `z`.
+    pub fn format(&self, value: &str, size: usize) -> String {
+        if value.starts_with('r') || value.starts_with('R') {
+            return value[1..].to_owned();
+        }
+        let bits = extend_bits(value, size);
+        match self {
+            Radix::Binary => bits,
+            Radix::Octal => group_bits(&bits, 3),
+            Radix::Hex => group_bits(&bits, 4),
+            Radix::Decimal => {
+                if let Some(c) = bits.chars().find(|c| !matches!(c, '0' | '1')) {
+                    return c.to_ascii_lowercase().to_string();
+                }
+                match u128::from_str_radix(&bits, 2) {
+                    Ok(value) => value.to_string(),
+                    // Too wide for a native integer, fall back to hex
+                    Err(_) => group_bits(&bits, 4),
+                }
+            }
+        }
+    }
+}
+
+/// Strip the `b` prefix of a binary value and extend it to `size` bits.
+/// Values are extended with `0` unless the leftmost bit is `x` or `z`,
+/// in which case that bit is repeated as described in IEEE 1364-2005 18.2.1.
+pub fn extend_bits(value: &str, size: usize) -> String {
+    let value = value
+        .strip_prefix('b')
+        .or_else(|| value.strip_prefix('B'))
+        .unwrap_or(value)
+        .to_ascii_lowercase();
+    let fill = value
+        .chars()
+        .next()
+        .filter(|c| matches!(c, 'x' | 'z'))
+        .unwrap_or('0');
+    let padding = size.saturating_sub(value.len());
+    let mut bits = fill.to_string().repeat(padding);
+    bits.push_str(&value);
+    bits
+}
+
+/// The shortest binary value, without the `b` prefix, that [`extend_bits`]
+/// turns back into `bits`, e.g. `0101` becomes `101` and `xxx1` becomes `x1`.
+pub fn compact_bits(bits: &str) -> &str {
+    let bytes = bits.as_bytes();
+    let mut start = 0;
+    while start + 1 < bytes.len() {
+        let redundant = matches!(
+            (bytes[start], bytes[start + 1]),
+            (b'0', b'0' | b'1') | (b'x', b'x') | (b'z', b'z')
+        );
+        if !redundant {
+            break;
+        }
+        start += 1;
+    }
+    &bits[start..]
+}
+
+/// Group bits from the least significant end into digits of `width` bits.
+fn group_bits(bits: &str, width: usize) -> String {
+    let bits = bits.as_bytes();
+    let mut digits = vec![];
+    let mut end = bits.len();
+    while end > 0 {
+        let start = end.saturating_sub(width);
+        let chunk = &bits[start..end];
+        let digit = if chunk.contains(&b'x') {
+            'x'
+        } else if chunk.contains(&b'z') {
+            'z'
+        } else {
+            let value = chunk
+                .iter()
+                .fold(0, |acc, bit| (acc << 1) | u32::from(*bit == b'1'));
+            std::char::from_digit(value, 16).unwrap()
+        };
+        digits.push(digit);
+        end = start;
+    }
+    digits.iter().rev().collect()
+}
+
+impl FromStr for Radix {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bin" | "binary" => Ok(Radix::Binary),
+            "oct" | "octal" => Ok(Radix::Octal),
+            "dec" | "decimal" => Ok(Radix::Decimal),
+            "hex" => Ok(Radix::Hex),
+            _ => Err(format!("unknown radix '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Radix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Radix::Binary => "binary",
+            Radix::Octal => "octal",
+            Radix::Decimal => "decimal",
+            Radix::Hex => "hex",
+        };
+        f.write_str(name)
+    }
+}
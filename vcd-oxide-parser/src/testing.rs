@@ -0,0 +1,62 @@
+//! Small dumps for the tests of the crates built on the parser, enabled by
+//! the `testing` feature.
+
+use crate::{identifier_code, ValueChangeDump};
+use std::fmt::Display;
+
+/// A wire given by its name, size and `(time, value)` changes. Values are
+/// written as they print, so they are binary digits or levels such as `'x'`.
+pub type Wire<'a, V> = (&'a str, usize, Vec<(usize, V)>);
+
+/// A dump of wires in a `tb` scope with a 1ns timescale.
+pub fn dump<V: Display>(signals: &[Wire<V>]) -> ValueChangeDump {
+    let mut vcd = "$timescale 1ns $end\n$scope module tb $end\n".to_owned();
+    let mut changes = vec![];
+    for (index, (name, size, values)) in signals.iter().enumerate() {
+        let identifier = identifier_code(index);
+        vcd += &format!("$var wire {size} {identifier} {name} $end\n");
+        for (time, value) in values {
+            let change = if *size == 1 {
+                format!("{value}{identifier}")
+            } else {
+                format!("b{value} {identifier}")
+            };
+            changes.push((*time, change));
+        }
+    }
+    vcd += "$upscope $end\n$enddefinitions $end\n";
+    changes.sort_by_key(|(time, _)| *time);
+    for (time, change) in changes {
+        vcd += &format!("#{time}\n{change}\n");
+    }
+    ValueChangeDump::parse(&vcd).unwrap()
+}
+
+/// A dump of a `tb` scope with a clock `clk` rising at `10 * cycle + 5` and
+/// signals changing between edges to the value listed for each cycle. Signals
+/// hold their last value once the list ends, and the clock runs for two more
+/// cycles after the longest list.
+pub fn clocked_dump(signals: &[(&str, usize, &[u64])]) -> ValueChangeDump {
+    let cycles = signals.iter().map(|(_, _, v)| v.len()).max().unwrap_or(0) + 2;
+    let clock = (0..cycles)
+        .flat_map(|cycle| {
+            [
+                (cycle * 10, "0".to_owned()),
+                (cycle * 10 + 5, "1".to_owned()),
+            ]
+        })
+        .collect();
+    let mut waves = vec![("clk", 1, clock)];
+    for (name, size, values) in signals {
+        let mut changes = vec![];
+        let mut previous = None;
+        for (cycle, value) in values.iter().enumerate() {
+            if previous != Some(value) {
+                changes.push((cycle * 10, format!("{value:b}")));
+            }
+            previous = Some(value);
+        }
+        waves.push((name, *size, changes));
+    }
+    dump(&waves)
+}
@@ -0,0 +1,28 @@
+/// Time units with their length in femtoseconds, longest first
+pub(crate) const UNITS: [(&str, u128); 6] = [
+    ("s", 1_000_000_000_000_000),
+    ("ms", 1_000_000_000_000),
+    ("us", 1_000_000_000),
+    ("ns", 1_000_000),
+    ("ps", 1_000),
+    ("fs", 1),
+];
+
+/// Length of a time unit in femtoseconds for a timescale such as `10ns`,
+/// `None` if the timescale isn't valid.
+pub fn timescale_femtoseconds(timescale: &str) -> Option<u128> {
+    let timescale = timescale.trim();
+    let digits = timescale
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(timescale.len());
+    let (number, unit) = timescale.split_at(digits);
+    let number: u128 = number.parse().ok().filter(|number| *number > 0)?;
+    let (_, scale) = UNITS.into_iter().find(|(name, _)| *name == unit.trim())?;
+    Some(number * scale)
+}
+
+/// Length of a time unit in seconds for a timescale such as `10ns`, `None`
+/// if the timescale isn't valid.
+pub fn timescale_seconds(timescale: &str) -> Option<f64> {
+    timescale_femtoseconds(timescale).map(|femtoseconds| femtoseconds as f64 / 1e15)
+}
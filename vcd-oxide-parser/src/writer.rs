@@ -0,0 +1,97 @@
+use crate::{ValueChangeDump, ValueChangeDumpScope};
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, Write},
+};
+
+impl ValueChangeDump {
+    /// Write the dump in VCD format. Changes at the first timestamp are
+    /// written as `$dumpvars`, the others in declaration order per timestamp.
+    pub fn write_vcd(&self, mut writer: impl Write) -> io::Result<()> {
+        for (keyword, value) in [
+            ("date", &self.date),
+            ("version", &self.version),
+            ("timescale", &self.timescale),
+        ] {
+            if !value.trim().is_empty() {
+                writeln!(writer, "${}\n\t{}\n$end", keyword, value.trim())?;
+            }
+        }
+        let root = self.root_scope.borrow();
+        write_vars(&mut writer, &root)?;
+        for scope in &root.scopes {
+            write_scope(&mut writer, &scope.borrow())?;
+        }
+        writeln!(writer, "$enddefinitions $end")?;
+
+        let signals = self.signals();
+        let mut seen = HashSet::new();
+        let mut changes: BTreeMap<usize, Vec<(&str, &str)>> = BTreeMap::new();
+        for sig in &signals {
+            if !seen.insert(sig.identifier.as_str()) {
+                continue;
+            }
+            for value_change in &self.wave(&sig.identifier).value_changes {
+                changes
+                    .entry(value_change.time)
+                    .or_default()
+                    .push((&sig.identifier, &value_change.value));
+            }
+        }
+
+        for (index, (time, changes)) in changes.iter().enumerate() {
+            writeln!(writer, "#{time}")?;
+            if index == 0 {
+                writeln!(writer, "$dumpvars")?;
+            }
+            for (identifier, value) in changes {
+                match value.chars().next() {
+                    Some('b' | 'B' | 'r' | 'R') => writeln!(writer, "{value} {identifier}")?,
+                    _ => writeln!(writer, "{value}{identifier}")?,
+                }
+            }
+            if index == 0 {
+                writeln!(writer, "$end")?;
+            }
+        }
+        // The dump can end after its last change
+        if changes.keys().last().copied().unwrap_or(0) < self.last_time {
+            writeln!(writer, "#{}", self.last_time)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_vcd(&self) -> String {
+        let mut vcd = vec![];
+        self.write_vcd(&mut vcd).unwrap();
+        String::from_utf8(vcd).unwrap()
+    }
+}
+
+fn write_scope(writer: &mut impl Write, scope: &ValueChangeDumpScope) -> io::Result<()> {
+    let kind = if scope.kind.is_empty() {
+        "module"
+    } else {
+        &scope.kind
+    };
+    writeln!(writer, "$scope {} {} $end", kind, scope.name)?;
+    write_vars(writer, scope)?;
+    for child in &scope.scopes {
+        write_scope(writer, &child.borrow())?;
+    }
+    writeln!(writer, "$upscope $end")
+}
+
+fn write_vars(writer: &mut impl Write, scope: &ValueChangeDumpScope) -> io::Result<()> {
+    for sig in &scope.signals {
+        writeln!(
+            writer,
+            "$var {} {} {} {} $end",
+            sig.kind,
+            sig.size,
+            sig.identifier,
+            sig.short_reference()
+        )?;
+    }
+    Ok(())
+}
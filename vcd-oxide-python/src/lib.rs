@@ -0,0 +1,266 @@
+use numpy::PyArray1;
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+};
+use vcd_oxide_parser::{
+    decompress, extend_bits, Radix, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave,
+};
+use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions};
+
+/// Python bindings, importable as `vcd_oxide`.
+#[pymodule]
+fn vcd_oxide(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(open, m)?)?;
+    m.add_class::<Dump>()?;
+    m.add_class::<Signal>()?;
+    m.add_class::<Changes>()?;
+    Ok(())
+}
+
+/// Parse the text of a VCD file.
+#[pyfunction]
+fn parse(text: &str) -> PyResult<Dump> {
+    let vcd = ValueChangeDump::parse(text).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(Dump { vcd })
+}
+
+/// Read a VCD file, decompressing it first if needed.
+#[pyfunction]
+fn open(path: std::path::PathBuf) -> PyResult<Dump> {
+    let bytes = std::fs::read(&path)?;
+    let bytes = decompress(&bytes)?;
+    let text = std::str::from_utf8(&bytes)
+        .map_err(|e| PyValueError::new_err(format!("{}: {}", path.display(), e)))?;
+    parse(text)
+}
+
+/// A parsed dump. Signals are looked up by their hierarchical path, e.g.
+/// `Counter.count`, with or without the bit select. Glob patterns are accepted
+/// and the first matching signal is used.
+#[pyclass(unsendable, module = "vcd_oxide")]
+pub struct Dump {
+    vcd: ValueChangeDump,
+}
+
+#[pyclass(frozen, get_all, module = "vcd_oxide")]
+#[derive(Clone)]
+pub struct Signal {
+    reference: String,
+    kind: String,
+    identifier: String,
+    size: usize,
+}
+
+/// Sample times and values returned by `Dump.sample`.
+type Samples<'py> = (Bound<'py, PyArray1<u64>>, Bound<'py, PyArray1<f64>>);
+
+/// Iterator over the `(time, value)` changes of a signal.
+#[pyclass(unsendable, module = "vcd_oxide")]
+pub struct Changes {
+    dump: Py<Dump>,
+    identifier: String,
+    index: usize,
+}
+
+impl Dump {
+    fn find(&self, path: &str) -> PyResult<(ValueChangeDumpSignal, &ValueChangeDumpWave)> {
+        let sig = self
+            .vcd
+            .signals()
+            .into_iter()
+            .find(|sig| sig.matches(path))
+            .ok_or_else(|| PyKeyError::new_err(path.to_owned()))?;
+        let wave = self.vcd.wave(&sig.identifier);
+        Ok((sig, wave))
+    }
+}
+
+#[pymethods]
+impl Dump {
+    #[getter]
+    fn date(&self) -> &str {
+        self.vcd.date.trim()
+    }
+
+    #[getter]
+    fn version(&self) -> &str {
+        self.vcd.version.trim()
+    }
+
+    #[getter]
+    fn timescale(&self) -> &str {
+        self.vcd.timescale.trim()
+    }
+
+    /// Time of the last value change in the dump.
+    #[getter]
+    fn end_time(&self) -> usize {
+        self.vcd.end_time()
+    }
+
+    fn signals(&self) -> Vec<Signal> {
+        self.vcd.signals().into_iter().map(Signal::from).collect()
+    }
+
+    fn signal(&self, path: &str) -> PyResult<Signal> {
+        Ok(self.find(path)?.0.into())
+    }
+
+    /// The value of a signal at `time`, or `None` before its first change.
+    /// Values are returned as written in the dump, e.g. `b1010`, unless a
+    /// radix (`binary`, `octal`, `decimal` or `hex`) is given.
+    #[pyo3(signature = (path, time, radix = None))]
+    fn value_at(&self, path: &str, time: usize, radix: Option<&str>) -> PyResult<Option<String>> {
+        let radix = radix.map(parse_option::<Radix>).transpose()?;
+        let (sig, wave) = self.find(path)?;
+        Ok(wave.value_at(time).map(|vc| match radix {
+            Some(radix) => radix.format(&vc.value, sig.size),
+            None => vc.value.to_owned(),
+        }))
+    }
+
+    fn changes(slf: PyRef<'_, Self>, path: &str) -> PyResult<Changes> {
+        let identifier = slf.find(path)?.0.identifier;
+        Ok(Changes {
+            dump: slf.into(),
+            identifier,
+            index: 0,
+        })
+    }
+
+    /// Sample a signal every `period` time units from `start` up to `end`
+    /// (exclusive, defaulting to just after the last change). Returns a pair of
+    /// numpy arrays: the `uint64` sample times and the `float64` values, with
+    /// `nan` where the signal is unassigned or has unknown bits.
+    #[pyo3(signature = (path, period = 1, start = 0, end = None))]
+    fn sample<'py>(
+        &self,
+        py: Python<'py>,
+        path: &str,
+        period: usize,
+        start: usize,
+        end: Option<usize>,
+    ) -> PyResult<Samples<'py>> {
+        if period == 0 {
+            return Err(PyValueError::new_err("period must be positive"));
+        }
+        let (sig, wave) = self.find(path)?;
+        let end = end.unwrap_or_else(|| self.vcd.end_time() + 1);
+        let times: Vec<usize> = (start..end).step_by(period).collect();
+        let values = times
+            .iter()
+            .map(|time| {
+                wave.value_at(*time)
+                    .map_or(f64::NAN, |vc| numeric_value(&vc.value, sig.size))
+            })
+            .collect();
+        Ok((
+            PyArray1::from_vec(py, times.into_iter().map(|time| time as u64).collect()),
+            PyArray1::from_vec(py, values),
+        ))
+    }
+
+    /// Convert the dump to WaveJson, with the options of `vcd-oxide convert`.
+    #[pyo3(signature = (
+        signals = vec![],
+        radix = None,
+        start = None,
+        end = None,
+        period = None,
+        expand_busses = false,
+        group_by = None,
+        naming = None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn to_wavejson(
+        &self,
+        signals: Vec<String>,
+        radix: Option<&str>,
+        start: Option<usize>,
+        end: Option<usize>,
+        period: Option<usize>,
+        expand_busses: bool,
+        group_by: Option<&str>,
+        naming: Option<&str>,
+    ) -> PyResult<String> {
+        let options = WaveJsonOptions {
+            signals,
+            radix: radix.map(parse_option).transpose()?,
+            start,
+            end,
+            period,
+            expand_busses,
+            group_by: group_by.map(parse_option).transpose()?.unwrap_or_default(),
+            naming: naming.map(parse_option).transpose()?.unwrap_or_default(),
+            ..Default::default()
+        };
+        Ok(WaveJson::from_vcd(&self.vcd, &options).to_json())
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "<Dump {} signals, timescale {}, end time {}>",
+            self.vcd.signals().len(),
+            self.vcd.timescale.trim(),
+            self.vcd.end_time()
+        )
+    }
+}
+
+#[pymethods]
+impl Signal {
+    fn __repr__(&self) -> String {
+        format!("<Signal {} {} {}>", self.reference, self.kind, self.size)
+    }
+}
+
+#[pymethods]
+impl Changes {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(usize, String)> {
+        let dump = slf.dump.clone_ref(slf.py());
+        let dump = dump.borrow(slf.py());
+        let value_change = dump
+            .vcd
+            .wave(&slf.identifier)
+            .value_changes
+            .get(slf.index)?;
+        slf.index += 1;
+        Some((value_change.time, value_change.value.to_owned()))
+    }
+}
+
+impl From<ValueChangeDumpSignal> for Signal {
+    fn from(sig: ValueChangeDumpSignal) -> Self {
+        Signal {
+            reference: sig.reference,
+            kind: sig.kind,
+            identifier: sig.identifier,
+            size: sig.size,
+        }
+    }
+}
+
+fn parse_option<T: std::str::FromStr<Err = String>>(value: &str) -> PyResult<T> {
+    value.parse().map_err(PyValueError::new_err)
+}
+
+/// A value as a number: reals as is, vectors as unsigned integers, which
+/// lose precision past 53 bits. Values with `x` or `z` bits are `nan`.
+fn numeric_value(value: &str, size: usize) -> f64 {
+    if let Some(real) = value.strip_prefix(['r', 'R']) {
+        return real.parse().unwrap_or(f64::NAN);
+    }
+    let bits = extend_bits(value, size);
+    if !bits.chars().all(|c| matches!(c, '0' | '1')) {
+        return f64::NAN;
+    }
+    bits.chars().fold(0.0, |number, bit| {
+        number * 2.0 + if bit == '1' { 1.0 } else { 0.0 }
+    })
+}
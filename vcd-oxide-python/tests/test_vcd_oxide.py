@@ -0,0 +1,58 @@
+import json
+import math
+from pathlib import Path
+
+import pytest
+import vcd_oxide
+
+COUNTER = Path(__file__).parents[2] / "vcd-oxide-parser" / "test" / "Counter.vcd"
+
+
+@pytest.fixture
+def dump():
+    return vcd_oxide.open(COUNTER)
+
+
+def test_header(dump):
+    assert dump.timescale == "1s"
+    assert dump.version == "Icarus Verilog"
+    assert dump.end_time == 6
+
+
+def test_signal_lookup(dump):
+    assert [sig.reference for sig in dump.signals()] == ["Counter.count [3:0]", "Counter.clk"]
+    count = dump.signal("Counter.count")
+    assert (count.kind, count.size, count.identifier) == ("reg", 4, "!")
+    assert dump.signal("*.clk").reference == "Counter.clk"
+    with pytest.raises(KeyError):
+        dump.signal("Counter.missing")
+
+
+def test_value_at(dump):
+    assert dump.value_at("Counter.count", 3) == "b10"
+    assert dump.value_at("Counter.count", 3, radix="decimal") == "2"
+    assert dump.value_at("Counter.count", 0, radix="hex") == "x"
+    with pytest.raises(ValueError):
+        dump.value_at("Counter.count", 3, radix="roman")
+
+
+def test_changes(dump):
+    assert list(dump.changes("Counter.clk"))[:3] == [(0, "0"), (1, "1"), (2, "0")]
+
+
+def test_sample(dump):
+    times, values = dump.sample("Counter.count", period=2)
+    assert times.dtype.name == "uint64"
+    assert times.tolist() == [0, 2, 4, 6]
+    assert math.isnan(values[0])
+    assert values[1:].tolist() == [1.0, 3.0, 5.0]
+
+
+def test_to_wavejson(dump):
+    wave = json.loads(dump.to_wavejson(signals=["*.clk"], end=4))
+    assert wave["signal"][0] == {"name": "Counter.clk", "wave": "lhlh", "data": []}
+
+
+def test_parse_error():
+    with pytest.raises(ValueError, match="syntax error"):
+        vcd_oxide.parse("not a dump")
@@ -0,0 +1,207 @@
+use crate::{Error, Tokens};
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, Write},
+};
+
+/// The declarations of a dump, kept as a scope tree so pieces can take
+/// parts of it.
+#[derive(Debug, Default)]
+pub(crate) struct Header {
+    /// `$date`, `$version`, `$timescale` and `$comment` with their text
+    pub(crate) keywords: Vec<(String, String)>,
+    /// Scopes with the root, holding signals declared outside any scope, first
+    pub(crate) scopes: Vec<Scope>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Scope {
+    pub(crate) kind: String,
+    pub(crate) name: String,
+    pub(crate) vars: Vec<Var>,
+    pub(crate) children: Vec<usize>,
+    pub(crate) parent: Option<usize>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Var {
+    pub(crate) kind: String,
+    pub(crate) size: String,
+    pub(crate) identifier: String,
+    pub(crate) reference: String,
+}
+
+impl Header {
+    /// Read the declarations up to and including `$enddefinitions $end`.
+    pub(crate) fn read(tokens: &mut Tokens<impl BufRead>) -> Result<Self, Error> {
+        let mut header = Header {
+            scopes: vec![Scope::default()],
+            ..Default::default()
+        };
+        let mut current = 0;
+        loop {
+            let Some(keyword) = tokens.next()? else {
+                return Err(tokens.syntax("missing $enddefinitions"));
+            };
+            let body = tokens.until_end()?;
+            match keyword.as_str() {
+                "$enddefinitions" => return Ok(header),
+                "$scope" => {
+                    let [kind, name] = <[String; 2]>::try_from(body)
+                        .map_err(|_| tokens.syntax("expected $scope <kind> <name> $end"))?;
+                    let index = header.scopes.len();
+                    header.scopes.push(Scope {
+                        kind,
+                        name,
+                        parent: Some(current),
+                        ..Default::default()
+                    });
+                    header.scopes[current].children.push(index);
+                    current = index;
+                }
+                "$upscope" => {
+                    current = header.scopes[current]
+                        .parent
+                        .ok_or_else(|| tokens.syntax("$upscope without a matching $scope"))?;
+                }
+                "$var" => {
+                    if body.len() < 4 {
+                        return Err(tokens
+                            .syntax("expected $var <kind> <size> <identifier> <reference> $end"));
+                    }
+                    let mut body = body.into_iter();
+                    let var = Var {
+                        kind: body.next().unwrap(),
+                        size: body.next().unwrap(),
+                        identifier: body.next().unwrap(),
+                        reference: body.collect::<Vec<_>>().join(" "),
+                    };
+                    header.scopes[current].vars.push(var);
+                }
+                _ if keyword.starts_with('$') => header.keywords.push((keyword, body.join(" "))),
+                _ => return Err(tokens.syntax(&format!("unexpected '{keyword}'"))),
+            }
+        }
+    }
+
+    /// Names of the scopes from the top level down to `index`, e.g. `tb.dut`.
+    pub(crate) fn path(&self, index: usize) -> String {
+        let mut names = vec![];
+        let mut scope = Some(index);
+        while let Some(index) = scope.filter(|index| *index != 0) {
+            names.push(self.scopes[index].name.as_str());
+            scope = self.scopes[index].parent;
+        }
+        names.reverse();
+        names.join(".")
+    }
+
+    /// Scopes cut out as pieces at `depth`, where the top level scopes are at
+    /// depth 1. Shallower scopes without child scopes are pieces too, so no
+    /// signal is left out.
+    pub(crate) fn pieces(&self, depth: usize) -> Vec<usize> {
+        let mut pieces = vec![];
+        let mut stack = vec![(0, 0)];
+        while let Some((index, level)) = stack.pop() {
+            let scope = &self.scopes[index];
+            if level == depth || (level > 0 && scope.children.is_empty()) {
+                pieces.push(index);
+            } else {
+                stack.extend(scope.children.iter().rev().map(|child| (*child, level + 1)));
+            }
+        }
+        pieces
+    }
+
+    /// Identifier codes of the whole dump or of a piece, in declaration
+    /// order without repeats.
+    pub(crate) fn identifiers(&self, piece: Option<usize>) -> Vec<&str> {
+        let mut scopes = vec![];
+        match piece {
+            Some(piece) => {
+                scopes.extend(self.ancestors(piece).into_iter().map(|a| &self.scopes[a]));
+                self.walk(piece, &mut scopes);
+            }
+            None => self.walk(0, &mut scopes),
+        }
+        let mut seen = HashSet::new();
+        scopes
+            .into_iter()
+            .flat_map(|scope| &scope.vars)
+            .map(|var| var.identifier.as_str())
+            .filter(|identifier| seen.insert(*identifier))
+            .collect()
+    }
+
+    /// The scope at `index` and every scope below it, depth first.
+    fn walk<'a>(&'a self, index: usize, scopes: &mut Vec<&'a Scope>) {
+        scopes.push(&self.scopes[index]);
+        for child in &self.scopes[index].children {
+            self.walk(*child, scopes);
+        }
+    }
+
+    /// Scopes containing `index`, from the root down, without `index` itself.
+    fn ancestors(&self, index: usize) -> Vec<usize> {
+        let mut ancestors = vec![];
+        let mut scope = self.scopes[index].parent;
+        while let Some(index) = scope {
+            ancestors.push(index);
+            scope = self.scopes[index].parent;
+        }
+        ancestors.reverse();
+        ancestors
+    }
+
+    /// Write the declarations of the whole dump, or of one piece with the
+    /// signals of the scopes above it.
+    pub(crate) fn write(&self, writer: &mut impl Write, piece: Option<usize>) -> io::Result<()> {
+        for (keyword, body) in &self.keywords {
+            writeln!(writer, "{keyword}\n\t{body}\n$end")?;
+        }
+        match piece {
+            Some(piece) => {
+                let ancestors = self.ancestors(piece);
+                for ancestor in &ancestors {
+                    let scope = &self.scopes[*ancestor];
+                    if *ancestor != 0 {
+                        writeln!(writer, "$scope {} {} $end", scope.kind, scope.name)?;
+                    }
+                    write_vars(writer, scope)?;
+                }
+                self.write_scope(writer, piece)?;
+                for _ in ancestors.iter().filter(|ancestor| **ancestor != 0) {
+                    writeln!(writer, "$upscope $end")?;
+                }
+            }
+            None => self.write_scope(writer, 0)?,
+        }
+        writeln!(writer, "$enddefinitions $end")
+    }
+
+    fn write_scope(&self, writer: &mut impl Write, index: usize) -> io::Result<()> {
+        let scope = &self.scopes[index];
+        if index != 0 {
+            writeln!(writer, "$scope {} {} $end", scope.kind, scope.name)?;
+        }
+        write_vars(writer, scope)?;
+        for child in &scope.children {
+            self.write_scope(writer, *child)?;
+        }
+        if index != 0 {
+            writeln!(writer, "$upscope $end")?;
+        }
+        Ok(())
+    }
+}
+
+fn write_vars(writer: &mut impl Write, scope: &Scope) -> io::Result<()> {
+    for var in &scope.vars {
+        writeln!(
+            writer,
+            "$var {} {} {} {} $end",
+            var.kind, var.size, var.identifier, var.reference
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,498 @@
+mod header;
+
+use crate::header::Header;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    io::{self, BufRead, Write},
+};
+
+/// How a dump is cut into pieces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitMode {
+    /// Slices of this many time units, starting at time 0
+    Time(usize),
+    /// One piece per scope at this depth, where the top level scopes are at depth 1
+    Scope(usize),
+}
+
+/// A piece of a split dump, passed to the function creating its output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece {
+    /// The value changes from `start` up to but excluding `end`. Every slice
+    /// but the first starts with the values at `start` as `$dumpvars`.
+    Slice { start: usize, end: usize },
+    /// The signals of a scope and the scopes below it, e.g. `tb.dut`, along
+    /// with those declared directly in the scopes above it.
+    Scope { path: String },
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The input does not follow the VCD format
+    Syntax {
+        line: usize,
+        message: String,
+    },
+    /// A value change refers to an identifier code without a `$var` declaration
+    UnknownIdentifier(String),
+    /// A slice length or depth of 0
+    InvalidMode(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Syntax { line, message } => write!(f, "line {line}: {message}"),
+            Error::UnknownIdentifier(identifier) => write!(
+                f,
+                "value change for undeclared identifier code '{identifier}'"
+            ),
+            Error::InvalidMode(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl Piece {
+    /// File name for a piece of the dump called `stem`, e.g. `soc.0-1000.vcd`
+    /// or `soc.tb.dut.vcd`.
+    pub fn file_name(&self, stem: &str) -> String {
+        match self {
+            Piece::Slice { start, end } => format!("{stem}.{start}-{end}.vcd"),
+            Piece::Scope { path } => format!("{}.{}.vcd", stem, path.replace(['/', '\\'], "_")),
+        }
+    }
+}
+
+/// Split a VCD dump while reading it, so only the declarations and the
+/// current value of each signal are held in memory. `create` is called with
+/// each piece to get the writer it is written to. Time slices are created as
+/// the dump reaches them and left out when they have no value changes; scope
+/// pieces are all created up front.
+pub fn split_vcd<W: Write>(
+    reader: impl BufRead,
+    mode: SplitMode,
+    create: impl FnMut(&Piece) -> io::Result<W>,
+) -> Result<Vec<Piece>, Error> {
+    let mut tokens = Tokens::new(reader);
+    match mode {
+        SplitMode::Time(0) => Err(Error::InvalidMode(
+            "time slices must be at least 1 time unit long",
+        )),
+        SplitMode::Scope(0) => Err(Error::InvalidMode("the top level scopes are at depth 1")),
+        SplitMode::Time(every) => {
+            let header = Header::read(&mut tokens)?;
+            split_time(tokens, &header, every, create)
+        }
+        SplitMode::Scope(depth) => {
+            let header = Header::read(&mut tokens)?;
+            split_scopes(tokens, &header, depth, create)
+        }
+    }
+}
+
+fn split_time<W: Write>(
+    mut tokens: Tokens<impl BufRead>,
+    header: &Header,
+    every: usize,
+    mut create: impl FnMut(&Piece) -> io::Result<W>,
+) -> Result<Vec<Piece>, Error> {
+    let identifiers = header.identifiers(None);
+    let known: HashSet<&str> = identifiers.iter().copied().collect();
+    let mut values: HashMap<String, String> = HashMap::new();
+    let mut pieces = vec![];
+    let mut output: Option<(usize, Output<W>)> = None;
+    let mut time = 0;
+    let mut block = None;
+    while let Some(event) = tokens.next_event()? {
+        match event {
+            Event::Time(t) => time = t,
+            Event::Block(keyword) => block = Some(keyword),
+            Event::EndBlock => {
+                block = None;
+                if let Some((_, output)) = &mut output {
+                    output.end_block()?;
+                }
+            }
+            Event::Change { identifier, value } => {
+                if !known.contains(identifier.as_str()) {
+                    return Err(Error::UnknownIdentifier(identifier));
+                }
+                let start = time / every * every;
+                if output.as_ref().map(|(start, _)| *start) != Some(start) {
+                    if let Some((_, output)) = output.take() {
+                        output.finish()?;
+                    }
+                    let piece = Piece::Slice {
+                        start,
+                        end: start + every,
+                    };
+                    let mut writer = create(&piece)?;
+                    header.write(&mut writer, None)?;
+                    let mut slice = Output::new(writer);
+                    if !values.is_empty() {
+                        slice.write_values(start, &identifiers, &values)?;
+                    }
+                    output = Some((start, slice));
+                    pieces.push(piece);
+                }
+                let (_, slice) = output.as_mut().unwrap();
+                slice.write_change(time, block.as_deref(), &change(&value, &identifier))?;
+                values.insert(identifier, value);
+            }
+        }
+    }
+    if let Some((_, output)) = output {
+        output.finish()?;
+    }
+    Ok(pieces)
+}
+
+fn split_scopes<W: Write>(
+    mut tokens: Tokens<impl BufRead>,
+    header: &Header,
+    depth: usize,
+    mut create: impl FnMut(&Piece) -> io::Result<W>,
+) -> Result<Vec<Piece>, Error> {
+    let mut pieces = vec![];
+    let mut outputs = vec![];
+    // Pieces each identifier code is written to
+    let mut routes: HashMap<&str, Vec<usize>> = HashMap::new();
+    for scope in header.pieces(depth) {
+        let piece = Piece::Scope {
+            path: header.path(scope),
+        };
+        let mut writer = create(&piece)?;
+        header.write(&mut writer, Some(scope))?;
+        for identifier in header.identifiers(Some(scope)) {
+            routes.entry(identifier).or_default().push(outputs.len());
+        }
+        outputs.push(Output::new(writer));
+        pieces.push(piece);
+    }
+
+    let known: HashSet<&str> = header.identifiers(None).into_iter().collect();
+    let mut time = 0;
+    let mut block = None;
+    while let Some(event) = tokens.next_event()? {
+        match event {
+            Event::Time(t) => time = t,
+            Event::Block(keyword) => block = Some(keyword),
+            Event::EndBlock => {
+                block = None;
+                for output in &mut outputs {
+                    output.end_block()?;
+                }
+            }
+            Event::Change { identifier, value } => {
+                if !known.contains(identifier.as_str()) {
+                    return Err(Error::UnknownIdentifier(identifier));
+                }
+                let line = change(&value, &identifier);
+                for index in routes.get(identifier.as_str()).into_iter().flatten() {
+                    outputs[*index].write_change(time, block.as_deref(), &line)?;
+                }
+            }
+        }
+    }
+    for output in outputs {
+        output.finish()?;
+    }
+    Ok(pieces)
+}
+
+/// A value change as written in a dump, e.g. `1!` or `b1010 #`.
+fn change(value: &str, identifier: &str) -> String {
+    match value.chars().next() {
+        Some('b' | 'B' | 'r' | 'R') => format!("{value} {identifier}"),
+        _ => format!("{value}{identifier}"),
+    }
+}
+
+/// A piece being written. Timestamps and `$dumpvars` style blocks are only
+/// written once a value change for the piece needs them.
+struct Output<W> {
+    writer: W,
+    time: Option<usize>,
+    in_block: bool,
+}
+
+impl<W: Write> Output<W> {
+    fn new(writer: W) -> Self {
+        Output {
+            writer,
+            time: None,
+            in_block: false,
+        }
+    }
+
+    fn write_change(&mut self, time: usize, block: Option<&str>, change: &str) -> io::Result<()> {
+        if self.time != Some(time) {
+            writeln!(self.writer, "#{time}")?;
+            self.time = Some(time);
+        }
+        if let (Some(keyword), false) = (block, self.in_block) {
+            writeln!(self.writer, "{keyword}")?;
+            self.in_block = true;
+        }
+        writeln!(self.writer, "{change}")
+    }
+
+    fn end_block(&mut self) -> io::Result<()> {
+        if self.in_block {
+            writeln!(self.writer, "$end")?;
+            self.in_block = false;
+        }
+        Ok(())
+    }
+
+    /// The values of every assigned signal at `time` as `$dumpvars`.
+    fn write_values(
+        &mut self,
+        time: usize,
+        identifiers: &[&str],
+        values: &HashMap<String, String>,
+    ) -> io::Result<()> {
+        writeln!(self.writer, "#{time}\n$dumpvars")?;
+        for identifier in identifiers {
+            if let Some(value) = values.get(*identifier) {
+                writeln!(self.writer, "{}", change(value, identifier))?;
+            }
+        }
+        writeln!(self.writer, "$end")?;
+        self.time = Some(time);
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<()> {
+        self.end_block()?;
+        self.writer.flush()
+    }
+}
+
+enum Event {
+    Time(usize),
+    /// `$dumpvars`, `$dumpall`, `$dumpon` or `$dumpoff`
+    Block(String),
+    EndBlock,
+    Change {
+        identifier: String,
+        value: String,
+    },
+}
+
+/// Whitespace separated tokens of a dump, read a line at a time.
+pub(crate) struct Tokens<R> {
+    reader: R,
+    buffer: String,
+    tokens: VecDeque<String>,
+    line: usize,
+}
+
+impl<R: BufRead> Tokens<R> {
+    fn new(reader: R) -> Self {
+        Tokens {
+            reader,
+            buffer: String::new(),
+            tokens: VecDeque::new(),
+            line: 0,
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Result<Option<String>, Error> {
+        while self.tokens.is_empty() {
+            self.buffer.clear();
+            if self.reader.read_line(&mut self.buffer)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+            self.tokens
+                .extend(self.buffer.split_whitespace().map(str::to_owned));
+        }
+        Ok(self.tokens.pop_front())
+    }
+
+    /// The tokens up to the next `$end`, which is consumed.
+    pub(crate) fn until_end(&mut self) -> Result<Vec<String>, Error> {
+        let mut body = vec![];
+        loop {
+            match self.next()? {
+                Some(token) if token == "$end" => return Ok(body),
+                Some(token) => body.push(token),
+                None => return Err(self.syntax("missing $end")),
+            }
+        }
+    }
+
+    pub(crate) fn syntax(&self, message: &str) -> Error {
+        Error::Syntax {
+            line: self.line,
+            message: message.to_owned(),
+        }
+    }
+
+    fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        loop {
+            let Some(token) = self.next()? else {
+                return Ok(None);
+            };
+            let event = match token.chars().next() {
+                Some('#') => Event::Time(
+                    token[1..]
+                        .parse()
+                        .map_err(|_| self.syntax(&format!("invalid time '{token}'")))?,
+                ),
+                Some('$') => match token.as_str() {
+                    "$end" => Event::EndBlock,
+                    "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" => Event::Block(token),
+                    "$comment" => {
+                        self.until_end()?;
+                        continue;
+                    }
+                    _ => return Err(self.syntax(&format!("unexpected '{token}'"))),
+                },
+                Some('0' | '1' | 'x' | 'X' | 'z' | 'Z') if token.len() > 1 => Event::Change {
+                    identifier: token[1..].to_owned(),
+                    value: token[..1].to_owned(),
+                },
+                Some('b' | 'B' | 'r' | 'R') => Event::Change {
+                    identifier: self
+                        .next()?
+                        .ok_or_else(|| self.syntax("missing identifier code"))?,
+                    value: token,
+                },
+                _ => return Err(self.syntax(&format!("unexpected '{token}'"))),
+            };
+            return Ok(Some(event));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use std::{cell::RefCell, rc::Rc};
+    use vcd_oxide_parser::ValueChangeDump;
+
+    const DUMP: &str = "$date today $end\n$timescale 1ns $end\n\
+        $scope module tb $end\n$var wire 1 ! clk $end\n\
+        $scope module cpu $end\n$var wire 8 \" pc [7:0] $end\n$upscope $end\n\
+        $scope module gpu $end\n$var wire 1 # busy $end\n\
+        $scope module core $end\n$var wire 1 ! clk $end\n$upscope $end\n$upscope $end\n\
+        $upscope $end\n$enddefinitions $end\n\
+        #0\n$dumpvars\n0!\nb0 \"\n0#\n$end\n#5\n1!\n#10\n0!\nb1 \"\n$comment x $end\n\
+        #15\n1!\n1#\n#20\n0!\nb10 \"\n#35\n1!\n0#\n";
+
+    /// A writer whose content is kept after the splitter drops it.
+    #[derive(Clone, Default)]
+    struct Buffer(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Split `input` and return each piece with its content.
+    fn split(input: &str, mode: SplitMode) -> Result<Vec<(Piece, String)>, Error> {
+        let mut buffers = vec![];
+        let pieces = split_vcd(input.as_bytes(), mode, |_| {
+            let buffer = Buffer::default();
+            buffers.push(buffer.clone());
+            Ok(buffer)
+        })?;
+        Ok(pieces
+            .into_iter()
+            .zip(buffers)
+            .map(|(piece, buffer)| (piece, String::from_utf8(buffer.0.take()).unwrap()))
+            .collect())
+    }
+
+    #[test]
+    fn test_time_slices() {
+        let pieces = split(DUMP, SplitMode::Time(10)).unwrap();
+        let starts: Vec<_> = pieces
+            .iter()
+            .map(|(piece, _)| match piece {
+                Piece::Slice { start, .. } => *start,
+                _ => unreachable!(),
+            })
+            .collect();
+        // Nothing changes between 20 and 30
+        assert_eq!(starts, [0, 10, 20, 30]);
+        assert_eq!(pieces[1].0.file_name("tb"), "tb.10-20.vcd");
+        assert_snapshot!(pieces[1].1);
+
+        // Each slice starts with the values of the whole dump at that time
+        let vcd = ValueChangeDump::parse(DUMP).unwrap();
+        for ((_, content), start) in pieces.iter().zip(starts) {
+            let slice = ValueChangeDump::parse(content).unwrap();
+            assert_eq!(slice.signals().len(), vcd.signals().len());
+            for (identifier, wave) in &vcd.wave_map {
+                assert_eq!(
+                    slice.wave(identifier).value_at(start).map(|vc| &vc.value),
+                    wave.value_at(start).map(|vc| &vc.value),
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_scopes() {
+        let paths = |depth| {
+            split(DUMP, SplitMode::Scope(depth))
+                .unwrap()
+                .into_iter()
+                .map(|(piece, _)| piece.file_name("dump"))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paths(1), ["dump.tb.vcd"]);
+        assert_eq!(paths(2), ["dump.tb.cpu.vcd", "dump.tb.gpu.vcd"]);
+        assert_eq!(paths(3), ["dump.tb.cpu.vcd", "dump.tb.gpu.core.vcd"]);
+
+        let pieces = split(DUMP, SplitMode::Scope(2)).unwrap();
+        assert_snapshot!(pieces[1].1);
+        let cpu = ValueChangeDump::parse(&pieces[0].1).unwrap();
+        let references: Vec<_> = cpu.signals().into_iter().map(|sig| sig.reference).collect();
+        assert_eq!(references, ["tb.cpu.pc [7:0]", "tb.clk"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |input: &str, mode| split(input, mode).unwrap_err().to_string();
+        assert_eq!(
+            error(DUMP, SplitMode::Time(0)),
+            "time slices must be at least 1 time unit long"
+        );
+        assert_eq!(
+            error("$scope module tb $end\n", SplitMode::Scope(1)),
+            "line 1: missing $enddefinitions"
+        );
+        let unknown = DUMP.replace("#35\n1!", "#35\n1?");
+        assert_eq!(
+            error(&unknown, SplitMode::Time(100)),
+            "value change for undeclared identifier code '?'"
+        );
+        let invalid = DUMP.replace("#35", "#3x5");
+        assert_eq!(
+            error(&invalid, SplitMode::Scope(1)),
+            "line 34: invalid time '#3x5'"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt::Write;
+use vcd_oxide_parser::ValueChangeDump;
+use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions, WaveJsonSignal, WaveJsonSignalItem};
+
+const PADDING: usize = 10;
+const AXIS_HEIGHT: usize = 20;
+const LANE_SPACING: usize = 10;
+const GROUP_INDENT: usize = 10;
+const CHAR_WIDTH: usize = 7;
+const SLANT: f32 = 3.0;
+
+/// Layout and time axis settings for SVG rendering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    /// Width of a single wave character in pixels
+    pub column_width: usize,
+    /// Height of a signal lane in pixels
+    pub lane_height: usize,
+    /// Simulation time of the first column
+    pub time_start: usize,
+    /// Simulation time covered by each column
+    pub time_step: usize,
+    /// Unit shown on the time axis, e.g. `1ns`
+    pub timescale: String,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            column_width: 20,
+            lane_height: 20,
+            time_start: 0,
+            time_step: 1,
+            timescale: "".to_owned(),
+        }
+    }
+}
+
+/// A rendered SVG waveform document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WaveSvg {
+    document: String,
+}
+
+/// A run of columns holding the same value.
+#[derive(Debug, PartialEq)]
+struct Segment {
+    start: usize,
+    len: usize,
+    level: Level,
+}
+
+#[derive(Debug, PartialEq)]
+enum Level {
+    Low,
+    High,
+    HighImpedance,
+    Unknown,
+    Data { label: String, fill: &'static str },
+    Clock { rising: bool },
+}
+
+/// A signal or group header laid out on its own row.
+enum Row<'a> {
+    Signal(&'a WaveJsonSignal, usize),
+    Group(&'a str, usize),
+}
+
+impl WaveSvg {
+    pub fn from_wavejson(wavejson: &WaveJson, options: &SvgOptions) -> Self {
+        let mut rows = vec![];
+        flatten_rows(&wavejson.signal, 0, &mut rows);
+
+        let columns = rows
+            .iter()
+            .filter_map(|row| match row {
+                Row::Signal(signal, _) => signal.wave.as_ref().map(|wave| wave.chars().count()),
+                Row::Group(..) => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let name_width = rows
+            .iter()
+            .map(|row| match row {
+                Row::Signal(signal, depth) => {
+                    signal.name.as_deref().unwrap_or("").chars().count() * CHAR_WIDTH
+                        + depth * GROUP_INDENT
+                }
+                Row::Group(name, depth) => name.chars().count() * CHAR_WIDTH + depth * GROUP_INDENT,
+            })
+            .max()
+            .unwrap_or(0)
+            + 2 * PADDING;
+
+        let pitch = options.lane_height + LANE_SPACING;
+        let x0 = PADDING + name_width;
+        let y0 = PADDING + AXIS_HEIGHT;
+        let width = x0 + columns * options.column_width + PADDING;
+        let height = y0 + rows.len() * pitch + PADDING;
+
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="monospace" font-size="11">"#,
+        )
+        .unwrap();
+        svg += concat!(
+            r#"<defs><pattern id="hatch" width="4" height="4" patternUnits="userSpaceOnUse" patternTransform="rotate(45)">"#,
+            r##"<line x1="0" y1="0" x2="0" y2="4" stroke="#f00" stroke-width="1"/></pattern></defs>"##,
+            "\n"
+        );
+        writeln!(
+            svg,
+            r##"<rect width="{width}" height="{height}" fill="#fff"/>"##
+        )
+        .unwrap();
+
+        render_grid(&mut svg, options, columns, x0, y0, height - PADDING);
+        for (index, row) in rows.iter().enumerate() {
+            let y = y0 + index * pitch + LANE_SPACING / 2;
+            match row {
+                Row::Group(name, depth) => {
+                    writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" font-weight="bold">{}</text>"#,
+                        PADDING + depth * GROUP_INDENT,
+                        y + options.lane_height * 3 / 4,
+                        escape(name)
+                    )
+                    .unwrap();
+                }
+                Row::Signal(signal, depth) => {
+                    writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}">{}</text>"#,
+                        PADDING + depth * GROUP_INDENT,
+                        y + options.lane_height * 3 / 4,
+                        escape(signal.name.as_deref().unwrap_or(""))
+                    )
+                    .unwrap();
+                    render_lane(&mut svg, signal, options, x0, y);
+                }
+            }
+        }
+        svg += "</svg>\n";
+
+        WaveSvg { document: svg }
+    }
+
+    /// Render a dump through its WaveJson representation, labelling the time
+    /// axis according to the conversion window and sampling period.
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &WaveJsonOptions) -> Self {
+        let svg_options = SvgOptions {
+            time_start: options.start.unwrap_or(0),
+            time_step: options.period.unwrap_or(1),
+            timescale: vcd.timescale.to_owned(),
+            ..Default::default()
+        };
+        let wavejson = WaveJson::from_vcd(vcd, options);
+        Self::from_wavejson(&wavejson, &svg_options)
+    }
+
+    pub fn to_svg(&self) -> String {
+        self.document.to_owned()
+    }
+}
+
+fn flatten_rows<'a>(items: &'a [WaveJsonSignalItem], depth: usize, rows: &mut Vec<Row<'a>>) {
+    for item in items {
+        match item {
+            WaveJsonSignalItem::Signal(signal) => rows.push(Row::Signal(signal, depth)),
+            WaveJsonSignalItem::Group(group) => {
+                rows.push(Row::Group(group.name.as_deref().unwrap_or(""), depth));
+                flatten_rows(&group.signals, depth + 1, rows);
+            }
+        }
+    }
+}
+
+/// Draw the column grid and the time axis above it.
+fn render_grid(
+    svg: &mut String,
+    options: &SvgOptions,
+    columns: usize,
+    x0: usize,
+    y0: usize,
+    bottom: usize,
+) {
+    // Keep axis labels at least 40px apart
+    let label_every = (40 + options.column_width - 1) / options.column_width.max(1);
+    writeln!(
+        svg,
+        r##"<text x="{}" y="{}" fill="#888">{}</text>"##,
+        PADDING,
+        PADDING + AXIS_HEIGHT / 2,
+        escape(&options.timescale)
+    )
+    .unwrap();
+    for column in 0..=columns {
+        let x = x0 + column * options.column_width;
+        writeln!(
+            svg,
+            r##"<line x1="{x}" y1="{y0}" x2="{x}" y2="{bottom}" stroke="#eee"/>"##,
+        )
+        .unwrap();
+        if column % label_every.max(1) == 0 {
+            writeln!(
+                svg,
+                r##"<text x="{}" y="{}" fill="#888" text-anchor="middle">{}</text>"##,
+                x,
+                PADDING + AXIS_HEIGHT / 2,
+                options.time_start + column * options.time_step
+            )
+            .unwrap();
+        }
+    }
+}
+
+/// Split a wave string into segments, consuming data labels in order.
+fn parse_segments(signal: &WaveJsonSignal) -> Vec<Segment> {
+    let mut data = signal.data.iter().flatten();
+    let mut segments: Vec<Segment> = vec![];
+    for (column, c) in signal.wave.as_deref().unwrap_or("").chars().enumerate() {
+        let level = match c {
+            '.' | '|' => {
+                if let Some(last) = segments.last_mut() {
+                    last.len += 1;
+                }
+                continue;
+            }
+            '0' | 'l' | 'L' | 'd' => Level::Low,
+            '1' | 'h' | 'H' | 'u' => Level::High,
+            'z' => Level::HighImpedance,
+            'p' | 'P' => Level::Clock { rising: true },
+            'n' | 'N' => Level::Clock { rising: false },
+            '=' | '2'..='9' => Level::Data {
+                label: data.next().cloned().unwrap_or_default(),
+                fill: data_fill(c),
+            },
+            _ => Level::Unknown,
+        };
+        segments.push(Segment {
+            start: column,
+            len: 1,
+            level,
+        });
+    }
+    segments
+}
+
+/// Fill colours used by WaveDrom for data segments.
+fn data_fill(c: char) -> &'static str {
+    match c {
+        '3' => "#ffffb4",
+        '4' => "#ffe0b9",
+        '5' => "#b9e0ff",
+        '6' => "#ccfdfe",
+        '7' => "#cdfdc5",
+        '8' => "#f0c1fb",
+        '9' => "#f6b0b0",
+        _ => "#fff",
+    }
+}
+
+fn render_lane(
+    svg: &mut String,
+    signal: &WaveJsonSignal,
+    options: &SvgOptions,
+    x0: usize,
+    y: usize,
+) {
+    let column_width = options.column_width as f32;
+    let top = y as f32;
+    let bottom = (y + options.lane_height) as f32;
+    let middle = (top + bottom) / 2.0;
+
+    // Vertical position of the previous digital level, used to draw edges
+    let mut last_y: Option<f32> = None;
+    for segment in parse_segments(signal) {
+        let x1 = x0 as f32 + segment.start as f32 * column_width;
+        let x2 = x1 + segment.len as f32 * column_width;
+        let level_y = match &segment.level {
+            Level::Low => Some(bottom),
+            Level::High => Some(top),
+            Level::HighImpedance => Some(middle),
+            _ => None,
+        };
+
+        match &segment.level {
+            Level::Low | Level::High | Level::HighImpedance => {
+                let level_y = level_y.unwrap();
+                let stroke = if segment.level == Level::HighImpedance {
+                    "#00f"
+                } else {
+                    "#000"
+                };
+                if let Some(last_y) = last_y.filter(|last_y| *last_y != level_y) {
+                    writeln!(
+                        svg,
+                        r##"<line x1="{x1}" y1="{last_y}" x2="{x1}" y2="{level_y}" stroke="#000"/>"##
+                    )
+                    .unwrap();
+                }
+                writeln!(
+                    svg,
+                    r#"<line x1="{x1}" y1="{level_y}" x2="{x2}" y2="{level_y}" stroke="{stroke}"/>"#
+                )
+                .unwrap();
+            }
+            Level::Clock { rising } => {
+                let mut path = String::new();
+                for column in 0..segment.len {
+                    let start = x1 + column as f32 * column_width;
+                    let half = start + column_width / 2.0;
+                    let end = start + column_width;
+                    let (first, second) = if *rising {
+                        (top, bottom)
+                    } else {
+                        (bottom, top)
+                    };
+                    write!(path, "M{start} {second} L{start} {first} L{half} {first} L{half} {second} L{end} {second} ").unwrap();
+                }
+                writeln!(
+                    svg,
+                    r##"<path d="{}" fill="none" stroke="#000"/>"##,
+                    path.trim_end()
+                )
+                .unwrap();
+            }
+            Level::Unknown => {
+                writeln!(
+                    svg,
+                    r##"<polygon points="{}" fill="url(#hatch)" stroke="#000"/>"##,
+                    hexagon(x1, x2, top, bottom)
+                )
+                .unwrap();
+            }
+            Level::Data { label, fill } => {
+                writeln!(
+                    svg,
+                    r##"<polygon points="{}" fill="{fill}" stroke="#000"/>"##,
+                    hexagon(x1, x2, top, bottom)
+                )
+                .unwrap();
+                // Only label segments wide enough to hold the text
+                if (label.chars().count() * CHAR_WIDTH) as f32 <= x2 - x1 - 2.0 * SLANT {
+                    writeln!(
+                        svg,
+                        r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                        (x1 + x2) / 2.0,
+                        middle + 4.0,
+                        escape(label)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        last_y = level_y;
+    }
+}
+
+fn hexagon(x1: f32, x2: f32, top: f32, bottom: f32) -> String {
+    let middle = (top + bottom) / 2.0;
+    format!(
+        "{x1},{middle} {},{top} {},{top} {x2},{middle} {},{bottom} {},{bottom}",
+        x1 + SLANT,
+        x2 - SLANT,
+        x2 - SLANT,
+        x1 + SLANT
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_render_counter() {
+        let vcd = ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/Counter.vcd"))
+            .unwrap();
+        let svg = WaveSvg::from_vcd(
+            &vcd,
+            &WaveJsonOptions::default().radix(vcd_oxide_parser::Radix::Hex),
+        );
+        assert_snapshot!(svg.to_svg())
+    }
+
+    #[test]
+    fn test_parse_segments() {
+        let signal = WaveJsonSignal {
+            name: None,
+            wave: Some("x=.hlz".to_owned()),
+            data: Some(vec!["a".to_owned()]),
+        };
+        let levels: Vec<(usize, usize)> = parse_segments(&signal)
+            .iter()
+            .map(|segment| (segment.start, segment.len))
+            .collect();
+        assert_eq!(levels, vec![(0, 1), (1, 2), (3, 1), (4, 1), (5, 1)]);
+    }
+}
@@ -0,0 +1,452 @@
+use std::{fmt, str::FromStr};
+use vcd_oxide_parser::{Radix, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave};
+
+/// Number of columns the automatic sampling period aims to fit a window into.
+const AUTO_COLUMNS: usize = 80;
+
+/// Options controlling how waves are printed as text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Glob patterns selecting the signals to print. Every signal is printed when empty.
+    pub signals: Vec<String>,
+    /// First simulation time to print.
+    pub start: Option<usize>,
+    /// Simulation time at which output stops (exclusive).
+    pub end: Option<usize>,
+    /// Time units covered by each column. Chosen to fit the window into
+    /// 80 columns when unset.
+    pub period: Option<usize>,
+    /// Characters used to draw each column.
+    pub column_width: usize,
+    /// Radix used for bus values.
+    pub radix: Radix,
+    pub charset: Charset,
+}
+
+impl Default for TextOptions {
+    fn default() -> Self {
+        Self {
+            signals: vec![],
+            start: None,
+            end: None,
+            period: None,
+            column_width: 2,
+            radix: Radix::Hex,
+            charset: Charset::Unicode,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// Box drawing and overline characters
+    #[default]
+    Unicode,
+    /// Plain ASCII for logs that mangle anything else
+    Ascii,
+}
+
+/// Characters used to draw a wave.
+struct Glyphs {
+    high: char,
+    low: char,
+    rise: char,
+    fall: char,
+    high_impedance: char,
+    unknown: char,
+    bus: char,
+    bus_edge: char,
+    /// A column in which the value changes more than once
+    busy: char,
+    ellipsis: char,
+    axis: char,
+    tick: char,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    high: '‾',
+    low: '_',
+    rise: '/',
+    fall: '\\',
+    high_impedance: '─',
+    unknown: 'x',
+    bus: '=',
+    bus_edge: '╳',
+    busy: '▒',
+    ellipsis: '…',
+    axis: '─',
+    tick: '┬',
+};
+
+const ASCII: Glyphs = Glyphs {
+    high: '-',
+    low: '_',
+    rise: '/',
+    fall: '\\',
+    high_impedance: '~',
+    unknown: 'x',
+    bus: '=',
+    bus_edge: 'X',
+    busy: '#',
+    ellipsis: '+',
+    axis: '-',
+    tick: '+',
+};
+
+/// Signals rendered as text waveforms below a time ruler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextWave {
+    timescale: String,
+    ruler: [String; 2],
+    lanes: Vec<(String, String)>,
+}
+
+impl TextWave {
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &TextOptions) -> Self {
+        let glyphs = match options.charset {
+            Charset::Unicode => &UNICODE,
+            Charset::Ascii => &ASCII,
+        };
+        let start = options.start.unwrap_or(0);
+        let end = options.end.unwrap_or_else(|| vcd.end_time() + 1).max(start);
+        let period = options
+            .period
+            .unwrap_or_else(|| (end - start).div_ceil(AUTO_COLUMNS))
+            .max(1);
+        let samples: Vec<usize> = (start..end).step_by(period).collect();
+        let column_width = options.column_width.max(1);
+
+        let lanes = vcd
+            .signals()
+            .into_iter()
+            .filter(|sig| {
+                options.signals.is_empty() || options.signals.iter().any(|p| sig.matches(p))
+            })
+            .map(|sig| {
+                let lane = render_lane(&sig, vcd.wave(&sig.identifier), &samples, options);
+                (sig.reference, lane)
+            })
+            .collect();
+
+        TextWave {
+            timescale: vcd.timescale.to_owned(),
+            ruler: render_ruler(&samples, column_width, glyphs),
+            lanes,
+        }
+    }
+
+    pub fn to_text(&self) -> String {
+        let header = format!("time ({})", self.timescale);
+        let name_width = self
+            .lanes
+            .iter()
+            .map(|(name, _)| name.chars().count())
+            .chain([header.chars().count()])
+            .max()
+            .unwrap_or(0);
+
+        let mut text = format!(
+            "{:<width$}  {}\n",
+            header,
+            self.ruler[0],
+            width = name_width
+        );
+        text += &format!("{:<width$}  {}\n", "", self.ruler[1], width = name_width);
+        for (name, lane) in &self.lanes {
+            text += &format!("{name:<name_width$}  {lane}\n");
+        }
+        text
+    }
+}
+
+/// Draw a single signal sampled at the given times, one column per sample.
+/// Each column covers the time until the next sample, the last one as long
+/// as the others, and is drawn as busy when the value changes within it.
+pub fn render_lane(
+    sig: &ValueChangeDumpSignal,
+    wave: &ValueChangeDumpWave,
+    samples: &[usize],
+    options: &TextOptions,
+) -> String {
+    let glyphs = match options.charset {
+        Charset::Unicode => &UNICODE,
+        Charset::Ascii => &ASCII,
+    };
+    let column_width = options.column_width.max(1);
+    let columns = sample(wave, samples);
+    if sig.size > 1 || columns.iter().any(|c| c.value.starts_with('r')) {
+        render_bus(sig, &columns, column_width, options.radix, glyphs)
+    } else {
+        render_scalar(&columns, column_width, glyphs)
+    }
+}
+
+/// Label a value for display, e.g. `0x3a` for a bus or `1` for a scalar.
+pub fn format_value(sig: &ValueChangeDumpSignal, value: &str, radix: Radix) -> String {
+    if sig.size > 1 || value.starts_with('r') || value.starts_with('R') {
+        format_bus_value(sig, value, radix)
+    } else {
+        value.to_owned()
+    }
+}
+
+impl fmt::Display for TextWave {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+/// The wave over the time a column covers.
+struct Column<'a> {
+    /// Raw value at the start of the column
+    value: &'a str,
+    /// Raw value at the end of the column
+    last: &'a str,
+    /// Whether the value changes after the start of the column, which a
+    /// single sample can't show
+    busy: bool,
+}
+
+/// The wave over the column of each sample time. Values before the first
+/// value change are unknown.
+fn sample<'a>(wave: &'a ValueChangeDumpWave, samples: &[usize]) -> Vec<Column<'a>> {
+    let step = samples
+        .windows(2)
+        .next()
+        .map_or(1, |pair| pair[1] - pair[0]);
+    let value_at = |time| wave.value_at(time).map_or("x", |vc| vc.value.as_str());
+    samples
+        .iter()
+        .enumerate()
+        .map(|(index, time)| {
+            let end = samples.get(index + 1).copied().unwrap_or(time + step);
+            let value = value_at(*time);
+            let after = wave.value_changes.partition_point(|vc| vc.time <= *time);
+            let busy = wave.value_changes[after..]
+                .iter()
+                .take_while(|vc| vc.time < end)
+                .any(|vc| vc.value != value);
+            Column {
+                value,
+                last: value_at(end - 1),
+                busy,
+            }
+        })
+        .collect()
+}
+
+fn render_scalar(columns: &[Column], column_width: usize, glyphs: &Glyphs) -> String {
+    let first = |value: &str| value.chars().next().unwrap_or('x').to_ascii_lowercase();
+    let mut lane = String::new();
+    let mut last: Option<char> = None;
+    for column in columns {
+        if column.busy {
+            lane += &glyphs.busy.to_string().repeat(column_width);
+            last = Some(first(column.last));
+            continue;
+        }
+        let value = first(column.value);
+        let level = match value {
+            '0' => glyphs.low,
+            '1' => glyphs.high,
+            'z' => glyphs.high_impedance,
+            _ => glyphs.unknown,
+        };
+        let edge = match (last, value) {
+            (Some(last), value) if last == value => level,
+            (Some(_), '1') => glyphs.rise,
+            (Some(_), '0') => glyphs.fall,
+            _ => level,
+        };
+        lane.push(edge);
+        lane += &level.to_string().repeat(column_width - 1);
+        last = Some(value);
+    }
+    lane
+}
+
+fn render_bus(
+    sig: &ValueChangeDumpSignal,
+    columns: &[Column],
+    column_width: usize,
+    radix: Radix,
+    glyphs: &Glyphs,
+) -> String {
+    let mut lane = String::new();
+    let mut index = 0;
+    while index < columns.len() {
+        if columns[index].busy {
+            lane += &glyphs.busy.to_string().repeat(column_width);
+            index += 1;
+            continue;
+        }
+        let value = columns[index].value;
+        let run = columns[index..]
+            .iter()
+            .take_while(|c| !c.busy && c.value == value)
+            .count();
+        let mut width = run * column_width;
+        // A busy column already shows the change
+        if index > 0 && !columns[index - 1].busy {
+            lane.push(glyphs.bus_edge);
+            width -= 1;
+        }
+        lane += &bus_segment(&format_bus_value(sig, value, radix), width, glyphs);
+        index += run;
+    }
+    lane
+}
+
+/// Label a bus value, e.g. `0x3a`. Values that are entirely unknown or
+/// high impedance are returned as a single `x` or `z`.
+fn format_bus_value(sig: &ValueChangeDumpSignal, value: &str, radix: Radix) -> String {
+    if value.starts_with('r') || value.starts_with('R') {
+        return radix.format(value, sig.size);
+    }
+    let formatted = radix.format(value, sig.size);
+    if let Some(c) = formatted.chars().next().filter(|c| matches!(c, 'x' | 'z')) {
+        if formatted.chars().all(|d| d == c) {
+            return c.to_string();
+        }
+    }
+    let prefix = match radix {
+        Radix::Binary => "0b",
+        Radix::Octal => "0o",
+        Radix::Decimal => "",
+        Radix::Hex => "0x",
+    };
+    format!("{prefix}{formatted}")
+}
+
+/// Draw a bus segment of `width` characters, e.g. `=[ 0x3a ]=`.
+fn bus_segment(label: &str, width: usize, glyphs: &Glyphs) -> String {
+    match label {
+        "x" => return glyphs.unknown.to_string().repeat(width),
+        "z" => return glyphs.high_impedance.to_string().repeat(width),
+        _ => {}
+    }
+    let len = label.chars().count();
+    let text = if len + 4 <= width {
+        format!("[ {label} ]")
+    } else if len <= width {
+        label.to_owned()
+    } else if width > 0 {
+        let mut text: String = label.chars().take(width - 1).collect();
+        text.push(glyphs.ellipsis);
+        text
+    } else {
+        "".to_owned()
+    };
+    let padding = width - text.chars().count();
+    let fill = glyphs.bus.to_string();
+    format!(
+        "{}{}{}",
+        fill.repeat(padding / 2),
+        text,
+        fill.repeat(padding - padding / 2)
+    )
+}
+
+/// Draw the time labels and tick marks above the lanes.
+fn render_ruler(samples: &[usize], column_width: usize, glyphs: &Glyphs) -> [String; 2] {
+    let widest = samples.last().map_or(1, |time| time.to_string().len());
+    // Leave at least one space between labels
+    let label_every = (widest + column_width) / column_width;
+
+    let mut labels = String::new();
+    let mut ticks = String::new();
+    for (column, time) in samples.iter().enumerate() {
+        let label_len = labels.chars().count();
+        if column % label_every == 0 && label_len <= column * column_width {
+            labels += &" ".repeat(column * column_width - label_len);
+            labels += &time.to_string();
+            ticks.push(glyphs.tick);
+        } else {
+            ticks.push(glyphs.axis);
+        }
+        ticks += &glyphs.axis.to_string().repeat(column_width - 1);
+    }
+    [labels, ticks]
+}
+
+impl FromStr for Charset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unicode" => Ok(Charset::Unicode),
+            "ascii" => Ok(Charset::Ascii),
+            _ => Err(format!("unknown charset '{s}'")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use vcd_oxide_parser::ParseOptions;
+
+    fn counter() -> ValueChangeDump {
+        ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/Counter.vcd")).unwrap()
+    }
+
+    #[test]
+    fn test_show_counter() {
+        let options = TextOptions {
+            column_width: 6,
+            ..Default::default()
+        };
+        assert_snapshot!(TextWave::from_vcd(&counter(), &options).to_text())
+    }
+
+    #[test]
+    fn test_show_counter_ascii_window() {
+        let options = TextOptions {
+            signals: vec!["*.count".to_owned()],
+            start: Some(2),
+            end: Some(6),
+            column_width: 4,
+            radix: Radix::Binary,
+            charset: Charset::Ascii,
+            ..Default::default()
+        };
+        assert_snapshot!(TextWave::from_vcd(&counter(), &options).to_text())
+    }
+
+    #[test]
+    fn test_busy_columns() {
+        // The clock toggles every time unit, within each 2 unit column
+        let options = TextOptions {
+            period: Some(2),
+            ..Default::default()
+        };
+        assert_snapshot!(TextWave::from_vcd(&counter(), &options).to_text())
+    }
+
+    #[test]
+    fn test_filtered_time_axis() {
+        let input = include_str!("../../vcd-oxide-parser/test/UartRxTest.vcd");
+        let parse_options = ParseOptions {
+            signals: vec!["*.rst".to_owned()],
+            ..Default::default()
+        };
+        let options = TextOptions {
+            signals: parse_options.signals.clone(),
+            ..Default::default()
+        };
+        let filtered = ValueChangeDump::parse_with(input, &parse_options).unwrap();
+        let all = ValueChangeDump::parse(input).unwrap();
+        assert_eq!(
+            TextWave::from_vcd(&filtered, &options).to_text(),
+            TextWave::from_vcd(&all, &options).to_text()
+        );
+    }
+
+    #[test]
+    fn test_bus_segment_truncates_label() {
+        assert_eq!(bus_segment("0x3a", 10, &UNICODE), "=[ 0x3a ]=");
+        assert_eq!(bus_segment("0x3a", 5, &UNICODE), "0x3a=");
+        assert_eq!(bus_segment("0x3a", 3, &ASCII), "0x+");
+    }
+}
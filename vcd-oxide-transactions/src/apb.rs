@@ -0,0 +1,33 @@
+use crate::{Bus, Error, Pending, Transaction};
+
+/// APB transfers from the setup phase to the access phase completed by `pready`.
+/// Without `pready` every access phase lasts one cycle, and without `pslverr`
+/// every transfer succeeds.
+pub(crate) fn apb(bus: &Bus, edges: &[usize]) -> Result<Vec<Transaction>, Error> {
+    bus.require(&["psel", "penable", "pwrite", "paddr"])?;
+    let mut transactions = vec![];
+    let mut setup: Option<(usize, usize)> = None;
+    for (cycle, &time) in edges.iter().enumerate() {
+        let at = (cycle, time);
+        let selected = bus.high("psel", time, false);
+        let enabled = bus.high("penable", time, false);
+        if selected && !enabled {
+            setup = Some(at);
+        }
+        if !(selected && enabled && bus.high("pready", time, true)) {
+            continue;
+        }
+        let write = bus.high("pwrite", time, false);
+        let mut transfer = Pending::new(setup.take().unwrap_or(at), bus.value("paddr", time), None);
+        let data = if write { "pwdata" } else { "prdata" };
+        transfer.data.extend(bus.value(data, time));
+        let response = if bus.high("pslverr", time, false) {
+            "SLVERR"
+        } else {
+            "OKAY"
+        };
+        let kind = if write { "write" } else { "read" };
+        transactions.push(transfer.finish(kind, at, Some(response.to_owned())));
+    }
+    Ok(transactions)
+}
@@ -0,0 +1,134 @@
+use crate::{Bus, Error, Pending, Transaction};
+use std::collections::VecDeque;
+
+const RESPONSES: [&str; 4] = ["OKAY", "EXOKAY", "SLVERR", "DECERR"];
+
+/// IDs match when they are equal or when either channel doesn't carry one.
+fn same_id(a: &Option<String>, b: &Option<String>) -> bool {
+    a.is_none() || b.is_none() || a == b
+}
+
+/// Reads and writes of an AXI4 interface. Either direction may be left out.
+///
+/// Write data follows the order of the write addresses, so the n-th address
+/// handshake owns the n-th data burst. Responses and read data are matched to
+/// the oldest outstanding request with the same ID.
+pub(crate) fn axi4(bus: &Bus, edges: &[usize]) -> Result<Vec<Transaction>, Error> {
+    let writes = bus.has("awvalid");
+    let reads = bus.has("arvalid");
+    if !writes && !reads {
+        return Err(bus.missing("awvalid"));
+    }
+    if writes {
+        bus.require(&[
+            "awready", "awaddr", "wvalid", "wready", "wdata", "bvalid", "bready",
+        ])?;
+    }
+    if reads {
+        bus.require(&["arready", "araddr", "rvalid", "rready", "rdata"])?;
+    }
+
+    let mut transactions = vec![];
+    // Write addresses still waiting for their data
+    let mut addresses: VecDeque<Pending> = VecDeque::new();
+    // Data bursts that completed before their address
+    let mut bursts: VecDeque<Pending> = VecDeque::new();
+    let mut burst: Option<Pending> = None;
+    // Writes with address and data, waiting for a response
+    let mut responses: Vec<Pending> = vec![];
+    let mut outstanding_reads: Vec<Pending> = vec![];
+    for (cycle, &time) in edges.iter().enumerate() {
+        let at = (cycle, time);
+        if writes {
+            if bus.fire("awvalid", "awready", time) {
+                let mut request =
+                    Pending::new(at, bus.value("awaddr", time), bus.value("awid", time));
+                request.beats = bus.number("awlen", time).map(|len| len + 1);
+                addresses.push_back(request);
+            }
+            if bus.fire("wvalid", "wready", time) {
+                let beat = burst.get_or_insert_with(|| Pending::new(at, None, None));
+                beat.data.push(bus.value("wdata", time).unwrap_or_default());
+                let last = if bus.has("wlast") {
+                    bus.high("wlast", time, false)
+                } else {
+                    let beats = addresses.get(bursts.len()).and_then(|a| a.beats);
+                    beat.data.len() as u64 >= beats.unwrap_or(1)
+                };
+                if last {
+                    bursts.extend(burst.take());
+                }
+            }
+            while !addresses.is_empty() && !bursts.is_empty() {
+                let mut write = addresses.pop_front().unwrap();
+                let data = bursts.pop_front().unwrap();
+                write.start = write.start.min(data.start);
+                write.data = data.data;
+                responses.push(write);
+            }
+            if bus.fire("bvalid", "bready", time) {
+                let id = bus.value("bid", time);
+                if let Some(index) = responses.iter().position(|w| same_id(&w.id, &id)) {
+                    let response = bus.number("bresp", time).map(response);
+                    let write = responses.remove(index);
+                    transactions.push(write.finish("write", at, response));
+                }
+            }
+        }
+
+        if reads {
+            if bus.fire("arvalid", "arready", time) {
+                let mut request =
+                    Pending::new(at, bus.value("araddr", time), bus.value("arid", time));
+                request.beats = bus.number("arlen", time).map(|len| len + 1);
+                outstanding_reads.push(request);
+            }
+            if bus.fire("rvalid", "rready", time) {
+                let id = bus.value("rid", time);
+                let Some(index) = outstanding_reads.iter().position(|r| same_id(&r.id, &id)) else {
+                    continue;
+                };
+                let read = &mut outstanding_reads[index];
+                read.data.push(bus.value("rdata", time).unwrap_or_default());
+                // The worst response of the burst is reported
+                if let Some(resp) = bus.number("rresp", time) {
+                    read.response = read.response.max(Some(resp));
+                }
+                let last = if bus.has("rlast") {
+                    bus.high("rlast", time, false)
+                } else {
+                    read.data.len() as u64 >= read.beats.unwrap_or(1)
+                };
+                if last {
+                    let read = outstanding_reads.remove(index);
+                    let response = read.response.map(response);
+                    transactions.push(read.finish("read", at, response));
+                }
+            }
+        }
+    }
+    Ok(transactions)
+}
+
+fn response(resp: u64) -> String {
+    RESPONSES[(resp & 3) as usize].to_owned()
+}
+
+/// AXI-Stream packets ending with `tlast`, or single beats if the stream has no `tlast`.
+pub(crate) fn axi_stream(bus: &Bus, edges: &[usize]) -> Result<Vec<Transaction>, Error> {
+    bus.require(&["tvalid", "tdata"])?;
+    let mut transactions = vec![];
+    let mut packet: Option<Pending> = None;
+    for (cycle, &time) in edges.iter().enumerate() {
+        if !bus.fire("tvalid", "tready", time) {
+            continue;
+        }
+        let at = (cycle, time);
+        let beat = packet.get_or_insert_with(|| Pending::new(at, None, bus.value("tid", time)));
+        beat.data.push(bus.value("tdata", time).unwrap_or_default());
+        if bus.high("tlast", time, true) {
+            transactions.push(packet.take().unwrap().finish("packet", at, None));
+        }
+    }
+    Ok(transactions)
+}
@@ -0,0 +1,513 @@
+mod apb;
+mod axi;
+mod wishbone;
+
+use serde::Serialize;
+use std::{collections::HashMap, fmt, str::FromStr};
+use vcd_oxide_csv::{CsvTable, Delimiter};
+use vcd_oxide_parser::{Radix, ValueChangeDump, ValueChangeDumpWave};
+
+/// Options selecting the bus whose transactions are extracted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransactionOptions {
+    pub protocol: Protocol,
+    /// Glob pattern of the bus clock. Handshakes are sampled on its rising edges.
+    pub clock: String,
+    /// Prepended to each port name to find its signal, e.g. `tb.dut.s_axi_`
+    /// finds `awvalid` in `tb.dut.s_axi_awvalid`.
+    pub prefix: String,
+    /// Glob patterns of ports that don't follow the prefix, e.g. `("awaddr", "tb.addr")`.
+    pub ports: Vec<(String, String)>,
+}
+
+/// Handshake protocols with the ports read from the dump.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    /// AXI4 and AXI4-Lite read and write channels
+    #[default]
+    Axi4,
+    AxiStream,
+    Apb,
+    /// Wishbone classic cycles
+    Wishbone,
+}
+
+impl Protocol {
+    /// Port names, optional ones included, as they appear after the prefix.
+    pub fn ports(&self) -> &'static [&'static str] {
+        match self {
+            Protocol::Axi4 => &[
+                "awvalid", "awready", "awaddr", "awid", "awlen", "wvalid", "wready", "wdata",
+                "wlast", "bvalid", "bready", "bresp", "bid", "arvalid", "arready", "araddr",
+                "arid", "arlen", "rvalid", "rready", "rdata", "rresp", "rlast", "rid",
+            ],
+            Protocol::AxiStream => &["tvalid", "tready", "tdata", "tlast", "tid"],
+            Protocol::Apb => &[
+                "psel", "penable", "pwrite", "paddr", "pwdata", "prdata", "pready", "pslverr",
+            ],
+            Protocol::Wishbone => &["cyc", "stb", "we", "adr", "dat_w", "dat_r", "ack", "err"],
+        }
+    }
+}
+
+/// A completed bus transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Transaction {
+    /// Time of the first handshake, e.g. the address phase of a write.
+    pub start: usize,
+    /// Time of the last handshake, e.g. the write response.
+    pub end: usize,
+    /// `read`, `write` or `packet` for AXI-Stream
+    pub kind: String,
+    pub address: Option<String>,
+    /// One value per data beat
+    pub data: Vec<String>,
+    /// `OKAY`, `EXOKAY`, `SLVERR`, `DECERR` or `ERR`, if the bus signals responses
+    pub response: Option<String>,
+    /// Clock cycles between the first and last handshake.
+    pub latency: usize,
+}
+
+impl Transaction {
+    /// Whether the response flags a failed transaction.
+    pub fn is_error(&self) -> bool {
+        self.response
+            .as_deref()
+            .is_some_and(|response| !matches!(response, "OKAY" | "EXOKAY"))
+    }
+}
+
+/// Statistics over the transactions of one kind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Summary {
+    pub kind: String,
+    pub count: usize,
+    pub beats: usize,
+    pub errors: usize,
+    pub min_latency: usize,
+    pub mean_latency: f64,
+    pub max_latency: usize,
+}
+
+/// The transactions of a bus in order of their start, with statistics per kind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionReport {
+    pub transactions: Vec<Transaction>,
+    pub summary: Vec<Summary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// No signal matches the clock pattern
+    UnknownClock(String),
+    /// A port mapping names a port the protocol doesn't have
+    UnknownPort(Protocol, String),
+    /// No signal matches the pattern of a required port
+    MissingPort { port: String, pattern: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnknownClock(pattern) => write!(f, "no signal matches clock '{pattern}'"),
+            Error::UnknownPort(protocol, port) => {
+                write!(f, "{protocol} has no port '{port}'")
+            }
+            Error::MissingPort { port, pattern } => {
+                write!(f, "no signal matches '{pattern}' for port {port}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl TransactionReport {
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &TransactionOptions) -> Result<Self, Error> {
+        let clock = vcd
+            .signals()
+            .into_iter()
+            .find(|sig| sig.matches(&options.clock))
+            .map(|sig| vcd.wave(&sig.identifier))
+            .ok_or_else(|| Error::UnknownClock(options.clock.to_owned()))?;
+        let edges: Vec<usize> = clock.edges(true).collect();
+
+        let bus = Bus::new(vcd, options)?;
+        let mut transactions = match options.protocol {
+            Protocol::Axi4 => axi::axi4(&bus, &edges)?,
+            Protocol::AxiStream => axi::axi_stream(&bus, &edges)?,
+            Protocol::Apb => apb::apb(&bus, &edges)?,
+            Protocol::Wishbone => wishbone::wishbone(&bus, &edges)?,
+        };
+        transactions.sort_by_key(|transaction| (transaction.start, transaction.end));
+        let summary = summarize(&transactions);
+        Ok(TransactionReport {
+            transactions,
+            summary,
+        })
+    }
+
+    /// The transactions as a table, with data beats separated by spaces.
+    pub fn to_csv(&self, delimiter: Delimiter) -> String {
+        let header = [
+            "start", "end", "kind", "address", "data", "response", "latency",
+        ]
+        .map(str::to_owned)
+        .to_vec();
+        let rows = self
+            .transactions
+            .iter()
+            .map(|transaction| {
+                vec![
+                    transaction.start.to_string(),
+                    transaction.end.to_string(),
+                    transaction.kind.to_owned(),
+                    transaction.address.clone().unwrap_or_default(),
+                    transaction.data.join(" "),
+                    transaction.response.clone().unwrap_or_default(),
+                    transaction.latency.to_string(),
+                ]
+            })
+            .collect();
+        CsvTable::new(header, rows, delimiter).to_csv()
+    }
+}
+
+fn summarize(transactions: &[Transaction]) -> Vec<Summary> {
+    let mut summary: Vec<Summary> = vec![];
+    for transaction in transactions {
+        let index = match summary.iter().position(|s| s.kind == transaction.kind) {
+            Some(index) => index,
+            None => {
+                summary.push(Summary {
+                    kind: transaction.kind.to_owned(),
+                    count: 0,
+                    beats: 0,
+                    errors: 0,
+                    min_latency: usize::MAX,
+                    mean_latency: 0.0,
+                    max_latency: 0,
+                });
+                summary.len() - 1
+            }
+        };
+        let s = &mut summary[index];
+        s.count += 1;
+        s.beats += transaction.data.len();
+        s.errors += usize::from(transaction.is_error());
+        s.min_latency = s.min_latency.min(transaction.latency);
+        s.max_latency = s.max_latency.max(transaction.latency);
+        // Running sum, divided once every transaction is counted
+        s.mean_latency += transaction.latency as f64;
+    }
+    for s in &mut summary {
+        s.mean_latency /= s.count as f64;
+    }
+    summary
+}
+
+impl fmt::Display for TransactionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .summary
+            .iter()
+            .map(|s| s.kind.len())
+            .chain(["kind".len()])
+            .max()
+            .unwrap_or_default();
+        writeln!(
+            f,
+            "{:width$}  {:>5}  {:>5}  {:>6}  latency min/mean/max (cycles)",
+            "kind", "count", "beats", "errors"
+        )?;
+        for s in &self.summary {
+            writeln!(
+                f,
+                "{:width$}  {:>5}  {:>5}  {:>6}  {}/{:.1}/{}",
+                s.kind, s.count, s.beats, s.errors, s.min_latency, s.mean_latency, s.max_latency
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The signals of a bus, looked up by port name and sampled just before clock edges.
+pub(crate) struct Bus<'a> {
+    ports: HashMap<&'static str, (&'a ValueChangeDumpWave, usize)>,
+    patterns: HashMap<&'static str, String>,
+}
+
+impl<'a> Bus<'a> {
+    fn new(vcd: &'a ValueChangeDump, options: &TransactionOptions) -> Result<Self, Error> {
+        let known = options.protocol.ports();
+        if let Some((port, _)) = options
+            .ports
+            .iter()
+            .find(|(port, _)| !known.contains(&port.as_str()))
+        {
+            return Err(Error::UnknownPort(options.protocol, port.to_owned()));
+        }
+
+        let signals = vcd.signals();
+        let mut ports = HashMap::new();
+        let mut patterns = HashMap::new();
+        for port in known {
+            let pattern = options
+                .ports
+                .iter()
+                .rfind(|(name, _)| name == port)
+                .map_or_else(
+                    || format!("{}{}", options.prefix, port),
+                    |(_, p)| p.to_owned(),
+                );
+            if let Some(sig) = signals.iter().find(|sig| sig.matches(&pattern)) {
+                ports.insert(*port, (vcd.wave(&sig.identifier), sig.size));
+            }
+            patterns.insert(*port, pattern);
+        }
+        Ok(Bus { ports, patterns })
+    }
+
+    pub(crate) fn has(&self, port: &str) -> bool {
+        self.ports.contains_key(port)
+    }
+
+    pub(crate) fn missing(&self, port: &str) -> Error {
+        Error::MissingPort {
+            port: port.to_owned(),
+            pattern: self.patterns[port].to_owned(),
+        }
+    }
+
+    pub(crate) fn require(&self, ports: &[&str]) -> Result<(), Error> {
+        match ports.iter().find(|port| !self.has(port)) {
+            Some(port) => Err(self.missing(port)),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether a single bit port is high at the edge, `default` if the bus doesn't have it.
+    pub(crate) fn high(&self, port: &str, time: usize, default: bool) -> bool {
+        match self.ports.get(port) {
+            Some((wave, _)) => wave.level_before(time) == Some(true),
+            None => default,
+        }
+    }
+
+    /// A valid/ready handshake, with a missing ready port treated as always ready.
+    pub(crate) fn fire(&self, valid: &str, ready: &str, time: usize) -> bool {
+        self.high(valid, time, false) && self.high(ready, time, true)
+    }
+
+    /// The value of a port in hex, `x` before its first assignment.
+    pub(crate) fn value(&self, port: &str, time: usize) -> Option<String> {
+        let (wave, size) = self.ports.get(port)?;
+        Some(match wave.previous_change(time) {
+            Some(vc) => format!("0x{}", Radix::Hex.format(&vc.value, *size)),
+            None => "x".to_owned(),
+        })
+    }
+
+    /// The value of a port as a number, `None` if it is missing or unknown.
+    pub(crate) fn number(&self, port: &str, time: usize) -> Option<u64> {
+        let (wave, size) = self.ports.get(port)?;
+        let bits = vcd_oxide_parser::extend_bits(&wave.previous_change(time)?.value, *size);
+        u64::from_str_radix(&bits, 2).ok()
+    }
+}
+
+/// A transaction waiting for its remaining handshakes, started at `(cycle, time)`.
+pub(crate) struct Pending {
+    start: (usize, usize),
+    address: Option<String>,
+    id: Option<String>,
+    /// Expected data beats, if the bus announces a burst length
+    beats: Option<u64>,
+    data: Vec<String>,
+    /// Worst response code seen so far, for protocols that answer every beat
+    response: Option<u64>,
+}
+
+impl Pending {
+    fn new(start: (usize, usize), address: Option<String>, id: Option<String>) -> Self {
+        Pending {
+            start,
+            address,
+            id,
+            beats: None,
+            data: vec![],
+            response: None,
+        }
+    }
+
+    fn finish(self, kind: &str, end: (usize, usize), response: Option<String>) -> Transaction {
+        Transaction {
+            start: self.start.1,
+            end: end.1,
+            kind: kind.to_owned(),
+            address: self.address,
+            data: self.data,
+            response,
+            latency: end.0 - self.start.0,
+        }
+    }
+}
+
+impl FromStr for Protocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "axi4" | "axi" => Ok(Protocol::Axi4),
+            "axi-stream" | "axis" => Ok(Protocol::AxiStream),
+            "apb" => Ok(Protocol::Apb),
+            "wishbone" | "wb" => Ok(Protocol::Wishbone),
+            _ => Err(format!("unknown protocol '{s}'")),
+        }
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Protocol::Axi4 => "axi4",
+            Protocol::AxiStream => "axi-stream",
+            Protocol::Apb => "apb",
+            Protocol::Wishbone => "wishbone",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_snapshot;
+    use vcd_oxide_parser::testing::clocked_dump;
+
+    fn options(protocol: Protocol, prefix: &str) -> TransactionOptions {
+        TransactionOptions {
+            protocol,
+            clock: "tb.clk".to_owned(),
+            prefix: prefix.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_axi4() {
+        // A two beat write to 0x100 with a stalled beat and a read of 0x200 answered with SLVERR
+        let vcd = clocked_dump(&[
+            ("axi_awvalid", 1, &[0, 1, 0]),
+            ("axi_awready", 1, &[1]),
+            ("axi_awaddr", 32, &[0, 0x100]),
+            ("axi_awid", 4, &[0, 1]),
+            ("axi_awlen", 8, &[0, 1]),
+            ("axi_wvalid", 1, &[0, 0, 1, 1, 1, 0]),
+            ("axi_wready", 1, &[0, 0, 1, 0, 1, 0]),
+            ("axi_wdata", 32, &[0, 0, 0xaa, 0xbb, 0xbb, 0]),
+            ("axi_wlast", 1, &[0, 0, 0, 1, 1, 0]),
+            ("axi_bvalid", 1, &[0, 0, 0, 0, 0, 0, 1, 0]),
+            ("axi_bready", 1, &[1]),
+            ("axi_bresp", 2, &[0]),
+            ("axi_bid", 4, &[0, 0, 0, 0, 0, 0, 1]),
+            ("axi_arvalid", 1, &[0, 0, 1, 0]),
+            ("axi_arready", 1, &[1]),
+            ("axi_araddr", 32, &[0, 0, 0x200]),
+            ("axi_rvalid", 1, &[0, 0, 0, 0, 1, 0]),
+            ("axi_rready", 1, &[1]),
+            ("axi_rdata", 32, &[0, 0, 0, 0, 0xdead]),
+            ("axi_rresp", 2, &[0, 0, 0, 0, 2, 0]),
+            ("axi_rlast", 1, &[0, 0, 0, 0, 1, 0]),
+        ]);
+        let report =
+            TransactionReport::from_vcd(&vcd, &options(Protocol::Axi4, "tb.axi_")).unwrap();
+        assert_snapshot!(report.to_csv(Delimiter::Comma));
+        assert_snapshot!(report.to_string());
+    }
+
+    #[test]
+    fn test_axi_stream() {
+        // Three beats with a stall, then a single beat packet
+        let vcd = clocked_dump(&[
+            ("s_tvalid", 1, &[0, 1, 1, 1, 1, 1, 0]),
+            ("s_tready", 1, &[1, 1, 0, 1, 1, 1]),
+            ("s_tdata", 8, &[0, 1, 2, 2, 3, 4]),
+            ("s_tlast", 1, &[0, 0, 0, 0, 1, 1, 0]),
+        ]);
+        let report =
+            TransactionReport::from_vcd(&vcd, &options(Protocol::AxiStream, "*.s_")).unwrap();
+        assert_snapshot!(report.to_csv(Delimiter::Comma));
+    }
+
+    #[test]
+    fn test_apb() {
+        // A write without wait states, then a read with one wait state failing
+        let vcd = clocked_dump(&[
+            ("psel", 1, &[0, 1, 1, 0, 1, 1, 1, 0]),
+            ("penable", 1, &[0, 0, 1, 0, 0, 1, 1, 0]),
+            ("pwrite", 1, &[0, 1, 1, 0]),
+            ("paddr", 16, &[0, 0x10, 0x10, 0, 0x20]),
+            ("pwdata", 32, &[0, 0x1234]),
+            ("prdata", 32, &[0, 0, 0, 0, 0, 0, 0x55]),
+            ("pready", 1, &[1, 1, 1, 1, 1, 0, 1]),
+            ("pslverr", 1, &[0, 0, 0, 0, 0, 0, 1, 0]),
+        ]);
+        let report = TransactionReport::from_vcd(&vcd, &options(Protocol::Apb, "tb.")).unwrap();
+        assert_snapshot!(report.to_csv(Delimiter::Comma));
+    }
+
+    #[test]
+    fn test_wishbone() {
+        // A write acknowledged after two cycles, then a read ending in an error
+        let vcd = clocked_dump(&[
+            ("wb_cyc", 1, &[0, 1, 1, 1, 1, 0]),
+            ("wb_stb", 1, &[0, 1, 1, 0, 1, 0]),
+            ("wb_we", 1, &[0, 1, 1, 0]),
+            ("wb_adr", 16, &[0, 0x4, 0x4, 0, 0x8]),
+            ("wb_dat_w", 32, &[0, 0x99]),
+            ("wb_dat_r", 32, &[0]),
+            ("wb_ack", 1, &[0, 0, 1, 0]),
+            ("wb_err", 1, &[0, 0, 0, 0, 1, 0]),
+        ]);
+        let report =
+            TransactionReport::from_vcd(&vcd, &options(Protocol::Wishbone, "tb.wb_")).unwrap();
+        assert_snapshot!(report.to_csv(Delimiter::Comma));
+    }
+
+    #[test]
+    fn test_port_mapping() {
+        let vcd = clocked_dump(&[
+            ("psel", 1, &[0, 1, 1, 0]),
+            ("penable", 1, &[0, 0, 1, 0]),
+            ("pwrite", 1, &[0]),
+            ("address", 8, &[0, 0x3]),
+            ("prdata", 8, &[0, 0, 0x7]),
+        ]);
+        let mut apb = options(Protocol::Apb, "tb.");
+        assert_eq!(
+            TransactionReport::from_vcd(&vcd, &apb),
+            Err(Error::MissingPort {
+                port: "paddr".to_owned(),
+                pattern: "tb.paddr".to_owned()
+            })
+        );
+        apb.ports = vec![("paddr".to_owned(), "*.address".to_owned())];
+        let report = TransactionReport::from_vcd(&vcd, &apb).unwrap();
+        assert_eq!(report.transactions[0].address.as_deref(), Some("0x03"));
+        assert_eq!(report.transactions[0].data, ["0x07"]);
+
+        apb.ports = vec![("awaddr".to_owned(), "*.address".to_owned())];
+        assert_eq!(
+            TransactionReport::from_vcd(&vcd, &apb),
+            Err(Error::UnknownPort(Protocol::Apb, "awaddr".to_owned()))
+        );
+        apb.clock = "*.missing".to_owned();
+        assert_eq!(
+            TransactionReport::from_vcd(&vcd, &apb),
+            Err(Error::UnknownClock("*.missing".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Bus, Error, Pending, Transaction};
+
+/// Wishbone classic cycles, from the first cycle `stb` is asserted until the
+/// slave answers with `ack` or `err`.
+pub(crate) fn wishbone(bus: &Bus, edges: &[usize]) -> Result<Vec<Transaction>, Error> {
+    bus.require(&["cyc", "stb", "we", "adr", "ack"])?;
+    let mut transactions = vec![];
+    let mut request: Option<(usize, usize)> = None;
+    for (cycle, &time) in edges.iter().enumerate() {
+        let at = (cycle, time);
+        if !(bus.high("cyc", time, false) && bus.high("stb", time, false)) {
+            request = None;
+            continue;
+        }
+        let start = *request.get_or_insert(at);
+        let error = bus.high("err", time, false);
+        if !(bus.high("ack", time, false) || error) {
+            continue;
+        }
+        let write = bus.high("we", time, false);
+        let mut transfer = Pending::new(start, bus.value("adr", time), None);
+        let data = if write { "dat_w" } else { "dat_r" };
+        transfer.data.extend(bus.value(data, time));
+        let response = if error { "ERR" } else { "OKAY" };
+        let kind = if write { "write" } else { "read" };
+        transactions.push(transfer.finish(kind, at, Some(response.to_owned())));
+        request = None;
+    }
+    Ok(transactions)
+}
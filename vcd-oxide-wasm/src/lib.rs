@@ -0,0 +1,51 @@
+use serde::Serialize;
+use serde_wasm_bindgen::Serializer;
+use vcd_oxide_parser::ValueChangeDump;
+use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions};
+use wasm_bindgen::prelude::*;
+
+/// A parsed dump, kept on the Rust side so it is only parsed once.
+#[wasm_bindgen]
+pub struct Dump {
+    vcd: ValueChangeDump,
+}
+
+/// Parse the text of a VCD file.
+#[wasm_bindgen(js_name = parseVcd)]
+pub fn parse_vcd(text: &str) -> Result<Dump, JsError> {
+    Ok(Dump {
+        vcd: ValueChangeDump::parse(text)?,
+    })
+}
+
+#[wasm_bindgen]
+impl Dump {
+    /// The full dump model, in the same shape as `vcd-oxide convert --format model`.
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> Result<JsValue, JsError> {
+        to_js(&self.vcd)
+    }
+
+    /// Every signal with its `kind`, `identifier`, `reference` and `size`.
+    pub fn signals(&self) -> Result<JsValue, JsError> {
+        to_js(&self.vcd.signals())
+    }
+
+    /// Convert the dump to a WaveJson object. Options use the keys of the
+    /// `vcd-oxide` config file, e.g. `{ signals: ["*.clk"], "expand-busses": true }`,
+    /// and default when `undefined`.
+    #[wasm_bindgen(js_name = toWaveJson)]
+    pub fn to_wavejson(&self, options: JsValue) -> Result<JsValue, JsError> {
+        let options: WaveJsonOptions = if options.is_undefined() || options.is_null() {
+            WaveJsonOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options)?
+        };
+        to_js(&WaveJson::from_vcd(&self.vcd, &options))
+    }
+}
+
+/// Serialize maps as plain objects so the result matches the JSON output of the CLI.
+fn to_js(value: &impl Serialize) -> Result<JsValue, JsError> {
+    Ok(value.serialize(&Serializer::json_compatible())?)
+}
@@ -0,0 +1,38 @@
+//! Run with `wasm-pack test --node vcd-oxide-wasm`.
+#![cfg(target_arch = "wasm32")]
+
+use vcd_oxide_wasm::parse_vcd;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const COUNTER: &str = include_str!("../../vcd-oxide-parser/test/Counter.vcd");
+
+fn stringify(value: &JsValue) -> String {
+    js_sys::JSON::stringify(value).unwrap().into()
+}
+
+#[wasm_bindgen_test]
+fn test_parse_and_list_signals() {
+    let dump = parse_vcd(COUNTER).unwrap();
+    let signals = stringify(&dump.signals().unwrap());
+    assert!(signals.contains(r#""reference":"Counter.clk""#));
+    assert!(signals.contains(r#""reference":"Counter.count [3:0]""#));
+
+    let model = stringify(&dump.to_json().unwrap());
+    assert!(model.starts_with(r#"{"date":"#));
+    assert!(model.contains(r#""waves":{"#));
+}
+
+#[wasm_bindgen_test]
+fn test_to_wavejson() {
+    let dump = parse_vcd(COUNTER).unwrap();
+    let options = js_sys::JSON::parse(r#"{"signals":["*.clk"],"end":4}"#).unwrap();
+    let wave = stringify(&dump.to_wavejson(options).unwrap());
+    assert!(wave.starts_with(r#"{"signal":[{"name":"Counter.clk","wave":"lhlh""#));
+    assert!(dump.to_wavejson(JsValue::UNDEFINED).is_ok());
+}
+
+#[wasm_bindgen_test]
+fn test_parse_error() {
+    assert!(parse_vcd("not a dump").is_err());
+}
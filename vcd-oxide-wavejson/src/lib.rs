@@ -1,5 +1,12 @@
+mod options;
+
+pub use crate::options::*;
+
 use serde::{ser::SerializeSeq, Deserialize, Serialize};
-use vcd_oxide_parser::{ValueChange, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave};
+use std::collections::HashSet;
+use vcd_oxide_parser::{
+    extend_bits, ValueChange, ValueChangeDump, ValueChangeDumpSignal, ValueChangeDumpWave,
+};
 
 #[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,7 +28,7 @@ pub enum WaveJsonSignalItem {
 #[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
 pub struct WaveJsonGroup {
     pub name: Option<String>,
-    pub signals: Vec<WaveJsonSignal>,
+    pub signals: Vec<WaveJsonSignalItem>,
 }
 
 #[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -41,93 +48,226 @@ pub struct Head {}
 #[serde(rename_all = "camelCase")]
 pub struct Foot {}
 
-/// Generate a WaveJson group for the given signal and wave.
-fn vcd_wave_to_wavejson_group(
-    sig: ValueChangeDumpSignal,
-    wave: &ValueChangeDumpWave,
-    max_value_change_len: usize,
-) -> WaveJsonGroup {
-    let mut signals = vec![];
-    for i in 0..sig.size {
-        let mut result = "".to_owned();
-        let mut last_bit_value: Option<String> = None;
-        for value_change in &wave.value_changes {
-            let bit_value = extract_bit_value(value_change, &sig, i);
-            if let Some(last) = &last_bit_value {
-                if bit_value == *last {
-                    result += ".";
-                    continue;
-                }
+/// A named lane of labelled intervals, such as decoded bus transfers, drawn
+/// as WaveJson data on the same time grid as the signals of a dump.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DataLane {
+    pub name: String,
+    /// `(start, end, label)` intervals in simulation time, sorted by start.
+    /// Time between intervals is drawn as high impedance.
+    pub intervals: Vec<(usize, usize, String)>,
+}
+
+impl DataLane {
+    /// The lane as a wave whose values index into the intervals, with an empty value between them.
+    fn to_wave(&self) -> ValueChangeDumpWave {
+        let mut value_changes: Vec<ValueChange> = vec![ValueChange {
+            time: 0,
+            value: String::new(),
+        }];
+        for (index, (start, end, _)) in self.intervals.iter().enumerate() {
+            if end <= start {
+                continue;
+            }
+            if value_changes.last().is_some_and(|last| last.time == *start) {
+                value_changes.pop();
             }
-            result += map_signal_value_to_wavejson_value(&bit_value);
-            last_bit_value = Some(bit_value);
+            value_changes.push(ValueChange {
+                time: *start,
+                value: index.to_string(),
+            });
+            value_changes.push(ValueChange {
+                time: *end,
+                value: String::new(),
+            });
         }
-        let wave = format!("{:.<width$}", result, width = max_value_change_len);
-        signals.push(WaveJsonSignal {
-            name: Some(format!("{}[{}]", sig.reference, i)),
-            wave: Some(wave),
-            ..Default::default()
-        });
-    }
-    WaveJsonGroup {
-        name: Some(sig.reference),
-        signals,
+        ValueChangeDumpWave { value_changes }
     }
 }
 
-/// Extract the bit value at the given index from the given value change.
-/// The value change is expected to be a binary value.
-fn extract_bit_value(value_change: &ValueChange, sig: &ValueChangeDumpSignal, i: usize) -> String {
-    let mut value = value_change.value.as_str();
-    // remove the "b" or "B" prefix
-    if value.starts_with('b') || value.starts_with('B') {
-        value = &value[1..];
+/// Conversion state shared by every signal of a dump.
+struct Conversion<'a> {
+    options: &'a WaveJsonOptions,
+    /// Length every wave is padded to
+    width: usize,
+    /// Time the last value change of every wave extends to
+    end: Option<usize>,
+}
+
+impl Conversion<'_> {
+    /// Generate a WaveJson group for the given signal and wave.
+    fn vcd_wave_to_wavejson_group(
+        &self,
+        sig: &ValueChangeDumpSignal,
+        wave: &ValueChangeDumpWave,
+    ) -> WaveJsonGroup {
+        let name = self.options.signal_name(sig);
+        let mut signals = vec![];
+        for i in 0..sig.size {
+            let mut result = "".to_owned();
+            let mut last_bit_value: Option<String> = None;
+            let mut signal_iter = wave.value_changes.iter().peekable();
+            while let Some(value_change) = signal_iter.next() {
+                let repeat = self.repeat(value_change, signal_iter.peek());
+                let bit_value = extract_bit_value(value_change, sig, i);
+                match &last_bit_value {
+                    Some(last) if bit_value == *last => result += ".",
+                    _ => result += map_signal_value_to_wavejson_value(&bit_value),
+                }
+                if repeat > 1 {
+                    result += &".".repeat(repeat - 1);
+                }
+                last_bit_value = Some(bit_value);
+            }
+            let wave = format!("{:.<width$}", result, width = self.width);
+            signals.push(WaveJsonSignalItem::Signal(WaveJsonSignal {
+                name: Some(format!("{name}[{i}]")),
+                wave: Some(wave),
+                ..Default::default()
+            }));
+        }
+        WaveJsonGroup {
+            name: Some(name),
+            signals,
+        }
     }
 
-    // pad the value with leading zeros to the signal size
-    let value = format!("{:0>width$}", value, width = sig.size);
+    fn vcd_wave_to_wavejson_signal(
+        &self,
+        sig: &ValueChangeDumpSignal,
+        wave: &ValueChangeDumpWave,
+    ) -> WaveJsonSignal {
+        let mut result = "".to_owned();
+        let mut data = vec![];
+        let mut signal_iter = wave.value_changes.iter().peekable();
+        while let Some(value_change) = signal_iter.next() {
+            let repeat = self.repeat(value_change, signal_iter.peek());
 
-    let bit_index = sig.size - i - 1;
-    let bit_value = &value[bit_index..bit_index + 1];
-    bit_value.to_owned()
-}
+            let value = value_change.value.as_str();
+            if sig.size > 1 {
+                data.push(match self.options.radix {
+                    Some(radix) => radix.format(value, sig.size),
+                    None => value.to_owned(),
+                });
+            }
 
-fn vcd_wave_to_wavejson_signal(
-    sig: ValueChangeDumpSignal,
-    wave: &ValueChangeDumpWave,
-    max_value_change_len: usize,
-    expand_busses: bool,
-) -> WaveJsonSignal {
-    let mut result = "".to_owned();
-    let mut data = vec![];
-    let mut signal_iter = wave.value_changes.iter().peekable();
-    while let Some(value_change) = signal_iter.next() {
-        let mut repeat = 0;
-        if let Some(next) = signal_iter.peek() {
-            repeat = next.time - value_change.time;
+            result += map_signal_value_to_wavejson_value(value);
+            if repeat > 1 {
+                result += &".".repeat(repeat - 1);
+            }
         }
 
-        let value = value_change.value.as_str();
-        if sig.size > 1 && !expand_busses {
-            data.push(value.to_owned());
+        let wave = format!("{:.<width$}", result, width = self.width);
+        WaveJsonSignal {
+            name: Some(self.options.signal_name(sig)),
+            wave: Some(wave),
+            data: Some(data),
         }
+    }
+
+    fn lane_to_wavejson_signal(
+        &self,
+        lane: &DataLane,
+        wave: &ValueChangeDumpWave,
+    ) -> WaveJsonSignal {
+        let mut result = "".to_owned();
+        let mut data = vec![];
+        let mut signal_iter = wave.value_changes.iter().peekable();
+        while let Some(value_change) = signal_iter.next() {
+            let repeat = self.repeat(value_change, signal_iter.peek());
+            if repeat == 0 && signal_iter.peek().is_some() {
+                continue;
+            }
+            match value_change.value.parse::<usize>() {
+                Ok(index) => {
+                    result += "=";
+                    data.push(lane.intervals[index].2.to_owned());
+                }
+                Err(_) => result += "z",
+            }
+            if repeat > 1 {
+                result += &".".repeat(repeat - 1);
+            }
+        }
+        let wave = format!("{:.<width$}", result, width = self.width);
+        WaveJsonSignal {
+            name: Some(lane.name.to_owned()),
+            wave: Some(wave),
+            data: Some(data),
+        }
+    }
+
+    /// Number of time units the value change lasts for.
+    fn repeat(&self, value_change: &ValueChange, next: Option<&&ValueChange>) -> usize {
+        next.map(|next| next.time)
+            .or(self.end)
+            .map_or(0, |end| end.saturating_sub(value_change.time))
+    }
+}
 
-        result += map_signal_value_to_wavejson_value(value);
-        if repeat > 1 {
-            result += &".".repeat(repeat - 1);
+/// Restrict a wave to the configured time window. The value in effect at the start
+/// of the window is carried over as the first value change.
+fn window_wave(wave: &ValueChangeDumpWave, options: &WaveJsonOptions) -> ValueChangeDumpWave {
+    let end = options.end.unwrap_or(usize::MAX);
+    // Changes at the start of the window are folded into the initial value
+    let first = options.start.map_or(0, |start| start + 1);
+    let mut value_changes = vec![];
+    if let Some(start) = options.start {
+        if let Some(initial) = wave.value_at(start).filter(|_| start < end) {
+            value_changes.push(ValueChange {
+                time: start,
+                value: initial.value.to_owned(),
+            });
         }
     }
+    for value_change in wave
+        .value_changes
+        .iter()
+        .filter(|vc| vc.time >= first && vc.time < end)
+    {
+        value_changes.push(ValueChange {
+            time: value_change.time,
+            value: value_change.value.to_owned(),
+        });
+    }
+    ValueChangeDumpWave { value_changes }
+}
 
-    let wave = format!("{:.<width$}", result, width = max_value_change_len);
-    WaveJsonSignal {
-        name: Some(sig.reference),
-        wave: Some(wave),
-        data: Some(data),
+/// Sample a wave at the given times, using `unknown` before its first value change.
+/// The resulting value changes are indexed by sample rather than by simulation
+/// time, and only retain samples that differ from the previous one.
+fn sample_wave(
+    wave: &ValueChangeDumpWave,
+    samples: &[usize],
+    unknown: &str,
+) -> ValueChangeDumpWave {
+    let mut value_changes: Vec<ValueChange> = vec![];
+    for (column, time) in samples.iter().enumerate() {
+        let value = wave.value_at(*time).map_or(unknown, |vc| vc.value.as_str());
+        match value_changes.last() {
+            Some(last) if last.value == value => {}
+            _ => value_changes.push(ValueChange {
+                time: column,
+                value: value.to_owned(),
+            }),
+        }
     }
+    ValueChangeDumpWave { value_changes }
+}
+
+/// Extract the bit value at the given index from the given value change.
+/// The value change is expected to be a binary value.
+fn extract_bit_value(value_change: &ValueChange, sig: &ValueChangeDumpSignal, i: usize) -> String {
+    // pad the value to the signal size
+    let value = extend_bits(&value_change.value, sig.size);
+
+    let bit_index = value.len() - i - 1;
+    let bit_value = &value[bit_index..bit_index + 1];
+    bit_value.to_owned()
 }
 
 /// Map a VCD signal value to a WaveJson signal value.
-/// If the value is not a binary signal value, the value is mapped to "=" 
+/// If the value is not a binary signal value, the value is mapped to "="
 /// assuming that associated data value will be included in the signal
 fn map_signal_value_to_wavejson_value(value: &str) -> &'static str {
     match value {
@@ -140,26 +280,101 @@ fn map_signal_value_to_wavejson_value(value: &str) -> &'static str {
 }
 
 impl WaveJson {
-    pub fn from_vcd(vcd: ValueChangeDump, expand_busses: bool) -> Self {
+    pub fn from_vcd(vcd: &ValueChangeDump, options: &WaveJsonOptions) -> Self {
+        Self::from_vcd_with_lanes(vcd, options, &[])
+    }
+
+    /// Convert a dump and append data lanes below its signals.
+    pub fn from_vcd_with_lanes(
+        vcd: &ValueChangeDump,
+        options: &WaveJsonOptions,
+        lanes: &[DataLane],
+    ) -> Self {
+        let mut seen_identifiers = HashSet::new();
+        let vcd_signals: Vec<ValueChangeDumpSignal> = vcd
+            .signals()
+            .into_iter()
+            .filter(|sig| options.is_selected(sig))
+            .filter(|sig| !options.dedup_aliases || seen_identifiers.insert(sig.identifier.clone()))
+            .collect();
+
+        let samples = options.period.map(|period| {
+            let start = options.start.unwrap_or(0);
+            let end = options.end.unwrap_or_else(|| vcd.end_time() + 1);
+            (start..end).step_by(period.max(1)).collect::<Vec<_>>()
+        });
+
+        let waves: Vec<ValueChangeDumpWave> = vcd_signals
+            .iter()
+            .map(|sig| {
+                let wave = vcd.wave(&sig.identifier);
+                match &samples {
+                    Some(samples) => {
+                        sample_wave(wave, samples, if sig.size > 1 { "bx" } else { "x" })
+                    }
+                    None => window_wave(wave, options),
+                }
+            })
+            .collect();
+        let lane_waves: Vec<ValueChangeDumpWave> = lanes
+            .iter()
+            .map(|lane| {
+                let wave = lane.to_wave();
+                match &samples {
+                    Some(samples) => sample_wave(&wave, samples, ""),
+                    None => window_wave(&wave, options),
+                }
+            })
+            .collect();
+
+        let conversion = match &samples {
+            Some(samples) => Conversion {
+                options,
+                width: samples.len(),
+                end: Some(samples.len()),
+            },
+            None => Conversion {
+                options,
+                width: waves
+                    .iter()
+                    .chain(&lane_waves)
+                    .map(|wave| wave.value_changes.len())
+                    .max()
+                    .unwrap_or(0),
+                end: options.end,
+            },
+        };
+
         let mut wavejson_signals = vec![];
-        let vcd_signals = vcd.signals();
-        let max_value_change_len = vcd
-            .wave_map
-            .values()
-            .map(|sig| sig.value_changes.len())
-            .max()
-            .unwrap_or(0);
-
-        for sig in vcd_signals {
-            let raw_wave = vcd.wave_map.get(&sig.identifier).unwrap();
-            if sig.size > 1 && expand_busses {
-                let group = vcd_wave_to_wavejson_group(sig, raw_wave, max_value_change_len);
-                wavejson_signals.push(WaveJsonSignalItem::Group(group));
+        let mut scopes: Vec<WaveJsonGroup> = vec![];
+        for (sig, wave) in vcd_signals.iter().zip(&waves) {
+            let item = if options.is_expanded(sig) {
+                WaveJsonSignalItem::Group(conversion.vcd_wave_to_wavejson_group(sig, wave))
             } else {
-                let wave =
-                    vcd_wave_to_wavejson_signal(sig, raw_wave, max_value_change_len, expand_busses);
-                wavejson_signals.push(WaveJsonSignalItem::Signal(wave));
+                WaveJsonSignalItem::Signal(conversion.vcd_wave_to_wavejson_signal(sig, wave))
             };
+
+            let scope = sig.scope_reference();
+            if options.group_by == Grouping::None || scope.is_empty() {
+                wavejson_signals.push(item);
+                continue;
+            }
+            match scopes
+                .iter_mut()
+                .find(|group| group.name.as_deref() == Some(scope))
+            {
+                Some(group) => group.signals.push(item),
+                None => scopes.push(WaveJsonGroup {
+                    name: Some(scope.to_owned()),
+                    signals: vec![item],
+                }),
+            }
+        }
+        wavejson_signals.extend(scopes.into_iter().map(WaveJsonSignalItem::Group));
+        for (lane, wave) in lanes.iter().zip(&lane_waves) {
+            wavejson_signals.push(WaveJsonSignalItem::Signal(
+                conversion.lane_to_wavejson_signal(lane, wave),
+            ));
         }
 
         WaveJson {
@@ -188,3 +403,79 @@ impl Serialize for WaveJsonGroup {
         seq.end()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use insta::assert_json_snapshot;
+    use vcd_oxide_parser::Radix;
+
+    fn counter() -> ValueChangeDump {
+        ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/Counter.vcd")).unwrap()
+    }
+
+    #[test]
+    fn test_default_options() {
+        let wave = WaveJson::from_vcd(&counter(), &WaveJsonOptions::default());
+        assert_json_snapshot!(wave)
+    }
+
+    #[test]
+    fn test_sampled_window_with_radix() {
+        let options = WaveJsonOptions::default()
+            .signals(["*.count"])
+            .radix(Radix::Decimal)
+            .start(2)
+            .end(6)
+            .period(2)
+            .naming(NamingStyle::Short);
+        let wave = WaveJson::from_vcd(&counter(), &options);
+        assert_json_snapshot!(wave)
+    }
+
+    #[test]
+    fn test_expanded_bus_grouped_by_scope() {
+        let options = WaveJsonOptions::default()
+            .expand(["Counter.count"])
+            .group_by(Grouping::Scope);
+        let wave = WaveJson::from_vcd(&counter(), &options);
+        assert_json_snapshot!(wave)
+    }
+
+    #[test]
+    fn test_dedup_aliases() {
+        let vcd =
+            ValueChangeDump::parse(include_str!("../../vcd-oxide-parser/test/UartRxTest.vcd"))
+                .unwrap();
+        let options = WaveJsonOptions::default()
+            .signals(["*clk"])
+            .dedup_aliases(true);
+        let wave = WaveJson::from_vcd(&vcd, &options);
+        assert_eq!(wave.signal.len(), 1);
+    }
+
+    #[test]
+    fn test_options_from_config() {
+        let config = r#"
+signals = ["*.count"]
+expand-busses = true
+radix = "hex"
+end = 20
+period = 2
+group-by = "scope"
+dedup-aliases = true
+naming = "short"
+"#;
+        let options: WaveJsonOptions = toml::from_str(config).unwrap();
+        let expected = WaveJsonOptions::default()
+            .signals(["*.count"])
+            .expand_busses(true)
+            .radix(Radix::Hex)
+            .end(20)
+            .period(2)
+            .group_by(Grouping::Scope)
+            .dedup_aliases(true)
+            .naming(NamingStyle::Short);
+        assert_eq!(options, expected);
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use vcd_oxide_parser::{Radix, ValueChangeDumpSignal};
+
+/// Options controlling how a `ValueChangeDump` is converted into WaveJson.
+///
+/// Every option maps 1:1 to a CLI flag and to a key of the same name
+/// in the TOML config file accepted by `vcd-oxide`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct WaveJsonOptions {
+    /// Glob patterns selecting the signals to include. Every signal is included when empty.
+    pub signals: Vec<String>,
+    /// Expand every bus into individual signals.
+    pub expand_busses: bool,
+    /// Glob patterns selecting individual busses to expand.
+    pub expand: Vec<String>,
+    /// Radix used for bus data labels. Raw VCD values are used when unset.
+    pub radix: Option<Radix>,
+    /// First simulation time to include.
+    pub start: Option<usize>,
+    /// Simulation time at which output stops (exclusive).
+    pub end: Option<usize>,
+    /// Sample every signal on a fixed grid of this many time units instead of
+    /// emitting one wave character per time unit between value changes.
+    pub period: Option<usize>,
+    /// How signals are grouped in the output.
+    pub group_by: Grouping,
+    /// Only emit the first of several signals sharing an identifier code.
+    pub dedup_aliases: bool,
+    /// How signals are named in the output.
+    pub naming: NamingStyle,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grouping {
+    /// A flat list of signals
+    #[default]
+    None,
+    /// One WaveJson group per scope
+    Scope,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NamingStyle {
+    /// Fully qualified reference, e.g. `Counter.count [3:0]`
+    #[default]
+    Full,
+    /// Reference without the scope hierarchy, e.g. `count [3:0]`
+    Short,
+}
+
+impl WaveJsonOptions {
+    pub fn signals<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.signals = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn expand_busses(mut self, expand_busses: bool) -> Self {
+        self.expand_busses = expand_busses;
+        self
+    }
+
+    pub fn expand<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.expand = patterns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn radix(mut self, radix: Radix) -> Self {
+        self.radix = Some(radix);
+        self
+    }
+
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn end(mut self, end: usize) -> Self {
+        self.end = Some(end);
+        self
+    }
+
+    pub fn period(mut self, period: usize) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    pub fn group_by(mut self, group_by: Grouping) -> Self {
+        self.group_by = group_by;
+        self
+    }
+
+    pub fn dedup_aliases(mut self, dedup_aliases: bool) -> Self {
+        self.dedup_aliases = dedup_aliases;
+        self
+    }
+
+    pub fn naming(mut self, naming: NamingStyle) -> Self {
+        self.naming = naming;
+        self
+    }
+
+    pub(crate) fn is_selected(&self, sig: &ValueChangeDumpSignal) -> bool {
+        self.signals.is_empty() || self.signals.iter().any(|pattern| sig.matches(pattern))
+    }
+
+    pub(crate) fn is_expanded(&self, sig: &ValueChangeDumpSignal) -> bool {
+        sig.size > 1
+            && (self.expand_busses || self.expand.iter().any(|pattern| sig.matches(pattern)))
+    }
+
+    pub(crate) fn signal_name(&self, sig: &ValueChangeDumpSignal) -> String {
+        match self.naming {
+            NamingStyle::Full => sig.reference.to_owned(),
+            NamingStyle::Short => sig.short_reference().to_owned(),
+        }
+    }
+}
+
+impl FromStr for Grouping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Grouping::None),
+            "scope" => Ok(Grouping::Scope),
+            _ => Err(format!("unknown grouping '{s}'")),
+        }
+    }
+}
+
+impl FromStr for NamingStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "full" => Ok(NamingStyle::Full),
+            "short" => Ok(NamingStyle::Short),
+            _ => Err(format!("unknown naming style '{s}'")),
+        }
+    }
+}
@@ -0,0 +1,46 @@
+use crate::files::{read_input, read_vcd, write_output, Result};
+use clap::Args;
+use std::{path::PathBuf, process::ExitCode};
+use vcd_oxide_check::{CheckOptions, CheckReport, Property};
+
+#[derive(Args, Debug)]
+pub struct CheckArgs {
+    #[arg(help = "Properties file, one property per line")]
+    properties: PathBuf,
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Number of failures reported per property",
+        default_value = "10"
+    )]
+    max_failures: usize,
+    #[arg(long, help = "Print the results as JSON")]
+    json: bool,
+}
+
+/// Print the result of each property, failing when any of them fails.
+pub fn run(args: CheckArgs) -> Result<ExitCode> {
+    let properties = Property::parse_file(&read_input(&args.properties)?)
+        .map_err(|e| format!("{}: {}", args.properties.display(), e))?;
+    let vcd = read_vcd(&args.file)?;
+    let options = CheckOptions {
+        max_failures: Some(args.max_failures),
+    };
+    let report = CheckReport::from_vcd(&vcd, &properties, &options)?;
+
+    let content = if args.json {
+        serde_json::to_string_pretty(&report)? + "\n"
+    } else {
+        report.to_string()
+    };
+    write_output(&args.output, &content)?;
+    Ok(if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,245 @@
+use crate::files::{output_path, read_input, read_vcd_with, write_output, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use vcd_oxide_csv::{CsvOptions, CsvTable, Delimiter, Edge, Layout};
+use vcd_oxide_fst::write_fst;
+use vcd_oxide_parser::{Compression, DerivedSignal, ParseOptions, Radix};
+use vcd_oxide_svg::WaveSvg;
+use vcd_oxide_wavejson::{Grouping, NamingStyle, WaveJson, WaveJsonOptions};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Json,
+    Svg,
+    Vcd,
+    Fst,
+    Csv,
+    Tsv,
+    Model,
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl OutputFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Vcd => "vcd",
+            OutputFormat::Fst => "fst",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Model => "model.json",
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Args, Debug)]
+pub struct ConvertArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Output file, - for stdout. Defaults to the input file with the format's extension"
+    )]
+    output: Option<PathBuf>,
+    #[arg(
+        short,
+        long,
+        help = "Output formats. vcd, fst and model contain the whole dump, ignoring the options below",
+        value_delimiter = ',',
+        default_value = "json"
+    )]
+    format: Vec<OutputFormat>,
+    #[arg(long, help = "Compress vcd output [gzip, zstd, xz]")]
+    compress: Option<Compression>,
+    #[arg(short, long, help = "Load conversion options from a TOML file")]
+    config: Option<PathBuf>,
+    #[arg(short, long, help = "Glob patterns selecting the signals to include")]
+    signals: Vec<String>,
+    #[arg(
+        long,
+        help = "Add a signal computed from others, e.g. 'fire = valid && ready'",
+        value_name = "NAME=EXPR"
+    )]
+    derive: Vec<DerivedSignal>,
+    #[arg(
+        short,
+        long,
+        help = "Expand busses into individual signals",
+        default_value = "false"
+    )]
+    expand_busses: bool,
+    #[arg(long, help = "Glob patterns selecting individual busses to expand")]
+    expand: Vec<String>,
+    #[arg(short, long, help = "Radix of bus data labels [bin, oct, dec, hex]")]
+    radix: Option<Radix>,
+    #[arg(long, help = "First simulation time to include")]
+    start: Option<usize>,
+    #[arg(long, help = "Simulation time at which output stops")]
+    end: Option<usize>,
+    #[arg(short, long, help = "Sample signals every <PERIOD> time units")]
+    period: Option<usize>,
+    #[arg(long, help = "Layout of csv and tsv tables [events, sampled]")]
+    table: Option<Layout>,
+    #[arg(
+        long,
+        help = "Sample csv and tsv tables on the edges of this clock signal"
+    )]
+    clock: Option<String>,
+    #[arg(long, help = "Clock edges to sample on [rising, falling, both]")]
+    edge: Option<Edge>,
+    #[arg(short, long, help = "Group signals [none, scope]")]
+    group_by: Option<Grouping>,
+    #[arg(short, long, help = "Only include the first of aliased signals")]
+    dedup_aliases: bool,
+    #[arg(short, long, help = "Signal naming style [full, short]")]
+    naming: Option<NamingStyle>,
+}
+
+impl ConvertArgs {
+    /// Options from the config file, if any, overridden by command line flags.
+    fn wavejson_options(&self) -> Result<WaveJsonOptions> {
+        let mut options = match &self.config {
+            Some(path) => toml::from_str(&read_input(path)?)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+            None => WaveJsonOptions::default(),
+        };
+        if !self.signals.is_empty() {
+            options.signals = self.signals.clone();
+        }
+        if !self.expand.is_empty() {
+            options.expand = self.expand.clone();
+        }
+        options.expand_busses |= self.expand_busses;
+        options.dedup_aliases |= self.dedup_aliases;
+        options.radix = self.radix.or(options.radix);
+        options.start = self.start.or(options.start);
+        options.end = self.end.or(options.end);
+        options.period = self.period.or(options.period);
+        options.group_by = self.group_by.unwrap_or(options.group_by);
+        options.naming = self.naming.unwrap_or(options.naming);
+        Ok(options)
+    }
+
+    /// Table options sharing the signal selection, window and radix of the WaveJson options.
+    fn csv_options(&self, options: &WaveJsonOptions, delimiter: Delimiter) -> CsvOptions {
+        CsvOptions {
+            signals: options.signals.clone(),
+            // Picking a clock implies sampling
+            layout: self.table.unwrap_or(if self.clock.is_some() {
+                Layout::Sampled
+            } else {
+                Layout::Events
+            }),
+            start: options.start,
+            end: options.end,
+            period: options.period,
+            clock: self.clock.clone(),
+            edge: self.edge.unwrap_or_default(),
+            radix: options.radix.unwrap_or_default(),
+            delimiter,
+        }
+    }
+
+    /// Signals to keep while parsing: those selected for output and the
+    /// clock. Formats holding the whole dump and derived signals, which may
+    /// refer to any signal, need all of them.
+    fn parse_options(&self, options: &WaveJsonOptions) -> ParseOptions {
+        let whole_dump = self.format.iter().any(|format| {
+            matches!(
+                format,
+                OutputFormat::Vcd | OutputFormat::Fst | OutputFormat::Model
+            )
+        });
+        if whole_dump || !self.derive.is_empty() || options.signals.is_empty() {
+            return ParseOptions::default();
+        }
+        ParseOptions {
+            signals: options.signals.iter().chain(&self.clock).cloned().collect(),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn run(args: ConvertArgs) -> Result<()> {
+    if args.output.is_some() && args.format.len() > 1 {
+        return Err("--output can only be used with a single --format".into());
+    }
+    let options = args.wavejson_options()?;
+    let mut vcd = read_vcd_with(&args.file, &args.parse_options(&options))?;
+    for derived in &args.derive {
+        vcd.add_derived(derived)?;
+    }
+
+    for format in &args.format {
+        let extension = match (format, args.compress) {
+            (OutputFormat::Vcd, Some(compression)) => format!("vcd.{}", compression.extension()),
+            _ => format.extension().to_owned(),
+        };
+        let path = output_path(&args.file, args.output.as_deref(), &extension);
+        let content = match format {
+            OutputFormat::Vcd => {
+                let mut output = vec![];
+                match args.compress {
+                    Some(compression) => vcd.write_vcd_compressed(&mut output, compression)?,
+                    None => vcd.write_vcd(&mut output)?,
+                }
+                output
+            }
+            OutputFormat::Fst => {
+                let mut fst = vec![];
+                write_fst(&vcd, &mut fst)?;
+                fst
+            }
+            OutputFormat::Model => (serde_json::to_string_pretty(&vcd)? + "\n").into_bytes(),
+            OutputFormat::Csv => {
+                CsvTable::from_vcd(&vcd, &args.csv_options(&options, Delimiter::Comma))?
+                    .to_csv()
+                    .into_bytes()
+            }
+            OutputFormat::Tsv => {
+                CsvTable::from_vcd(&vcd, &args.csv_options(&options, Delimiter::Tab))?
+                    .to_csv()
+                    .into_bytes()
+            }
+            #[cfg(feature = "parquet")]
+            OutputFormat::Parquet => {
+                write_parquet(&vcd, &options, &path)?;
+                continue;
+            }
+            OutputFormat::Json => WaveJson::from_vcd(&vcd, &options).to_json().into_bytes(),
+            OutputFormat::Svg => WaveSvg::from_vcd(&vcd, &options).to_svg().into_bytes(),
+        };
+        write_output(&path, content)?;
+    }
+    Ok(())
+}
+
+/// Write value changes to `path` and the signal dictionary next to it, e.g.
+/// `Counter.parquet` and `Counter.signals.parquet`.
+#[cfg(feature = "parquet")]
+fn write_parquet(
+    vcd: &vcd_oxide_parser::ValueChangeDump,
+    options: &WaveJsonOptions,
+    path: &std::path::Path,
+) -> Result<()> {
+    use crate::files::is_stdio;
+    use vcd_oxide_arrow::{ArrowDump, ArrowOptions};
+
+    if is_stdio(path) {
+        return Err("parquet output needs --output to be a file".into());
+    }
+    let arrow_options = ArrowOptions {
+        signals: options.signals.clone(),
+        ..Default::default()
+    };
+    let dump = ArrowDump::from_vcd(vcd, &arrow_options)?;
+    let (mut signals, mut value_changes) = (vec![], vec![]);
+    dump.write_parquet(&mut signals, &mut value_changes)?;
+    write_output(&path.with_extension("signals.parquet"), signals)?;
+    write_output(path, value_changes)
+}
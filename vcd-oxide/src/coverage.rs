@@ -0,0 +1,41 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use vcd_oxide_coverage::{CoverageOptions, ToggleCoverage};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum CoverageFormat {
+    Text,
+    Json,
+    Html,
+}
+
+#[derive(Args, Debug)]
+pub struct CoverageArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Output format", default_value = "text")]
+    format: CoverageFormat,
+    #[arg(short, long, help = "Glob patterns selecting the signals to measure")]
+    signals: Vec<String>,
+    #[arg(long, help = "Leave out parameters, which never toggle")]
+    exclude_parameters: bool,
+}
+
+pub fn run(args: CoverageArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let options = CoverageOptions {
+        signals: args.signals,
+        exclude_parameters: args.exclude_parameters,
+    };
+    let coverage = ToggleCoverage::from_vcd(&vcd, &options);
+
+    let content = match args.format {
+        CoverageFormat::Text => coverage.to_string(),
+        CoverageFormat::Json => serde_json::to_string_pretty(&coverage)? + "\n",
+        CoverageFormat::Html => coverage.to_html(),
+    };
+    write_output(&args.output, &content)
+}
@@ -0,0 +1,147 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::{Args, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use vcd_oxide_csv::Delimiter;
+use vcd_oxide_decode::{decode, to_csv, to_lanes, Decoder, I2c, Parity, Spi, Uart};
+use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DecodeFormat {
+    Json,
+    Csv,
+    Tsv,
+    /// The decoded signals with the annotations as data lanes
+    Wavejson,
+}
+
+#[derive(Args, Debug)]
+pub struct DecodeArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Output format", default_value = "json")]
+    format: DecodeFormat,
+    #[arg(
+        short,
+        long,
+        help = "Glob patterns selecting the signals drawn above the lanes. Defaults to the decoded ones"
+    )]
+    signals: Vec<String>,
+    #[arg(long, help = "First simulation time drawn in wavejson output")]
+    start: Option<usize>,
+    #[arg(long, help = "Simulation time at which wavejson output stops")]
+    end: Option<usize>,
+    #[arg(short, long, help = "Sample wavejson output every <PERIOD> time units")]
+    period: Option<usize>,
+    #[command(subcommand)]
+    protocol: Protocol,
+}
+
+#[derive(Subcommand, Debug)]
+enum Protocol {
+    /// Asynchronous serial line
+    Uart(UartArgs),
+    /// Serial peripheral interface
+    Spi(SpiArgs),
+    /// I2C bus with 7-bit addresses
+    I2c(I2cArgs),
+}
+
+#[derive(Args, Debug)]
+struct UartArgs {
+    #[arg(long, help = "Glob pattern of the serial line")]
+    line: String,
+    #[arg(long, help = "Time units per bit")]
+    bit_time: usize,
+    #[arg(long, help = "Data bits per frame", default_value = "8")]
+    data_bits: usize,
+    #[arg(long, help = "Parity bit [none, even, odd]", default_value = "none")]
+    parity: Parity,
+    #[arg(long, help = "Stop bits per frame", default_value = "1")]
+    stop_bits: usize,
+    #[arg(long, help = "The line idles low")]
+    inverted: bool,
+}
+
+#[derive(Args, Debug)]
+struct SpiArgs {
+    #[arg(long, help = "Glob pattern of the clock")]
+    sclk: String,
+    #[arg(long, help = "Glob pattern of the controller output")]
+    mosi: Option<String>,
+    #[arg(long, help = "Glob pattern of the peripheral output")]
+    miso: Option<String>,
+    #[arg(long, help = "Glob pattern of the active low chip select")]
+    cs: Option<String>,
+    #[arg(long, help = "SPI mode, 0 to 3", default_value = "0", value_parser = clap::value_parser!(u8).range(0..4))]
+    mode: u8,
+    #[arg(long, help = "Bits per word", default_value = "8")]
+    word_bits: usize,
+    #[arg(long, help = "Words are sent least significant bit first")]
+    lsb_first: bool,
+}
+
+#[derive(Args, Debug)]
+struct I2cArgs {
+    #[arg(long, help = "Glob pattern of the clock")]
+    scl: String,
+    #[arg(long, help = "Glob pattern of the data line")]
+    sda: String,
+}
+
+impl Protocol {
+    fn decoder(self) -> Box<dyn Decoder> {
+        match self {
+            Protocol::Uart(args) => Box::new(Uart {
+                line: args.line,
+                bit_time: args.bit_time,
+                data_bits: args.data_bits,
+                parity: args.parity,
+                stop_bits: args.stop_bits,
+                inverted: args.inverted,
+            }),
+            Protocol::Spi(args) => Box::new(Spi {
+                sclk: args.sclk,
+                mosi: args.mosi,
+                miso: args.miso,
+                cs: args.cs,
+                mode: args.mode,
+                word_bits: args.word_bits,
+                lsb_first: args.lsb_first,
+            }),
+            Protocol::I2c(args) => Box::new(I2c {
+                scl: args.scl,
+                sda: args.sda,
+            }),
+        }
+    }
+}
+
+pub fn run(args: DecodeArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let decoder = args.protocol.decoder();
+    let annotations = decode(&vcd, decoder.as_ref())?;
+
+    let content = match args.format {
+        DecodeFormat::Json => serde_json::to_string_pretty(&annotations)? + "\n",
+        DecodeFormat::Csv => to_csv(&annotations, Delimiter::Comma),
+        DecodeFormat::Tsv => to_csv(&annotations, Delimiter::Tab),
+        DecodeFormat::Wavejson => {
+            let signals = if args.signals.is_empty() {
+                decoder.signals().into_iter().map(str::to_owned).collect()
+            } else {
+                args.signals
+            };
+            let options = WaveJsonOptions {
+                signals,
+                start: args.start,
+                end: args.end,
+                period: args.period,
+                ..Default::default()
+            };
+            WaveJson::from_vcd_with_lanes(&vcd, &options, &to_lanes(&annotations)).to_json()
+        }
+    };
+    write_output(&args.output, content)
+}
@@ -0,0 +1,79 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::Args;
+use std::{path::PathBuf, process::ExitCode};
+use vcd_oxide_parser::{DiffOptions, DumpDiff};
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    #[arg(help = "Reference dump, e.g. a golden run")]
+    a: PathBuf,
+    #[arg(help = "Dump compared against the reference")]
+    b: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Glob patterns selecting the signals to compare")]
+    signals: Vec<String>,
+    #[arg(
+        long,
+        help = "Prefix removed from references in both dumps before matching"
+    )]
+    strip_prefix: Vec<String>,
+    #[arg(
+        long,
+        help = "Match signal A in the first dump with B in the second",
+        value_name = "A=B",
+        value_parser = parse_rename
+    )]
+    rename: Vec<(String, String)>,
+    #[arg(
+        short,
+        long,
+        help = "Ignore mismatches lasting up to <TOLERANCE> time units",
+        default_value = "0"
+    )]
+    tolerance: usize,
+    #[arg(short, long, help = "Treat x bits as matching any value")]
+    x_wildcard: bool,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Number of mismatches reported per signal",
+        default_value = "10"
+    )]
+    max_mismatches: usize,
+    #[arg(long, help = "Print the differences as JSON")]
+    json: bool,
+}
+
+fn parse_rename(s: &str) -> std::result::Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(a, b)| (a.to_owned(), b.to_owned()))
+        .ok_or_else(|| format!("expected A=B, got '{s}'"))
+}
+
+/// Print the differences, exiting with 1 when there are any so CI jobs stop.
+pub fn run(args: DiffArgs) -> Result<ExitCode> {
+    let a = read_vcd(&args.a)?;
+    let b = read_vcd(&args.b)?;
+    let options = DiffOptions {
+        signals: args.signals,
+        strip_prefixes: args.strip_prefix,
+        rename: args.rename.into_iter().collect(),
+        tolerance: args.tolerance,
+        x_wildcard: args.x_wildcard,
+        max_mismatches: args.max_mismatches,
+    };
+    let diff = DumpDiff::from_vcds(&a, &b, &options);
+
+    let content = if args.json {
+        serde_json::to_string_pretty(&diff)? + "\n"
+    } else {
+        diff.to_string()
+    };
+    write_output(&args.output, &content)?;
+    Ok(if diff.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,103 @@
+use std::{
+    fs,
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+use vcd_oxide_fst::{is_fst, read_fst};
+use vcd_oxide_parser::{Compression, ParseOptions, ValueChangeDump};
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Whether the path stands for stdin or stdout.
+pub fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+fn display(path: &Path) -> String {
+    if is_stdio(path) {
+        "<stdin>".to_owned()
+    } else {
+        path.display().to_string()
+    }
+}
+
+/// Read a file, or stdin when the path is `-`.
+pub fn read_bytes(path: &Path) -> Result<Vec<u8>> {
+    let mut content = vec![];
+    let result = if is_stdio(path) {
+        io::stdin().read_to_end(&mut content).map(|_| ())
+    } else {
+        fs::read(path).map(|c| content = c)
+    };
+    result.map_err(|e| format!("{}: {}", display(path), e))?;
+    Ok(content)
+}
+
+/// Read a text file, or stdin when the path is `-`.
+pub fn read_input(path: &Path) -> Result<String> {
+    let content = read_bytes(path)?;
+    String::from_utf8(content).map_err(|e| format!("{}: {}", display(path), e).into())
+}
+
+/// Read a dump in VCD, FST or JSON model format, detected from its content,
+/// and decompress it first if it is gzip, zstd or xz compressed.
+pub fn read_vcd(path: &Path) -> Result<ValueChangeDump> {
+    read_vcd_with(path, &ParseOptions::default())
+}
+
+/// Like [`read_vcd`], but VCD text only keeps the signals selected by
+/// `options` while parsing. FST and JSON model input is read whole.
+pub fn read_vcd_with(path: &Path, options: &ParseOptions) -> Result<ValueChangeDump> {
+    let mut content = read_bytes(path)?;
+    if let Some(compression) = Compression::detect(&content) {
+        content = compression
+            .decompress(&content)
+            .map_err(|e| format!("{}: {}", display(path), e))?;
+    }
+    let vcd = if is_fst(&content) {
+        read_fst(Cursor::new(content)).map_err(|e| format!("{}: {}", display(path), e))?
+    } else if content.trim_ascii_start().starts_with(b"{") {
+        serde_json::from_slice(&content).map_err(|e| format!("{}: {}", display(path), e))?
+    } else {
+        let content =
+            String::from_utf8(content).map_err(|e| format!("{}: {}", display(path), e))?;
+        ValueChangeDump::parse_with(&content, options)
+            .map_err(|e| format!("{}: {}", display(path), e))?
+    };
+    Ok(vcd)
+}
+
+/// Write to a file, or stdout when the path is `-`.
+pub fn write_output(path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+    if is_stdio(path) {
+        match io::stdout().lock().write_all(content.as_ref()) {
+            // The reader went away, e.g. `vcd-oxide show ... | head`
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            result => Ok(result?),
+        }
+    } else {
+        fs::write(path, content).map_err(|e| format!("{}: {}", path.display(), e).into())
+    }
+}
+
+/// Output path for a format: the explicit `--output` if given, stdout when
+/// reading from stdin and otherwise the input path with the format's extension.
+pub fn output_path(input: &Path, output: Option<&Path>, extension: &str) -> PathBuf {
+    match output {
+        Some(output) => output.to_owned(),
+        None if is_stdio(input) => PathBuf::from("-"),
+        // `Counter.vcd.gz` becomes `Counter.json` rather than `Counter.vcd.json`
+        None => {
+            let compressed = input
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.parse::<Compression>().is_ok());
+            let input = if compressed {
+                input.with_extension("")
+            } else {
+                input.to_owned()
+            };
+            input.with_extension(extension)
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::Args;
+use std::path::PathBuf;
+use vcd_oxide_parser::DumpInfo;
+
+#[derive(Args, Debug)]
+pub struct InfoArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(
+        short = 'n',
+        long,
+        help = "Number of most active signals to list",
+        default_value = "10"
+    )]
+    top: usize,
+    #[arg(long, help = "Print the summary as JSON")]
+    json: bool,
+}
+
+pub fn run(args: InfoArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let info = DumpInfo::from_vcd(&vcd, args.top);
+
+    let content = if args.json {
+        serde_json::to_string_pretty(&info)? + "\n"
+    } else {
+        info.to_string()
+    };
+    write_output(&args.output, &content)
+}
@@ -0,0 +1,34 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::Args;
+use std::path::PathBuf;
+use vcd_oxide_parser::{HierarchyOptions, HierarchyScope};
+
+#[derive(Args, Debug)]
+pub struct ListArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Glob patterns selecting the signals to list")]
+    signals: Vec<String>,
+    #[arg(short, long, help = "Number of scope levels to descend into")]
+    depth: Option<usize>,
+    #[arg(long, help = "Print the hierarchy as JSON")]
+    json: bool,
+}
+
+pub fn run(args: ListArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let options = HierarchyOptions {
+        signals: args.signals,
+        depth: args.depth,
+    };
+    let hierarchy = HierarchyScope::from_vcd(&vcd, &options);
+
+    let content = if args.json {
+        serde_json::to_string_pretty(&hierarchy)? + "\n"
+    } else {
+        hierarchy.to_string()
+    };
+    write_output(&args.output, &content)
+}
@@ -1,30 +1,87 @@
-use clap::Parser;
-use std::{fs, path::PathBuf};
-use vcd_oxide_parser::ValueChangeDump;
-use vcd_oxide_wavejson::WaveJson;
+mod check;
+mod convert;
+mod coverage;
+mod decode;
+mod diff;
+mod files;
+mod info;
+mod list;
+mod measure;
+mod merge;
+mod show;
+mod split;
+mod transactions;
+mod view;
+
+use clap::{Parser, Subcommand};
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
 // #[command(arg_required_else_help(true))]
-struct Args {
-    file: PathBuf,
-    #[arg(short, long, help = "Expand busses into individual signals", default_value = "false")]
-    expand_busses: bool,
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
 }
 
-fn main() -> Result<(), std::io::Error> {
-    let args = Args::parse();
-    let Args {
-        file,
-        expand_busses,
-    } = args;
-    let content = fs::read_to_string(&file).unwrap();
-    let vcd = ValueChangeDump::parse(&content);
-
-    let mut output_path = file;
-    output_path.set_extension("json");
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check temporal properties over a dump, exiting with 1 when any fails
+    Check(check::CheckArgs),
+    /// Convert a dump to WaveJson, SVG, VCD, FST or CSV
+    Convert(convert::ConvertArgs),
+    /// Report per-bit toggle coverage of the selected signals
+    Coverage(coverage::CoverageArgs),
+    /// Decode UART, SPI or I2C traffic into timestamped annotations
+    Decode(decode::DecodeArgs),
+    /// Compare the signals of two dumps, exiting with 1 when they differ and 2 on errors
+    Diff(diff::DiffArgs),
+    /// Summarise the header, hierarchy and activity of a dump
+    #[command(visible_alias = "stats")]
+    Info(info::InfoArgs),
+    /// Print the scopes and signals declared in a dump
+    List(list::ListArgs),
+    /// Measure the period, duty cycle, jitter and glitches of clocks and other single bit signals
+    Measure(measure::MeasureArgs),
+    /// Merge dumps into one, nesting them in scopes and converting to a common timescale
+    Merge(merge::MergeArgs),
+    /// Print signals as text waveforms
+    Show(show::ShowArgs),
+    /// Cut a dump into time slices or per-scope files while streaming it
+    Split(split::SplitArgs),
+    /// Extract AXI, AXI-Stream, APB or Wishbone transactions with latency statistics
+    Transactions(transactions::TransactionsArgs),
+    /// Browse signals in an interactive terminal viewer
+    View(view::ViewArgs),
+}
 
-    let wave = WaveJson::from_vcd(vcd, expand_busses);
-    let json = wave.to_json();
-    fs::write(output_path, json)
-}
\ No newline at end of file
+fn main() -> ExitCode {
+    let command = Cli::parse().command;
+    // Like diff(1), differences exit with 1 so errors need a code of their own
+    let error_code = match command {
+        Command::Diff(_) => ExitCode::from(2),
+        _ => ExitCode::FAILURE,
+    };
+    let result = match command {
+        Command::Check(args) => check::run(args),
+        Command::Convert(args) => convert::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Coverage(args) => coverage::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Decode(args) => decode::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Diff(args) => diff::run(args),
+        Command::Info(args) => info::run(args).map(|()| ExitCode::SUCCESS),
+        Command::List(args) => list::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Measure(args) => measure::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Merge(args) => merge::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Show(args) => show::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Split(args) => split::run(args).map(|()| ExitCode::SUCCESS),
+        Command::Transactions(args) => transactions::run(args).map(|()| ExitCode::SUCCESS),
+        Command::View(args) => view::run(args).map(|()| ExitCode::SUCCESS),
+    };
+    match result {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {e}");
+            error_code
+        }
+    }
+}
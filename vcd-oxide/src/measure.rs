@@ -0,0 +1,49 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::Args;
+use std::path::PathBuf;
+use vcd_oxide_parser::{MeasureOptions, TimingReport};
+
+#[derive(Args, Debug)]
+pub struct MeasureArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Glob patterns selecting the single bit signals to measure"
+    )]
+    signals: Vec<String>,
+    #[arg(
+        short,
+        long,
+        help = "Report pulses shorter than <GLITCH> time units as glitches",
+        default_value = "0"
+    )]
+    glitch: usize,
+    #[arg(long, help = "First simulation time to measure")]
+    start: Option<usize>,
+    #[arg(long, help = "Simulation time at which measuring stops")]
+    end: Option<usize>,
+    #[arg(long, help = "Print the measurements as JSON")]
+    json: bool,
+}
+
+pub fn run(args: MeasureArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let options = MeasureOptions {
+        signals: args.signals,
+        glitch_threshold: args.glitch,
+        start: args.start,
+        end: args.end,
+    };
+    let report = TimingReport::from_vcd(&vcd, &options);
+
+    let content = if args.json {
+        serde_json::to_string_pretty(&report)? + "\n"
+    } else {
+        report.to_string()
+    };
+    write_output(&args.output, &content)
+}
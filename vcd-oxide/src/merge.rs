@@ -0,0 +1,70 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use vcd_oxide_parser::{MergeOptions, ValueChangeDump};
+use vcd_oxide_wavejson::{WaveJson, WaveJsonOptions};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum MergeFormat {
+    Vcd,
+    Json,
+    Model,
+}
+
+#[derive(Args, Debug)]
+pub struct MergeArgs {
+    #[arg(
+        help = "Dumps to merge, one of them may be - for stdin",
+        required = true
+    )]
+    files: Vec<PathBuf>,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Output format", default_value = "vcd")]
+    format: MergeFormat,
+    #[arg(
+        short,
+        long,
+        help = "Scope to nest each dump in, in the order of the files. Empty keeps a dump at the top level"
+    )]
+    prefix: Vec<String>,
+    #[arg(
+        long,
+        help = "Time added to each dump in its own time units, in the order of the files"
+    )]
+    offset: Vec<usize>,
+}
+
+pub fn run(args: MergeArgs) -> Result<()> {
+    for (flag, count) in [
+        ("--prefix", args.prefix.len()),
+        ("--offset", args.offset.len()),
+    ] {
+        if count > args.files.len() {
+            return Err(format!(
+                "{} given {} times for {} files",
+                flag,
+                count,
+                args.files.len()
+            )
+            .into());
+        }
+    }
+    let dumps = args
+        .files
+        .iter()
+        .map(|path| read_vcd(path))
+        .collect::<Result<Vec<_>>>()?;
+    let options = MergeOptions {
+        prefixes: args.prefix,
+        offsets: args.offset,
+    };
+    let vcd = ValueChangeDump::merge(&dumps, &options)?;
+
+    let content = match args.format {
+        MergeFormat::Vcd => vcd.to_vcd(),
+        MergeFormat::Json => WaveJson::from_vcd(&vcd, &WaveJsonOptions::default()).to_json(),
+        MergeFormat::Model => serde_json::to_string_pretty(&vcd)? + "\n",
+    };
+    write_output(&args.output, content)
+}
@@ -0,0 +1,69 @@
+use crate::files::{read_vcd_with, write_output, Result};
+use clap::Args;
+use std::path::PathBuf;
+use vcd_oxide_parser::{DerivedSignal, ParseOptions, Radix};
+use vcd_oxide_text::{Charset, TextOptions, TextWave};
+
+#[derive(Args, Debug)]
+pub struct ShowArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Glob patterns selecting the signals to print")]
+    signals: Vec<String>,
+    #[arg(
+        long,
+        help = "Add a signal computed from others, e.g. 'fire = valid && ready'",
+        value_name = "NAME=EXPR"
+    )]
+    derive: Vec<DerivedSignal>,
+    #[arg(long, help = "First simulation time to print")]
+    start: Option<usize>,
+    #[arg(long, help = "Simulation time at which output stops")]
+    end: Option<usize>,
+    #[arg(
+        short,
+        long,
+        help = "Time units per column, fits the window into 80 columns by default"
+    )]
+    period: Option<usize>,
+    #[arg(short = 'w', long, help = "Characters per column", default_value = "2")]
+    column_width: usize,
+    #[arg(
+        short,
+        long,
+        help = "Radix of bus values [bin, oct, dec, hex]",
+        default_value = "hex"
+    )]
+    radix: Radix,
+    #[arg(long, help = "Only use ASCII characters")]
+    ascii: bool,
+}
+
+pub fn run(args: ShowArgs) -> Result<()> {
+    // Derived signals may refer to any signal, so only skip the others without them
+    let mut parse_options = ParseOptions::default();
+    if args.derive.is_empty() {
+        parse_options.signals = args.signals.clone();
+    }
+    let mut vcd = read_vcd_with(&args.file, &parse_options)?;
+    for derived in &args.derive {
+        vcd.add_derived(derived)?;
+    }
+
+    let options = TextOptions {
+        signals: args.signals,
+        start: args.start,
+        end: args.end,
+        period: args.period,
+        column_width: args.column_width,
+        radix: args.radix,
+        charset: if args.ascii {
+            Charset::Ascii
+        } else {
+            Charset::Unicode
+        },
+    };
+    write_output(&args.output, TextWave::from_vcd(&vcd, &options).to_text())
+}
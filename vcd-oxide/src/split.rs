@@ -0,0 +1,84 @@
+use crate::files::{is_stdio, output_path, write_output, Result};
+use clap::Args;
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Cursor},
+    path::PathBuf,
+};
+use vcd_oxide_fst::is_fst;
+use vcd_oxide_parser::Compression;
+use vcd_oxide_split::{split_vcd, SplitMode};
+
+#[derive(Args, Debug)]
+pub struct SplitArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Directory the pieces are written to",
+        default_value = "."
+    )]
+    output_dir: PathBuf,
+    #[arg(
+        long,
+        help = "Cut into slices of <EVERY> time units",
+        conflicts_with = "depth",
+        required_unless_present = "depth"
+    )]
+    every: Option<usize>,
+    #[arg(
+        long,
+        help = "Cut into one file per scope at this depth, 1 being the top level scopes"
+    )]
+    depth: Option<usize>,
+    #[arg(long, help = "Start of the file names, the input file name by default")]
+    name: Option<String>,
+}
+
+/// Split the dump while streaming it and print the path of each piece.
+/// Compressed dumps are decompressed in memory first.
+pub fn run(args: SplitArgs) -> Result<()> {
+    let mut reader: Box<dyn BufRead> = if is_stdio(&args.file) {
+        Box::new(io::stdin().lock())
+    } else {
+        let file = File::open(&args.file).map_err(|e| format!("{}: {}", args.file.display(), e))?;
+        Box::new(BufReader::new(file))
+    };
+    let start = reader.fill_buf()?;
+    if is_fst(start) {
+        return Err("split reads VCD, convert FST dumps with `convert -f vcd` first".into());
+    }
+    if let Some(compression) = Compression::detect(start) {
+        let mut content = vec![];
+        reader.read_to_end(&mut content)?;
+        reader = Box::new(Cursor::new(compression.decompress(&content)?));
+    }
+
+    let mode = match (args.every, args.depth) {
+        (Some(every), _) => SplitMode::Time(every),
+        (None, Some(depth)) => SplitMode::Scope(depth),
+        (None, None) => unreachable!("clap requires --every or --depth"),
+    };
+    let stem = match args.name {
+        Some(name) => name,
+        None => output_path(&args.file, None, "vcd")
+            .file_stem()
+            .filter(|_| !is_stdio(&args.file))
+            .map_or("dump".to_owned(), |stem| {
+                stem.to_string_lossy().into_owned()
+            }),
+    };
+    fs::create_dir_all(&args.output_dir)
+        .map_err(|e| format!("{}: {}", args.output_dir.display(), e))?;
+
+    let mut paths = String::new();
+    split_vcd(reader, mode, |piece| {
+        let path = args.output_dir.join(piece.file_name(&stem));
+        paths += &format!("{}\n", path.display());
+        File::create(&path)
+            .map(BufWriter::new)
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))
+    })?;
+    write_output(&PathBuf::from("-"), paths)
+}
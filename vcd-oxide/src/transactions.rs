@@ -0,0 +1,72 @@
+use crate::files::{read_vcd, write_output, Result};
+use clap::{Args, ValueEnum};
+use std::path::PathBuf;
+use vcd_oxide_csv::Delimiter;
+use vcd_oxide_transactions::{Protocol, TransactionOptions, TransactionReport};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TransactionFormat {
+    /// Statistics per transaction kind
+    Summary,
+    /// Every transaction followed by the statistics
+    Json,
+    Csv,
+    Tsv,
+}
+
+#[derive(Args, Debug)]
+pub struct TransactionsArgs {
+    #[arg(help = "Input file, - for stdin", default_value = "-")]
+    file: PathBuf,
+    #[arg(short, long, help = "Output file, - for stdout", default_value = "-")]
+    output: PathBuf,
+    #[arg(short, long, help = "Output format", default_value = "summary")]
+    format: TransactionFormat,
+    #[arg(
+        short,
+        long,
+        help = "Bus protocol [axi4, axi-stream, apb, wishbone]",
+        default_value = "axi4"
+    )]
+    protocol: Protocol,
+    #[arg(short, long, help = "Glob pattern of the bus clock")]
+    clock: String,
+    #[arg(
+        long,
+        help = "Prepended to each port name to find its signal, e.g. 'tb.dut.s_axi_'",
+        default_value = ""
+    )]
+    prefix: String,
+    #[arg(
+        long,
+        help = "Glob pattern of a port that doesn't follow the prefix",
+        value_name = "PORT=PATTERN",
+        value_parser = parse_port
+    )]
+    port: Vec<(String, String)>,
+}
+
+fn parse_port(s: &str) -> std::result::Result<(String, String), String> {
+    s.split_once('=')
+        .map(|(port, pattern)| (port.to_owned(), pattern.to_owned()))
+        .ok_or_else(|| format!("expected PORT=PATTERN, got '{s}'"))
+}
+
+pub fn run(args: TransactionsArgs) -> Result<()> {
+    let vcd = read_vcd(&args.file)?;
+    let options = TransactionOptions {
+        protocol: args.protocol,
+        clock: args.clock,
+        prefix: args.prefix,
+        ports: args.port,
+    };
+    let report = TransactionReport::from_vcd(&vcd, &options)?;
+
+    let content = match args.format {
+        TransactionFormat::Summary => report.to_string(),
+        TransactionFormat::Json => serde_json::to_string_pretty(&report)? + "\n",
+        TransactionFormat::Csv => report.to_csv(Delimiter::Comma),
+        TransactionFormat::Tsv => report.to_csv(Delimiter::Tab),
+    };
+    write_output(&args.output, &content)
+}
@@ -0,0 +1,674 @@
+use crate::files::{is_stdio, read_vcd, Result};
+use clap::Args;
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{collections::HashSet, path::PathBuf};
+use vcd_oxide_parser::{
+    Radix, ValueChangeDump, ValueChangeDumpScope, ValueChangeDumpSignal, ValueChangeDumpWave,
+};
+use vcd_oxide_text::{format_value, render_lane, TextOptions};
+
+const NAME_WIDTH: usize = 24;
+const VALUE_WIDTH: usize = 12;
+/// Columns between time labels on the ruler
+const RULER_SPACING: usize = 10;
+const HELP: &str = "j/k: select  a/Enter: add  d: remove  h/l: cursor  H/L: pan  +/-/f: zoom  \
+    e/E: edge  /,n/N: search  m: marker  r: radix";
+/// Smallest fraction of a time unit a column can be zoomed into
+const MIN_TIME_PER_COLUMN: f64 = 1.0 / 16.0;
+
+#[derive(Args, Debug)]
+pub struct ViewArgs {
+    #[arg(help = "Input file")]
+    file: PathBuf,
+    #[arg(
+        short,
+        long,
+        help = "Glob patterns selecting the signals shown initially"
+    )]
+    signals: Vec<String>,
+    #[arg(
+        short,
+        long,
+        help = "Radix of bus values [bin, oct, dec, hex]",
+        default_value = "hex"
+    )]
+    radix: Radix,
+}
+
+pub fn run(args: ViewArgs) -> Result<()> {
+    // The terminal takes the keyboard input, so the dump has to come from a file
+    if is_stdio(&args.file) {
+        return Err("view needs an input file, it can't read from stdin".into());
+    }
+    let vcd = read_vcd(&args.file)?;
+    let mut app = App::new(vcd, &args);
+
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    Ok(result?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Tree,
+    Waves,
+}
+
+/// A row of the scope tree pane.
+struct TreeNode {
+    depth: usize,
+    /// Hierarchical path used to collapse the scopes containing the node
+    path: String,
+    kind: TreeKind,
+}
+
+enum TreeKind {
+    Scope(String),
+    Signal(ValueChangeDumpSignal),
+}
+
+struct App {
+    vcd: ValueChangeDump,
+    tree: Vec<TreeNode>,
+    collapsed: HashSet<String>,
+    tree_state: ListState,
+    signals: Vec<ValueChangeDumpSignal>,
+    wave_selected: usize,
+    focus: Focus,
+    /// Time at the left edge of the wave pane
+    view_start: f64,
+    time_per_column: f64,
+    /// Number of wave columns drawn last frame
+    columns: usize,
+    cursor: usize,
+    marker: Option<usize>,
+    /// Search query being typed, if any
+    search: Option<String>,
+    last_search: Option<String>,
+    radix: Radix,
+    status: String,
+    quit: bool,
+}
+
+impl App {
+    fn new(vcd: ValueChangeDump, args: &ViewArgs) -> Self {
+        let mut tree = vec![];
+        build_tree(&vcd.root_scope.borrow(), "", 0, &mut tree);
+        let signals = if args.signals.is_empty() {
+            vec![]
+        } else {
+            vcd.signals()
+                .into_iter()
+                .filter(|sig| args.signals.iter().any(|pattern| sig.matches(pattern)))
+                .collect()
+        };
+        let focus = if signals.is_empty() {
+            Focus::Tree
+        } else {
+            Focus::Waves
+        };
+
+        App {
+            vcd,
+            tree,
+            collapsed: HashSet::new(),
+            tree_state: ListState::default().with_selected(Some(0)),
+            signals,
+            wave_selected: 0,
+            focus,
+            view_start: 0.0,
+            time_per_column: 1.0,
+            columns: 0,
+            cursor: 0,
+            marker: None,
+            search: None,
+            last_search: None,
+            radix: args.radix,
+            status: "Tab: switch pane  Enter: add/expand  /: search  ?: help  q: quit".to_owned(),
+            quit: false,
+        }
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        let mut fitted = false;
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            // The wave pane width is only known after the first frame
+            if !fitted {
+                self.zoom_fit();
+                fitted = true;
+                continue;
+            }
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn visible_tree(&self) -> Vec<&TreeNode> {
+        self.tree
+            .iter()
+            .filter(|node| {
+                !self
+                    .collapsed
+                    .iter()
+                    .any(|scope| node.path.starts_with(&format!("{scope}.")))
+            })
+            .collect()
+    }
+
+    fn selected_wave(&self) -> Option<(&ValueChangeDumpSignal, &ValueChangeDumpWave)> {
+        let sig = self.signals.get(self.wave_selected)?;
+        Some((sig, self.vcd.wave(&sig.identifier)))
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(query) = &mut self.search {
+            match key.code {
+                KeyCode::Char(c) => query.push(c),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Enter => {
+                    self.last_search = self.search.take();
+                    self.search_value(true);
+                }
+                KeyCode::Esc => self.search = None,
+                _ => {}
+            }
+            return;
+        }
+
+        let step = (self.time_per_column.ceil() as usize).max(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Tree => Focus::Waves,
+                    Focus::Waves => Focus::Tree,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Enter | KeyCode::Char(' ') | KeyCode::Char('a')
+                if self.focus == Focus::Tree =>
+            {
+                self.activate_tree_node()
+            }
+            KeyCode::Delete | KeyCode::Char('d')
+                if self.focus == Focus::Waves && self.wave_selected < self.signals.len() =>
+            {
+                self.signals.remove(self.wave_selected);
+                self.wave_selected = self.wave_selected.min(self.signals.len().saturating_sub(1));
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.set_cursor(self.cursor.saturating_sub(step)),
+            KeyCode::Right | KeyCode::Char('l') => self.set_cursor(self.cursor + step),
+            KeyCode::Char('H') => self.pan(-0.5),
+            KeyCode::Char('L') => self.pan(0.5),
+            KeyCode::Home => self.set_cursor(0),
+            KeyCode::End => self.set_cursor(self.vcd.end_time()),
+            KeyCode::Char('+') | KeyCode::Char('=') => self.zoom(0.5),
+            KeyCode::Char('-') => self.zoom(2.0),
+            KeyCode::Char('f') => self.zoom_fit(),
+            KeyCode::Char('e') => self.jump_edge(true),
+            KeyCode::Char('E') => self.jump_edge(false),
+            KeyCode::Char('/') => self.search = Some("".to_owned()),
+            KeyCode::Char('n') => self.search_value(true),
+            KeyCode::Char('N') => self.search_value(false),
+            KeyCode::Char('m') => {
+                self.marker = match self.marker {
+                    Some(marker) if marker == self.cursor => None,
+                    _ => Some(self.cursor),
+                }
+            }
+            KeyCode::Char('r') => {
+                self.radix = match self.radix {
+                    Radix::Binary => Radix::Octal,
+                    Radix::Octal => Radix::Decimal,
+                    Radix::Decimal => Radix::Hex,
+                    Radix::Hex => Radix::Binary,
+                };
+                self.status = format!("radix: {}", self.radix);
+            }
+            KeyCode::Char('?') => self.status = HELP.to_owned(),
+            _ => {}
+        }
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        match self.focus {
+            Focus::Tree => {
+                let len = self.visible_tree().len();
+                let selected = self.tree_state.selected().unwrap_or(0);
+                let selected = selected
+                    .saturating_add_signed(delta)
+                    .min(len.saturating_sub(1));
+                self.tree_state.select(Some(selected));
+            }
+            Focus::Waves => {
+                self.wave_selected = self
+                    .wave_selected
+                    .saturating_add_signed(delta)
+                    .min(self.signals.len().saturating_sub(1));
+            }
+        }
+    }
+
+    /// Toggle the selected scope or add the selected signal to the wave pane.
+    fn activate_tree_node(&mut self) {
+        let selected = self.tree_state.selected().unwrap_or(0);
+        let (path, signal) = match self.visible_tree().get(selected) {
+            Some(node) => match &node.kind {
+                TreeKind::Scope(_) => (Some(node.path.to_owned()), None),
+                TreeKind::Signal(sig) => (None, Some(sig.clone())),
+            },
+            None => return,
+        };
+        if let Some(path) = path {
+            if !self.collapsed.remove(&path) {
+                self.collapsed.insert(path);
+            }
+        }
+        if let Some(sig) = signal {
+            self.status = format!("added {}", sig.reference);
+            self.signals.push(sig);
+        }
+    }
+
+    /// Move the cursor, scrolling the view to keep it visible.
+    fn set_cursor(&mut self, time: usize) {
+        self.cursor = time.min(self.vcd.end_time());
+        let cursor = self.cursor as f64;
+        let span = self.columns as f64 * self.time_per_column;
+        if cursor < self.view_start {
+            self.view_start = cursor;
+        } else if cursor >= self.view_start + span {
+            self.view_start = cursor - span + self.time_per_column;
+        }
+    }
+
+    /// Scroll the view by a fraction of its width, dragging the cursor along if needed.
+    fn pan(&mut self, fraction: f64) {
+        let span = self.columns as f64 * self.time_per_column;
+        self.view_start = (self.view_start + span * fraction).max(0.0);
+        let cursor = (self.cursor as f64)
+            .max(self.view_start)
+            .min(self.view_start + span - self.time_per_column);
+        self.cursor = cursor.max(0.0) as usize;
+    }
+
+    /// Scale the time covered by each column, keeping the cursor at the same screen position.
+    fn zoom(&mut self, factor: f64) {
+        let offset = (self.cursor as f64 - self.view_start) / self.time_per_column;
+        self.time_per_column = (self.time_per_column * factor).max(MIN_TIME_PER_COLUMN);
+        self.view_start = (self.cursor as f64 - offset * self.time_per_column).max(0.0);
+    }
+
+    fn zoom_fit(&mut self) {
+        let columns = self.columns.max(1) as f64;
+        self.view_start = 0.0;
+        self.time_per_column =
+            ((self.vcd.end_time() + 1) as f64 / columns).max(MIN_TIME_PER_COLUMN);
+    }
+
+    fn jump_edge(&mut self, forward: bool) {
+        let Some((sig, wave)) = self.selected_wave() else {
+            return;
+        };
+        let edge = if forward {
+            next_edge(wave, self.cursor)
+        } else {
+            previous_edge(wave, self.cursor)
+        };
+        match edge {
+            Some(time) => self.set_cursor(time),
+            None => self.status = format!("no further edges on {}", sig.reference),
+        }
+    }
+
+    fn search_value(&mut self, forward: bool) {
+        let Some(query) = self.last_search.clone() else {
+            return;
+        };
+        let Some((sig, wave)) = self.selected_wave() else {
+            self.status = "select a signal to search".to_owned();
+            return;
+        };
+        let found = if forward {
+            wave.value_changes
+                .iter()
+                .filter(|vc| vc.time > self.cursor)
+                .find(|vc| matches_value(sig, &vc.value, &query))
+        } else {
+            wave.value_changes
+                .iter()
+                .rev()
+                .filter(|vc| vc.time < self.cursor)
+                .find(|vc| matches_value(sig, &vc.value, &query))
+        };
+        match found.map(|vc| vc.time) {
+            Some(time) => {
+                self.status = format!("{} = {} at {}", sig.reference, query, time);
+                self.set_cursor(time);
+            }
+            None => self.status = format!("{} not found on {}", query, sig.reference),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [tree, waves] =
+            Layout::horizontal([Constraint::Percentage(25), Constraint::Min(0)]).areas(main);
+        self.draw_tree(frame, tree);
+        self.draw_waves(frame, waves);
+
+        let status_line = match &self.search {
+            Some(query) => format!("/{query}"),
+            None => {
+                let mut line = format!(
+                    "cursor: {} ({})  zoom: {}/col",
+                    self.cursor, self.vcd.timescale, self.time_per_column
+                );
+                if let Some(marker) = self.marker {
+                    line += &format!(
+                        "  marker: {}  delta: {}",
+                        marker,
+                        self.cursor as i64 - marker as i64
+                    );
+                }
+                format!("{}  | {}", line, self.status)
+            }
+        };
+        frame.render_widget(
+            Paragraph::new(status_line).style(Style::default().add_modifier(Modifier::REVERSED)),
+            status,
+        );
+    }
+
+    fn pane_block(&self, title: &str, focus: Focus) -> Block<'static> {
+        let style = if self.focus == focus {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        Block::bordered()
+            .title(title.to_owned())
+            .border_style(style)
+    }
+
+    fn draw_tree(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .visible_tree()
+            .iter()
+            .map(|node| {
+                let indent = "  ".repeat(node.depth);
+                match &node.kind {
+                    TreeKind::Scope(name) => {
+                        let marker = if self.collapsed.contains(&node.path) {
+                            "▸"
+                        } else {
+                            "▾"
+                        };
+                        ListItem::new(format!("{indent}{marker} {name}"))
+                            .style(Style::default().add_modifier(Modifier::BOLD))
+                    }
+                    TreeKind::Signal(sig) => ListItem::new(format!(
+                        "{}  {} ({} {})",
+                        indent,
+                        sig.short_reference(),
+                        sig.kind,
+                        sig.size
+                    )),
+                }
+            })
+            .collect();
+        let list = List::new(items)
+            .block(self.pane_block("Scopes", Focus::Tree))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, area, &mut self.tree_state);
+    }
+
+    fn draw_waves(&mut self, frame: &mut Frame, area: Rect) {
+        let block = self.pane_block("Waves", Focus::Waves);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        self.columns = (inner.width as usize).saturating_sub(NAME_WIDTH + VALUE_WIDTH + 2);
+        let samples: Vec<usize> = (0..self.columns)
+            .map(|column| (self.view_start + column as f64 * self.time_per_column) as usize)
+            .collect();
+        let column_of = |time: usize| {
+            let column = (time as f64 - self.view_start) / self.time_per_column;
+            (column >= 0.0 && (column as usize) < self.columns).then_some(column as usize)
+        };
+        let cursor_column = column_of(self.cursor);
+        let marker_column = self.marker.and_then(column_of);
+
+        let mut lines = vec![Line::from(format!(
+            "{:width$}{}",
+            "",
+            ruler(&samples),
+            width = NAME_WIDTH + VALUE_WIDTH + 2
+        ))];
+        let options = TextOptions {
+            column_width: 1,
+            radix: self.radix,
+            ..Default::default()
+        };
+        for (index, sig) in self.signals.iter().enumerate() {
+            let wave = self.vcd.wave(&sig.identifier);
+            let value = wave.value_at(self.cursor).map_or("x".to_owned(), |vc| {
+                format_value(sig, &vc.value, self.radix)
+            });
+            let name_style = if index == self.wave_selected && self.focus == Focus::Waves {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+
+            let mut spans = vec![
+                Span::styled(fit(&sig.reference, NAME_WIDTH), name_style),
+                Span::raw(" "),
+                Span::styled(fit(&value, VALUE_WIDTH), Style::default().fg(Color::Yellow)),
+                Span::raw(" "),
+            ];
+            for (column, c) in render_lane(sig, wave, &samples, &options)
+                .chars()
+                .enumerate()
+            {
+                let style = if Some(column) == cursor_column {
+                    Style::default().bg(Color::White).fg(Color::Black)
+                } else if Some(column) == marker_column {
+                    Style::default().bg(Color::Yellow).fg(Color::Black)
+                } else {
+                    Style::default().fg(Color::Green)
+                };
+                spans.push(Span::styled(c.to_string(), style));
+            }
+            lines.push(Line::from(spans));
+        }
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+}
+
+fn build_tree(scope: &ValueChangeDumpScope, path: &str, depth: usize, tree: &mut Vec<TreeNode>) {
+    for child in &scope.scopes {
+        let child = child.borrow();
+        let child_path = if path.is_empty() {
+            child.name.to_owned()
+        } else {
+            format!("{}.{}", path, child.name)
+        };
+        tree.push(TreeNode {
+            depth,
+            path: child_path.to_owned(),
+            kind: TreeKind::Scope(child.name.to_owned()),
+        });
+        build_tree(&child, &child_path, depth + 1, tree);
+    }
+    for sig in &scope.signals {
+        tree.push(TreeNode {
+            depth,
+            path: sig.reference.to_owned(),
+            kind: TreeKind::Signal(sig.clone()),
+        });
+    }
+}
+
+/// Time of the first change after `time` that alters the value of the wave.
+fn next_edge(wave: &ValueChangeDumpWave, time: usize) -> Option<usize> {
+    let current = wave.value_at(time).map(|vc| &vc.value);
+    let mut time = time;
+    while let Some(change) = wave.next_change(time) {
+        time = change.time;
+        if wave.value_at(time).map(|vc| &vc.value) != current {
+            return Some(time);
+        }
+    }
+    None
+}
+
+/// Time of the last change before `time` that altered the value of the wave.
+fn previous_edge(wave: &ValueChangeDumpWave, time: usize) -> Option<usize> {
+    let mut time = time;
+    while let Some(change) = wave.previous_change(time) {
+        time = change.time;
+        let before = wave.previous_change(time).map(|vc| &vc.value);
+        if Some(&wave.value_at(time)?.value) != before {
+            return Some(time);
+        }
+    }
+    None
+}
+
+/// Whether a raw value matches a search query. Queries prefixed with
+/// `0x`, `0o` or `0b` are compared numerically in that radix, anything
+/// else is compared against the raw and decimal value.
+fn matches_value(sig: &ValueChangeDumpSignal, value: &str, query: &str) -> bool {
+    let query = query.trim().to_ascii_lowercase();
+    let radix = match query.get(..2) {
+        Some("0x") => Some(Radix::Hex),
+        Some("0o") => Some(Radix::Octal),
+        Some("0b") => Some(Radix::Binary),
+        _ => None,
+    };
+    match radix {
+        Some(radix) => {
+            let formatted = radix.format(value, sig.size);
+            formatted.trim_start_matches('0') == query[2..].trim_start_matches('0')
+        }
+        None => {
+            value.eq_ignore_ascii_case(&query) || Radix::Decimal.format(value, sig.size) == query
+        }
+    }
+}
+
+/// Time labels placed every few columns.
+fn ruler(samples: &[usize]) -> String {
+    let mut ruler = String::new();
+    for (column, time) in samples.iter().enumerate().step_by(RULER_SPACING) {
+        if ruler.len() <= column {
+            ruler += &" ".repeat(column - ruler.len());
+            ruler += &format!("|{time}");
+        }
+    }
+    ruler.truncate(samples.len());
+    ruler
+}
+
+/// Pad or truncate text to exactly `width` characters.
+fn fit(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    if len <= width {
+        format!("{text:<width$}")
+    } else {
+        let mut fitted: String = text.chars().skip(len + 1 - width).collect();
+        fitted.insert(0, '…');
+        fitted
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use vcd_oxide_parser::ValueChange;
+
+    fn wave(changes: &[(usize, &str)]) -> ValueChangeDumpWave {
+        ValueChangeDumpWave {
+            value_changes: changes
+                .iter()
+                .map(|(time, value)| ValueChange {
+                    time: *time,
+                    value: value.to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn signal(size: usize) -> ValueChangeDumpSignal {
+        ValueChangeDumpSignal {
+            kind: "wire".to_owned(),
+            identifier: "!".to_owned(),
+            reference: "tb.data".to_owned(),
+            size,
+        }
+    }
+
+    #[test]
+    fn test_edges_skip_repeated_values() {
+        let wave = wave(&[(0, "0"), (2, "1"), (4, "1"), (6, "0"), (9, "0")]);
+        assert_eq!(next_edge(&wave, 0), Some(2));
+        assert_eq!(next_edge(&wave, 2), Some(6));
+        assert_eq!(next_edge(&wave, 3), Some(6));
+        assert_eq!(next_edge(&wave, 6), None);
+        assert_eq!(previous_edge(&wave, 9), Some(6));
+        assert_eq!(previous_edge(&wave, 6), Some(2));
+        assert_eq!(previous_edge(&wave, 5), Some(2));
+        assert_eq!(previous_edge(&wave, 2), Some(0));
+        assert_eq!(previous_edge(&wave, 0), None);
+    }
+
+    #[test]
+    fn test_matches_value() {
+        let sig = signal(8);
+        assert!(matches_value(&sig, "b101010", "0x2a"));
+        assert!(matches_value(&sig, "b101010", "0X2A"));
+        assert!(matches_value(&sig, "b101010", "0b00101010"));
+        assert!(matches_value(&sig, "b101010", "0o52"));
+        assert!(matches_value(&sig, "b101010", "42"));
+        assert!(matches_value(&sig, "b101010", "b101010"));
+        assert!(!matches_value(&sig, "b101010", "0x2b"));
+        assert!(!matches_value(&sig, "b101010", "43"));
+        assert!(matches_value(&signal(1), "x", "X"));
+    }
+
+    #[test]
+    fn test_ruler() {
+        let samples: Vec<usize> = (0..25).map(|column| column * 5).collect();
+        assert_eq!(ruler(&samples), "|0        |50       |100");
+        // A label running into the next one's place hides it, and the last is cut off
+        let samples: Vec<usize> = (0..30).map(|column| 1_000_000_000_000 + column).collect();
+        assert_eq!(ruler(&samples), "|1000000000000      |100000000");
+        assert_eq!(ruler(&[]), "");
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("clk", 6), "clk   ");
+        assert_eq!(fit("tb.dut.clk", 10), "tb.dut.clk");
+        assert_eq!(fit("tb.dut.clk", 6), "…t.clk");
+        assert_eq!(fit("tb.dut.clk", 6).chars().count(), 6);
+    }
+}